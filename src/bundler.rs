@@ -0,0 +1,654 @@
+extern crate rustc_serialize;
+
+use std::collections::HashMap;
+use std::ffi::{ OsStr, OsString };
+use std::fs::{ self, File };
+use std::io::Read;
+use std::path::{ Path, PathBuf };
+
+use self::rustc_serialize::json::Json;
+
+use error::{ BundleError, BundleResult };
+use grammar::*;
+use parser;
+use transformer;
+use compressor::{ self, Define };
+use codegen;
+use visitor::{ Visitor, Visitable };
+
+/// Extensions tried, in order, when a specifier doesn't name a file.
+const EXTENSIONS: &[&str] = &[".js", ".json"];
+
+/// A single file of the bundle.
+#[derive(Debug)]
+pub struct Module {
+    /// Canonical path of the file
+    pub path: PathBuf,
+
+    pub program: Program,
+
+    /// Specifiers used by the module, along with the index of the module
+    /// in the graph each of them resolves to
+    pub dependencies: Vec<(String, usize)>,
+
+    /// Specifiers of modules that are only loaded by a dynamic `import()`
+    pub dynamic_imports: Vec<String>,
+}
+
+impl Module {
+    /// Returns the index of the module a `specifier` resolves to.
+    #[inline]
+    pub fn dependency(&self, specifier: &str) -> Option<usize> {
+        self.dependencies.iter()
+                         .find(|&(dependency, _)| dependency == specifier)
+                         .map(|&(_, index)| index)
+    }
+}
+
+/// All modules reachable from an entry file, the entry being the first one.
+#[derive(Debug)]
+pub struct ModuleGraph {
+    pub modules: Vec<Module>,
+
+    /// Globals replaced in the minified code of every module
+    pub defines: Vec<Define>,
+
+    /// Rename the variables declared in functions of every module
+    pub mangle: bool,
+}
+
+impl ModuleGraph {
+    /// Reads and parses the `entry` file, following its imports and
+    /// `require` calls until all dependencies are part of the graph.
+    pub fn build(entry: &Path) -> BundleResult<ModuleGraph> {
+        let entry = canonicalize(entry)?;
+
+        let mut modules: Vec<Module> = Vec::new();
+        let mut indexes = HashMap::new();
+        let mut queue = vec![entry.clone()];
+
+        indexes.insert(entry, 0);
+
+        // Modules are parsed in the order they were assigned an index
+        while modules.len() < queue.len() {
+            let path = queue[modules.len()].clone();
+            let program = read_module(&path)?;
+            let mut dependencies = Vec::new();
+            let mut specifiers = Specifiers::default();
+
+            program.body.visit(&mut specifiers);
+
+            if let Some(span) = specifiers.dynamic {
+                return Err(BundleError::Dynamic {
+                    path: path,
+                    start: span.start,
+                    end: span.end,
+                });
+            }
+
+            let dynamic_imports = specifiers.all.iter()
+                                                .filter(|specifier| !specifiers.evaluated.contains(specifier))
+                                                .cloned()
+                                                .collect();
+
+            for specifier in specifiers.all {
+                let resolved = match resolve(&specifier, &path) {
+                    Some(resolved) => canonicalize(&resolved)?,
+                    None => return Err(BundleError::Unresolved {
+                        specifier: specifier,
+                        path: path,
+                    }),
+                };
+
+                let index = *indexes.entry(resolved.clone()).or_insert_with(|| {
+                    queue.push(resolved);
+                    queue.len() - 1
+                });
+
+                dependencies.push((specifier, index));
+            }
+
+            modules.push(Module {
+                path: path,
+                program: program,
+                dependencies: dependencies,
+                dynamic_imports: dynamic_imports,
+            });
+        }
+
+        Ok(ModuleGraph {
+            modules: modules,
+            defines: Vec::new(),
+            mangle: false,
+        })
+    }
+
+    /// Returns a flag for every module of the graph, set if the module
+    /// is evaluated along with the module at `root`.
+    fn evaluated_with(&self, root: usize) -> Vec<bool> {
+        let mut evaluated = vec![false; self.modules.len()];
+        let mut stack = vec![root];
+
+        while let Some(index) = stack.pop() {
+            if evaluated[index] {
+                continue;
+            }
+
+            evaluated[index] = true;
+
+            let module = &self.modules[index];
+
+            for &(ref specifier, dependency) in &module.dependencies {
+                if !module.dynamic_imports.contains(specifier) {
+                    stack.push(dependency);
+                }
+            }
+        }
+
+        evaluated
+    }
+
+    /// Compiles all modules to ES5 CommonJS and wraps them in an entry
+    /// script with a module registry, which runs the entry module. Modules
+    /// only needed by dynamic imports are split into chunks: one for each
+    /// imported module, and common chunks for modules shared between them.
+    pub fn generate_code(self, minify: bool) -> BundleResult<Bundle> {
+        // Entry and every dynamically imported module start a chunk
+        let mut roots = vec![0];
+
+        for module in &self.modules {
+            for specifier in &module.dynamic_imports {
+                let index = module.dependency(specifier).expect("Dynamic imports are resolved");
+
+                if !roots.contains(&index) {
+                    roots.push(index);
+                }
+            }
+        }
+
+        let evaluated: Vec<Vec<bool>> = roots.iter().map(|&root| self.evaluated_with(root)).collect();
+
+        // Modules outside of the entry chunk are grouped by the set of
+        // roots that need them
+        let mut groups: Vec<(Vec<usize>, Vec<usize>)> = Vec::new();
+
+        for (index, &in_entry) in evaluated[0].iter().enumerate() {
+            if in_entry {
+                continue;
+            }
+
+            let key: Vec<usize> = (1 .. roots.len()).filter(|&root| evaluated[root][index]).collect();
+
+            match groups.iter_mut().find(|group| group.0 == key) {
+                Some(group) => group.1.push(index),
+                None        => groups.push((key, vec![index])),
+            }
+        }
+
+        let defines = self.defines;
+        let mangle = self.mangle;
+        let definitions = self.modules.into_iter()
+                                      .map(|module| definition(module, minify, mangle, &defines))
+                                      .collect::<BundleResult<Vec<String>>>()?;
+
+        let chunks: Vec<Chunk> = groups.iter().map(|(_, modules)| {
+            let mut code = String::from("badgerChunk({");
+
+            for (position, &index) in modules.iter().enumerate() {
+                if position != 0 {
+                    code.push(',');
+                }
+
+                code.push_str(&index.to_string());
+                code.push(':');
+                code.push_str(&definitions[index]);
+            }
+
+            code.push_str("});\n");
+
+            Chunk {
+                name: format!("chunk.{:016x}.js", fnv_hash(code.as_bytes())),
+                code: code,
+            }
+        }).collect();
+
+        let mut code = String::from(if minify { RUNTIME_MIN } else { RUNTIME });
+
+        for (index, definition) in definitions.iter().enumerate() {
+            if index != 0 {
+                code.push_str(if minify { "," } else { ",\n" });
+            }
+
+            // Modules from chunks leave holes in the array
+            if evaluated[0][index] {
+                code.push_str(definition);
+            }
+        }
+
+        code.push_str(if minify { "],{" } else { "\n], {" });
+
+        for (position, &root) in roots.iter().enumerate().skip(1) {
+            if position != 1 {
+                code.push_str(if minify { "," } else { ", " });
+            }
+
+            let names: Vec<String> = groups.iter()
+                                           .zip(chunks.iter())
+                                           .filter(|&(group, _)| group.0.contains(&position))
+                                           .map(|(_, chunk)| quote(&chunk.name))
+                                           .collect();
+
+            code.push_str(&root.to_string());
+            code.push_str(if minify { ":[" } else { ": [" });
+            code.push_str(&names.join(if minify { "," } else { ", " }));
+            code.push(']');
+        }
+
+        code.push_str(if minify { "});" } else { "});\n" });
+
+        Ok(Bundle {
+            code: code,
+            chunks: chunks,
+        })
+    }
+}
+
+/// Compiles a module to ES5 CommonJS, wrapped in a `[factory, map]` pair
+/// where the `map` translates specifiers to indexes of modules.
+fn definition(module: Module, minify: bool, mangle: bool, defines: &[Define]) -> BundleResult<String> {
+    let mut program = module.program;
+    let mut settings = transformer::Settings::target_es5();
+
+    settings.transform_commonjs = true;
+    settings.transform_chunk_imports = true;
+    settings.mangle = mangle;
+    if let Err(error) = transformer::transform(&mut program, settings) {
+        return Err(BundleError::Transform {
+            path: module.path,
+            error: error,
+        });
+    }
+
+    if minify {
+        compressor::compress(&mut program, defines);
+    }
+
+    let mut code = String::from(if minify { "[function(require,module,exports){" } else { "[function (require, module, exports) {\n" });
+
+    code.push_str(&codegen::generate_code(program, minify));
+    code.push_str(if minify { "},{" } else { "}, {" });
+
+    for (index, &(ref specifier, dependency)) in module.dependencies.iter().enumerate() {
+        if index != 0 {
+            code.push_str(if minify { "," } else { ", " });
+        }
+
+        code.push_str(&quote(specifier));
+        code.push_str(if minify { ":" } else { ": " });
+        code.push_str(&dependency.to_string());
+    }
+
+    code.push_str("}]");
+
+    Ok(code)
+}
+
+/// 64-bit FNV-1a hash, used to name chunks after their contents.
+#[inline]
+fn fnv_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Runtime of the bundle, it's called with an array of `[factory, map]`
+/// pairs, and a map of modules to the chunks that have to be loaded
+/// before they can be imported dynamically. Chunks add their modules
+/// to the array by calling the global `badgerChunk`.
+const RUNTIME: &str = "(function (modules, chunks) {
+    var cache = {};
+    var loading = {};
+    var base = typeof document !== 'undefined' && document.currentScript
+        ? document.currentScript.src.replace(/[^\\/]*$/, '')
+        : '';
+
+    this.badgerChunk = function (chunk) {
+        for (var id in chunk) modules[id] = chunk[id];
+    };
+
+    function loadChunk(name) {
+        if (!loading[name]) {
+            loading[name] = new Promise(function (resolve, reject) {
+                var script = document.createElement('script');
+
+                script.src = base + name;
+                script.onload = resolve;
+                script.onerror = reject;
+                document.head.appendChild(script);
+            });
+        }
+
+        return loading[name];
+    }
+
+    function load(id) {
+        if (cache[id]) return cache[id].exports;
+
+        var module = cache[id] = { exports: {} };
+        var definition = modules[id];
+
+        function require(specifier) {
+            return load(definition[1][specifier]);
+        }
+
+        require.import = function (specifier) {
+            var id = definition[1][specifier];
+
+            return Promise.all((chunks[id] || []).map(loadChunk)).then(function () {
+                return load(id);
+            });
+        };
+
+        definition[0].call(module.exports, require, module, module.exports);
+
+        return module.exports;
+    }
+
+    load(0);
+})([
+";
+
+const RUNTIME_MIN: &str = "(function(modules,chunks){var cache={};var loading={};\
+var base=typeof document!=='undefined'&&document.currentScript?document.currentScript.src.replace(/[^\\/]*$/,''):'';\
+this.badgerChunk=function(chunk){for(var id in chunk)modules[id]=chunk[id];};\
+function loadChunk(name){if(!loading[name]){loading[name]=new Promise(function(resolve,reject){\
+var script=document.createElement('script');script.src=base+name;script.onload=resolve;script.onerror=reject;\
+document.head.appendChild(script);});}return loading[name];}\
+function load(id){if(cache[id])return cache[id].exports;var module=cache[id]={exports:{}};var definition=modules[id];\
+function require(specifier){return load(definition[1][specifier]);}\
+require.import=function(specifier){var id=definition[1][specifier];\
+return Promise.all((chunks[id]||[]).map(loadChunk)).then(function(){return load(id);});};\
+definition[0].call(module.exports,require,module,module.exports);return module.exports;}load(0);})([";
+
+/// Script with modules loaded on demand by a dynamic `import()`.
+#[derive(Debug)]
+pub struct Chunk {
+    /// File name of the chunk, derived from a hash of its contents
+    pub name: String,
+
+    pub code: String,
+}
+
+/// Entry script produced by the bundler, along with the chunks it loads.
+#[derive(Debug)]
+pub struct Bundle {
+    pub code: String,
+    pub chunks: Vec<Chunk>,
+}
+
+/// Bundles the `entry` file with all of its dependencies into ES5 scripts.
+pub fn bundle(entry: &Path, minify: bool) -> BundleResult<Bundle> {
+    ModuleGraph::build(entry)?.generate_code(minify)
+}
+
+/// Resolves a module `specifier` used in the file at `from` following
+/// Node's rules: relative and absolute paths are tried as a file, then
+/// as a directory, other specifiers are looked up in `node_modules`
+/// directories of all ancestors.
+pub fn resolve(specifier: &str, from: &Path) -> Option<PathBuf> {
+    let base = from.parent().unwrap_or(from);
+
+    if specifier.starts_with("./") || specifier.starts_with("../") || specifier.starts_with('/')
+       || specifier == "." || specifier == ".." {
+        let path = base.join(specifier);
+
+        return load_as_file(&path).or_else(|| load_as_directory(&path));
+    }
+
+    for directory in base.ancestors() {
+        if directory.file_name() == Some(OsStr::new("node_modules")) {
+            continue;
+        }
+
+        let path = directory.join("node_modules").join(specifier);
+
+        if let Some(resolved) = load_as_file(&path).or_else(|| load_as_directory(&path)) {
+            return Some(resolved);
+        }
+    }
+
+    None
+}
+
+fn load_as_file(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+
+    EXTENSIONS.iter().map(|extension| {
+        let mut file = OsString::from(path.as_os_str());
+
+        file.push(extension);
+
+        PathBuf::from(file)
+    }).find(|file| file.is_file())
+}
+
+fn load_index(path: &Path) -> Option<PathBuf> {
+    EXTENSIONS.iter()
+              .map(|extension| path.join(format!("index{}", extension)))
+              .find(|file| file.is_file())
+}
+
+fn load_as_directory(path: &Path) -> Option<PathBuf> {
+    let main = read_package(path).and_then(|package| {
+        package.find("main").and_then(|main| main.as_string()).map(|main| path.join(main))
+    });
+
+    if let Some(main) = main {
+        if let Some(resolved) = load_as_file(&main).or_else(|| load_index(&main)) {
+            return Some(resolved);
+        }
+    }
+
+    load_index(path)
+}
+
+/// Reads the `package.json` file in the `directory`, if there is one.
+pub fn read_package(directory: &Path) -> Option<Json> {
+    let mut contents = String::new();
+
+    File::open(directory.join("package.json")).ok()?.read_to_string(&mut contents).ok()?;
+
+    Json::from_str(&contents).ok()
+}
+
+#[inline]
+fn canonicalize(path: &Path) -> BundleResult<PathBuf> {
+    fs::canonicalize(path).map_err(|error| BundleError::Io {
+        path: path.to_path_buf(),
+        error: error,
+    })
+}
+
+/// Reads and parses a module, JSON files become modules exporting
+/// their contents.
+fn read_module(path: &Path) -> BundleResult<Program> {
+    let mut source = String::new();
+
+    File::open(path).and_then(|mut file| file.read_to_string(&mut source)).map_err(|error| BundleError::Io {
+        path: path.to_path_buf(),
+        error: error,
+    })?;
+
+    if path.extension() == Some(OsStr::new("json")) {
+        source = format!("module.exports = {};", source.trim());
+    }
+
+    parser::parse(source).map_err(|error| BundleError::Parse {
+        path: path.to_path_buf(),
+        error: error,
+    })
+}
+
+/// Collects module sources used by `import` and `export` declarations,
+/// `require` calls and dynamic `import()` with a string literal.
+#[derive(Default)]
+struct Specifiers {
+    all: Vec<String>,
+
+    // Specifiers of modules evaluated along with the module
+    evaluated: Vec<String>,
+
+    // Location of the first `require` or `import()` of anything else
+    dynamic: Option<Span>,
+}
+
+impl Specifiers {
+    #[inline]
+    fn add(&mut self, source: OwnedSlice, dynamic: bool) {
+        let specifier = unquote(&source).to_string();
+
+        if !dynamic && !self.evaluated.contains(&specifier) {
+            self.evaluated.push(specifier.clone());
+        }
+
+        if !self.all.contains(&specifier) {
+            self.all.push(specifier);
+        }
+    }
+}
+
+impl Visitor for Specifiers {
+    fn statement(&mut self, statement: &Statement) {
+        match *statement {
+            Statement::ImportDeclaration {
+                source,
+                ..
+            } |
+            Statement::ExportDeclaration {
+                kind: ExportKind::Named {
+                    source: Some(source),
+                    ..
+                }
+            } |
+            Statement::ExportDeclaration {
+                kind: ExportKind::All {
+                    source,
+                }
+            } => self.add(source, false),
+
+            _ => {},
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        if let Some(source) = required_source(expression) {
+            self.add(source, false);
+        }
+
+        if let Some(source) = imported_source(expression) {
+            self.add(source, true);
+        }
+
+        if self.dynamic.is_none() {
+            self.dynamic = dynamic_source(expression);
+        }
+    }
+}
+
+/// Returns the quoted module source if the `expression` is a `require`
+/// call with a string literal.
+pub fn required_source(expression: &Expression) -> Option<OwnedSlice> {
+    if let Expression::Call {
+        ref callee,
+        ref arguments,
+    } = *expression {
+        if let (&Expression::Identifier(name), 1) = (&callee.item, arguments.len()) {
+            if let Expression::Literal(LiteralString(source)) = arguments[0].item {
+                if name.as_str() == "require" {
+                    return Some(source);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the quoted module source if the `expression` is a dynamic
+/// `import()` of a string literal.
+pub fn imported_source(expression: &Expression) -> Option<OwnedSlice> {
+    if let Expression::Import {
+        ref source,
+    } = *expression {
+        if let Expression::Literal(LiteralString(source)) = source.item {
+            return Some(source);
+        }
+    }
+
+    None
+}
+
+/// Returns the location of the module source if the `expression` is
+/// a `require` call or a dynamic `import()` of anything other than
+/// a string literal.
+fn dynamic_source(expression: &Expression) -> Option<Span> {
+    let source = match *expression {
+        Expression::Call {
+            ref callee,
+            ref arguments,
+        } => match (&callee.item, arguments.len()) {
+            (&Expression::Identifier(name), 1) if name.as_str() == "require" => &arguments[0],
+            _ => return None,
+        },
+
+        Expression::Import {
+            ref source,
+        } => &**source,
+
+        _ => return None,
+    };
+
+    match source.item {
+        Expression::Literal(LiteralString(_)) => None,
+        _                                     => Some(source.span),
+    }
+}
+
+/// Strips the quotes from a module source string literal.
+#[inline]
+pub fn unquote(source: &str) -> &str {
+    &source[1 .. source.len() - 1]
+}
+
+/// Returns the unquoted specifiers of all modules used by the `program`.
+pub fn module_specifiers(program: &Program) -> Vec<String> {
+    let mut specifiers = Specifiers::default();
+
+    program.body.visit(&mut specifiers);
+
+    specifiers.all
+}
+
+/// Quotes a specifier as a JavaScript string literal.
+#[inline]
+fn quote(specifier: &str) -> String {
+    let mut quoted = String::with_capacity(specifier.len() + 2);
+
+    quoted.push('"');
+
+    for ch in specifier.chars() {
+        match ch {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(ch);
+            },
+            _ => quoted.push(ch),
+        }
+    }
+
+    quoted.push('"');
+
+    quoted
+}
@@ -3,7 +3,7 @@ extern crate itoa;
 use std::ptr;
 
 use grammar::*;
-use grammar::OperatorType::*;
+use sourcemap::{ self, Mappings, SourceMap };
 
 /// The `Generator` is a wrapper around an owned `String` that's used to
 /// stringify the AST. There is a bunch of useful methods here to manage
@@ -12,6 +12,11 @@ struct Generator {
     pub minify: bool,
     code: Vec<u8>,
     dent: u16,
+    mappings: Option<Mappings>,
+    line: usize,
+    line_column: usize,
+    line_cursor: usize,
+    no_in: bool,
 }
 
 impl Generator {
@@ -20,6 +25,43 @@ impl Generator {
             minify: minify,
             code: Vec::with_capacity(128),
             dent: 0,
+            mappings: None,
+            line: 0,
+            line_column: 0,
+            line_cursor: 0,
+            no_in: false,
+        }
+    }
+
+    pub fn with_mappings(minify: bool) -> Self {
+        let mut gen = Generator::new(minify);
+
+        gen.mappings = Some(Mappings::new());
+
+        gen
+    }
+
+    /// Records a mapping from the current position in the generated code
+    /// to the start of the `span` in the original source.
+    #[inline]
+    pub fn map_span(&mut self, span: &Span, name: Option<&str>) {
+        if self.mappings.is_none() || !span.is_located() {
+            return;
+        }
+
+        while self.line_cursor < self.code.len() {
+            if self.code[self.line_cursor] == b'\n' {
+                self.line += 1;
+                self.line_column = 0;
+            } else {
+                self.line_column += sourcemap::utf16_width(self.code[self.line_cursor]);
+            }
+
+            self.line_cursor += 1;
+        }
+
+        if let Some(ref mut mappings) = self.mappings {
+            mappings.add(self.line, self.line_column, span, name);
         }
     }
 
@@ -58,10 +100,10 @@ impl Generator {
     }
 
     #[inline]
-    pub fn write_list<T: Code>(&mut self, items: &Vec<T>) {
+    pub fn write_list<T: Code>(&mut self, items: &[T]) {
         let mut iter = items.iter();
 
-        for item in iter.next() {
+        if let Some(item) = iter.next() {
             self.write(item);
         }
 
@@ -72,7 +114,10 @@ impl Generator {
     }
 
     #[inline]
-    pub fn write_block<T: Code>(&mut self, items: &Vec<T>) {
+    pub fn write_block<T: Code>(&mut self, items: &[T]) {
+        let no_in = self.no_in;
+
+        self.no_in = false;
         self.indent();
         for item in items {
             self.new_line();
@@ -80,6 +125,50 @@ impl Generator {
         }
         self.dedent();
         self.new_line();
+        self.no_in = no_in;
+    }
+
+    /// Writes the body of a loop, an `if` or a labeled statement, which has
+    /// to be an empty statement `;` if there's nothing in it.
+    #[inline]
+    pub fn write_body(&mut self, body: &Loc<Statement>) {
+        if is_empty(&body.item) {
+            self.write_byte(b';');
+        } else {
+            self.write(body);
+        }
+    }
+
+    /// Writes the `operand`, wrapped in parens if it binds weaker than `bp`.
+    #[inline]
+    pub fn write_operand(&mut self, operand: &Loc<Expression>, bp: u8) {
+        if operand.binding_power() < bp {
+            self.write_parenthesized(operand);
+        } else {
+            self.write(operand);
+        }
+    }
+
+    /// Writes the `item` in parens, inside of which `in` is allowed again.
+    #[inline]
+    pub fn write_parenthesized<T: Code>(&mut self, item: &T) {
+        let no_in = self.no_in;
+
+        self.no_in = false;
+        self.write_byte(b'(');
+        self.write(item);
+        self.write_byte(b')');
+        self.no_in = no_in;
+    }
+
+    /// Writes an expression where a sequence doesn't need parens, since
+    /// no other operator can follow it.
+    #[inline]
+    pub fn write_expression(&mut self, expression: &Loc<Expression>) {
+        match expression.item {
+            Expression::Sequence(ref items) => self.write_list(items),
+            _                               => self.write(expression),
+        }
     }
 
     pub fn write_declaration_or_expression(&mut self, statement: &Statement) {
@@ -95,14 +184,28 @@ impl Generator {
 
             Statement::Expression {
                 ref value,
-            } => {
-                value.to_code(self);
-            },
+            } => self.write_expression(value),
 
             _ => panic!("Invalid AST structure!"),
         }
     }
 
+    pub fn write_template(&mut self, quasis: &[OwnedSlice], expressions: &[Loc<Expression>]) {
+        let mut expressions = expressions.iter();
+
+        self.write_byte(b'`');
+        for quasi in quasis {
+            self.write(quasi);
+
+            if let Some(expression) = expressions.next() {
+                self.write_bytes(b"${");
+                self.write_expression(expression);
+                self.write_byte(b'}');
+            }
+        }
+        self.write_byte(b'`');
+    }
+
     #[inline]
     pub fn indent(&mut self) {
         self.dent += 1;
@@ -123,6 +226,12 @@ impl Generator {
 /// to efficiently write characters and string slices to the code `Generator`.
 trait Code {
     fn to_code(&self, gen: &mut Generator);
+
+    /// Name of the original identifier to put in the source map, if any.
+    #[inline]
+    fn source_name(&self) -> Option<&str> {
+        None
+    }
 }
 
 impl Code for u64 {
@@ -139,6 +248,14 @@ impl<T: Code> Code for Box<T> {
     }
 }
 
+impl<T: Code> Code for Loc<T> {
+    #[inline]
+    fn to_code(&self, gen: &mut Generator) {
+        gen.map_span(&self.span, self.item.source_name());
+        gen.write(&self.item);
+    }
+}
+
 impl Code for OwnedSlice {
     #[inline]
     fn to_code(&self, gen: &mut Generator) {
@@ -149,9 +266,8 @@ impl Code for OwnedSlice {
 impl<T: Code> Code for Option<T> {
     #[inline]
     fn to_code(&self, gen: &mut Generator) {
-        match *self {
-            Some(ref value) => value.to_code(gen),
-            None            => {}
+        if let Some(ref value) = *self {
+            value.to_code(gen);
         }
     }
 }
@@ -159,57 +275,7 @@ impl<T: Code> Code for Option<T> {
 impl Code for OperatorType {
     #[inline]
     fn to_code(&self, gen: &mut Generator) {
-        gen.write_bytes(match *self {
-            FatArrow         => b"=>",
-            Accessor         => b".",
-            New              => b"new",
-            Increment        => b"++",
-            Decrement        => b"--",
-            LogicalNot       => b"!",
-            BitwiseNot       => b"~",
-            Typeof           => b"typeof",
-            Void             => b"void",
-            Delete           => b"delete",
-            Multiplication   => b"*",
-            Division         => b"/",
-            Remainder        => b"%",
-            Exponent         => b"**",
-            Addition         => b"+",
-            Substraction     => b"-",
-            BitShiftLeft     => b"<<",
-            BitShiftRight    => b">>",
-            UBitShiftRight   => b">>>",
-            Lesser           => b"<",
-            LesserEquals     => b"<=",
-            Greater          => b">",
-            GreaterEquals    => b">=",
-            Instanceof       => b"instanceof",
-            In               => b"in",
-            StrictEquality   => b"===",
-            StrictInequality => b"!==",
-            Equality         => b"==",
-            Inequality       => b"!=",
-            BitwiseAnd       => b"&",
-            BitwiseXor       => b"^",
-            BitwiseOr        => b"|",
-            LogicalAnd       => b"&&",
-            LogicalOr        => b"||",
-            Conditional      => b"?",
-            Assign           => b"=",
-            AddAssign        => b"+=",
-            SubstractAssign  => b"-=",
-            ExponentAssign   => b"**=",
-            MultiplyAssign   => b"*=",
-            DivideAssign     => b"/=",
-            RemainderAssign  => b"%=",
-            BSLAssign        => b"<<=",
-            BSRAssign        => b">>=",
-            UBSRAssign       => b">>>=",
-            BitAndAssign     => b"&=",
-            BitXorAssign     => b"^=",
-            BitOrAssign      => b"|=",
-            Spread           => b"...",
-        });
+        gen.write_bytes(self.as_str().as_bytes());
     }
 }
 
@@ -224,6 +290,15 @@ impl Code for LiteralValue {
             LiteralInteger(ref num)   => gen.write(num),
             LiteralFloat(ref num)     => gen.write(num),
             LiteralString(ref string) => gen.write(string),
+            LiteralRegEx {
+                ref pattern,
+                ref flags,
+            } => {
+                gen.write_byte(b'/');
+                gen.write(pattern);
+                gen.write_byte(b'/');
+                gen.write(flags);
+            },
         }
     }
 }
@@ -281,6 +356,31 @@ impl Code for ObjectMember {
                 gen.write_block(body);
                 gen.write_byte(b'}');
             },
+
+            ObjectMember::Getter {
+                ref name,
+                ref body,
+            } => {
+                gen.write_bytes(b"get ");
+                gen.write(name);
+                gen.write_min(b"() {", b"(){");
+                gen.write_block(body);
+                gen.write_byte(b'}');
+            },
+
+            ObjectMember::Setter {
+                ref name,
+                ref params,
+                ref body,
+            } => {
+                gen.write_bytes(b"set ");
+                gen.write(name);
+                gen.write_byte(b'(');
+                gen.write_list(params);
+                gen.write_min(b") {", b"){");
+                gen.write_block(body);
+                gen.write_byte(b'}');
+            },
         }
     }
 }
@@ -288,7 +388,78 @@ impl Code for ObjectMember {
 impl Code for Parameter {
     #[inline]
     fn to_code(&self, gen: &mut Generator) {
-        gen.write_bytes(self.name.as_bytes());
+        if self.rest {
+            gen.write_bytes(b"...");
+        }
+        gen.write(&self.name);
+        if let Some(ref default) = self.default {
+            gen.write_min(b" = ", b"=");
+            gen.write(default);
+        }
+    }
+}
+
+impl Code for PropertyPattern {
+    #[inline]
+    fn to_code(&self, gen: &mut Generator) {
+        // Write `{ key }` and `{ key = default }` in the shorthand form
+        let shorthand = match self.value {
+            Pattern::Identifier(name) => Some(name),
+            Pattern::Default {
+                ref pattern,
+                ..
+            } => match **pattern {
+                Pattern::Identifier(name) => Some(name),
+                _                         => None,
+            },
+            _ => None,
+        };
+
+        if shorthand != Some(self.key) {
+            gen.write(&self.key);
+            gen.write_min(b": ", b":");
+        }
+        gen.write(&self.value);
+    }
+}
+
+impl Code for Pattern {
+    fn to_code(&self, gen: &mut Generator) {
+        match *self {
+            Pattern::Identifier(ref name) => gen.write(name),
+
+            Pattern::Object(ref properties) => {
+                gen.write_min(b"{ ", b"{");
+                gen.write_list(properties);
+                gen.write_min(b" }", b"}");
+            },
+
+            Pattern::Array(ref elements) => {
+                gen.write_byte(b'[');
+                gen.write_list(elements);
+                // Trailing hole needs an extra comma to survive
+                if let Some(&None) = elements.last() {
+                    gen.write_byte(b',');
+                }
+                gen.write_byte(b']');
+            },
+
+            Pattern::Rest(ref pattern) => {
+                gen.write_bytes(b"...");
+                gen.write(pattern);
+            },
+
+            Pattern::Default {
+                ref pattern,
+                ref value,
+            } => {
+                gen.write(pattern);
+                gen.write_min(b" = ", b"=");
+                gen.write(value);
+            },
+
+            Pattern::Expression(ref expression) => gen.write(expression),
+        }
     }
 }
 
@@ -298,6 +469,8 @@ impl Code for Expression {
 
             Expression::This => gen.write_bytes(b"this"),
 
+            Expression::Super => gen.write_bytes(b"super"),
+
             Expression::Identifier(ref ident) => gen.write(ident),
 
             Expression::Literal(ref literal)  => gen.write(literal),
@@ -308,6 +481,8 @@ impl Code for Expression {
                 gen.write_byte(b']');
             },
 
+            Expression::Pattern(ref pattern) => gen.write(pattern),
+
             Expression::Sequence(ref items) => {
                 gen.write_byte(b'(');
                 gen.write_list(items);
@@ -320,7 +495,7 @@ impl Code for Expression {
 
                 let mut iter = members.iter();
 
-                for member in iter.next() {
+                if let Some(member) = iter.next() {
                     gen.new_line();
                     gen.write(member);
                 }
@@ -340,7 +515,12 @@ impl Code for Expression {
                 ref object,
                 ref property,
             } => {
-                gen.write(object);
+                write_object(gen, object);
+
+                // `1.a` would be read as a number
+                if is_integer(object) {
+                    gen.write_byte(b' ');
+                }
                 gen.write_byte(b'.');
                 gen.write(property);
             },
@@ -349,9 +529,9 @@ impl Code for Expression {
                 ref object,
                 ref property,
             } => {
-                gen.write(object);
+                write_object(gen, object);
                 gen.write_byte(b'[');
-                gen.write(property);
+                gen.write_expression(property);
                 gen.write_byte(b']');
             },
 
@@ -359,7 +539,7 @@ impl Code for Expression {
                 ref callee,
                 ref arguments,
             } => {
-                gen.write(callee);
+                gen.write_operand(callee, 17);
                 gen.write_byte(b'(');
                 gen.write_list(arguments);
                 gen.write_byte(b')');
@@ -370,17 +550,40 @@ impl Code for Expression {
                 ref operator,
                 ref right,
             } => {
-                if left.binding_power() < self.binding_power() {
-                    gen.write_byte(b'(');
-                    gen.write(left);
-                    gen.write_byte(b')');
+                // `in` would be read as a `for in` loop in the initializer
+                // of a `for` loop
+                if *operator == OperatorType::In && gen.no_in {
+                    gen.write_parenthesized(self);
+                    return;
+                }
+
+                let bp = operator.binding_power();
+
+                // The side an operator doesn't associate to needs parens
+                // around operands of the same precedence. This also covers
+                // `(-a) ** b`, since unary operators share it with `**`,
+                // while assignments can only have a member on the left
+                let (left_bp, right_bp) = if operator.assignment() {
+                    (17, bp)
+                } else if operator.right_associative() {
+                    (bp + 1, bp)
                 } else {
-                    gen.write(left);
+                    (bp, bp + 1)
+                };
+
+                gen.write_operand(left, left_bp);
+                match *operator {
+                    OperatorType::Instanceof |
+                    OperatorType::In         => gen.write_byte(b' '),
+                    _                        => gen.write_min(b" ", b""),
                 }
-                gen.write_min(b" ", b"");
                 gen.write(operator);
-                gen.write_min(b" ", b"");
-                gen.write(right);
+                if is_word(*operator) || needs_space(*operator, right) {
+                    gen.write_byte(b' ');
+                } else {
+                    gen.write_min(b" ", b"");
+                }
+                gen.write_operand(right, right_bp);
             },
 
             Expression::Prefix {
@@ -388,14 +591,39 @@ impl Code for Expression {
                 ref operand,
             } => {
                 gen.write(operator);
-                gen.write(operand);
+                if is_word(*operator) || needs_space(*operator, operand) {
+                    gen.write_byte(b' ');
+                }
+                match *operator {
+                    // `new a()` would call `a` and construct its result
+                    OperatorType::New if has_call(operand) => {
+                        gen.write_byte(b'(');
+                        gen.write(operand);
+                        gen.write_byte(b')');
+                    },
+                    OperatorType::New    => gen.write_operand(operand, 17),
+                    OperatorType::Spread => gen.write_operand(operand, OperatorType::Assign.binding_power()),
+
+                    // `-a ** b` is a syntax error, rather than `-(a ** b)`
+                    _ => match operand.item {
+                        Expression::Binary {
+                            operator: OperatorType::Exponent,
+                            ..
+                        } => {
+                            gen.write_byte(b'(');
+                            gen.write(operand);
+                            gen.write_byte(b')');
+                        },
+                        _ => gen.write_operand(operand, 15),
+                    },
+                }
             },
 
             Expression::Postfix {
                 ref operator,
                 ref operand,
             } => {
-                gen.write(operand);
+                gen.write_operand(operand, 17);
                 gen.write(operator);
             },
 
@@ -404,7 +632,8 @@ impl Code for Expression {
                 ref consequent,
                 ref alternate,
             } => {
-                gen.write(test);
+                // Only the assignments and conditionals need parens
+                gen.write_operand(test, OperatorType::LogicalOr.binding_power());
                 gen.write_min(b" ? ", b"?");
                 gen.write(consequent);
                 gen.write_min(b" : ", b":");
@@ -415,7 +644,7 @@ impl Code for Expression {
                 ref params,
                 ref body,
             } => {
-                if params.len() == 1 {
+                if params.len() == 1 && params[0].is_simple() {
                     gen.write(&params[0]);
                 } else {
                     gen.write_byte(b'(');
@@ -423,7 +652,15 @@ impl Code for Expression {
                     gen.write_byte(b')');
                 }
                 gen.write_min(b" => ", b"=>");
-                match **body {
+                match body.item {
+                    // `() => {}` would be read as a block
+                    Statement::Expression {
+                        ref value,
+                    } if starts_with_brace(value) => {
+                        gen.write_byte(b'(');
+                        gen.write(value);
+                        gen.write_byte(b')');
+                    },
                     Statement::Expression {
                         ref value,
                     } => gen.write(value),
@@ -450,19 +687,45 @@ impl Code for Expression {
                 gen.write_byte(b'}');
             },
 
+            Expression::TemplateLiteral {
+                ref quasis,
+                ref expressions,
+            } => gen.write_template(quasis, expressions),
+
+            Expression::TaggedTemplate {
+                ref tag,
+                ref quasis,
+                ref expressions,
+            } => {
+                write_object(gen, tag);
+                gen.write_template(quasis, expressions);
+            },
+
+            Expression::Import {
+                ref source,
+            } => {
+                gen.write_bytes(b"import(");
+                gen.write(source);
+                gen.write_byte(b')');
+            },
+
             // _ => gen.write_byte('💀'),
         }
     }
+
+    #[inline]
+    fn source_name(&self) -> Option<&str> {
+        match *self {
+            Expression::Identifier(ref ident) => Some(ident.as_str()),
+            _                                 => None,
+        }
+    }
 }
 
 impl Code for VariableDeclarationKind {
     #[inline]
     fn to_code(&self, gen: &mut Generator) {
-        gen.write_bytes(match *self {
-            VariableDeclarationKind::Var   => b"var",
-            VariableDeclarationKind::Let   => b"let",
-            VariableDeclarationKind::Const => b"const",
-        })
+        gen.write_bytes(self.as_str().as_bytes())
     }
 }
 
@@ -510,11 +773,105 @@ impl Code for ClassMember {
                 gen.write_min(b" = ", b"=");
                 gen.write(value);
                 gen.write_byte(b';');
-            }
+            },
+
+            ClassMember::Getter {
+                is_static,
+                ref name,
+                ref body,
+            } => {
+                if is_static {
+                    gen.write_bytes(b"static ");
+                }
+                gen.write_bytes(b"get ");
+                gen.write(name);
+                gen.write_min(b"() {", b"(){");
+                gen.write_block(body);
+                gen.write_byte(b'}');
+            },
+
+            ClassMember::Setter {
+                is_static,
+                ref name,
+                ref params,
+                ref body,
+            } => {
+                if is_static {
+                    gen.write_bytes(b"static ");
+                }
+                gen.write_bytes(b"set ");
+                gen.write(name);
+                gen.write_byte(b'(');
+                gen.write_list(params);
+                gen.write_min(b") {", b"){");
+                gen.write_block(body);
+                gen.write_byte(b'}');
+            },
+        }
+    }
+}
+
+impl Code for ImportSpecifier {
+    #[inline]
+    fn to_code(&self, gen: &mut Generator) {
+        match *self {
+            ImportSpecifier::Default {
+                ref local,
+            } => gen.write(local),
+
+            ImportSpecifier::Namespace {
+                ref local,
+            } => {
+                gen.write_min(b"* as ", b"*as ");
+                gen.write(local);
+            },
+
+            ImportSpecifier::Named {
+                ref imported,
+                ref local,
+            } => {
+                gen.write(imported);
+                if imported != local {
+                    gen.write_bytes(b" as ");
+                    gen.write(local);
+                }
+            },
         }
     }
 }
 
+impl Code for ExportSpecifier {
+    #[inline]
+    fn to_code(&self, gen: &mut Generator) {
+        gen.write(&self.local);
+        if self.exported != self.local {
+            gen.write_bytes(b" as ");
+            gen.write(&self.exported);
+        }
+    }
+}
+
+impl Code for SwitchCase {
+    #[inline]
+    fn to_code(&self, gen: &mut Generator) {
+        match self.test {
+            Some(ref test) => {
+                gen.write_bytes(b"case ");
+                gen.write_expression(test);
+                gen.write_byte(b':');
+            },
+            None => gen.write_bytes(b"default:"),
+        }
+
+        gen.indent();
+        for statement in &self.consequent {
+            gen.new_line();
+            gen.write(statement);
+        }
+        gen.dedent();
+    }
+}
+
 impl Code for VariableDeclarator {
     #[inline]
     fn to_code(&self, gen: &mut Generator) {
@@ -526,6 +883,181 @@ impl Code for VariableDeclarator {
     }
 }
 
+/// Writes a function or class declaration, without the blank lines
+/// surrounding it.
+fn write_declaration(gen: &mut Generator, statement: &Statement) {
+    match *statement {
+        Statement::Function {
+            ref name,
+            ref params,
+            ref body,
+        } => {
+            gen.write_bytes(b"function ");
+            gen.write(name);
+            gen.write_byte(b'(');
+            gen.write_list(params);
+            gen.write_min(b") {", b"){");
+            gen.write_block(body);
+            gen.write_byte(b'}');
+        },
+
+        Statement::Class {
+            ref name,
+            ref extends,
+            ref body,
+        } => {
+            gen.write_bytes(b"class");
+            // Default export can be anonymous
+            if !name.as_str().is_empty() {
+                gen.write_byte(b' ');
+                gen.write(name);
+            }
+            if let Some(ref super_class) = *extends {
+                gen.write_bytes(b" extends ");
+                gen.write(super_class);
+            }
+            gen.write_min(b" {", b"{");
+            gen.write_block(body);
+            gen.write_byte(b'}');
+        },
+
+        _ => panic!("Invalid AST structure!"),
+    }
+}
+
+/// Writes the object of a member expression, or the tag of a template.
+fn write_object(gen: &mut Generator, object: &Loc<Expression>) {
+    match object.item {
+        // `new a.b` would construct `a.b`
+        Expression::Prefix {
+            operator: OperatorType::New,
+            ..
+        } => {
+            gen.write_byte(b'(');
+            gen.write(object);
+            gen.write_byte(b')');
+        },
+        _ => gen.write_operand(object, 17),
+    }
+}
+
+/// Finds the innermost expression the code of `expression` starts with.
+fn leftmost(expression: &Expression) -> &Expression {
+    match *expression {
+        Expression::Member {
+            ref object,
+            ..
+        } |
+        Expression::ComputedMember {
+            ref object,
+            ..
+        } => leftmost(object),
+
+        Expression::Call {
+            callee: ref inner,
+            ..
+        } |
+        Expression::TaggedTemplate {
+            tag: ref inner,
+            ..
+        } |
+        Expression::Binary {
+            left: ref inner,
+            ..
+        } |
+        Expression::Postfix {
+            operand: ref inner,
+            ..
+        } |
+        Expression::Conditional {
+            test: ref inner,
+            ..
+        } => leftmost(inner),
+
+        _ => expression,
+    }
+}
+
+/// Checks if the `statement` writes no code, like an empty `Transparent`.
+fn is_empty(statement: &Statement) -> bool {
+    match *statement {
+        Statement::Transparent {
+            ref body,
+        } => body.iter().all(|statement| is_empty(&statement.item)),
+        _ => false,
+    }
+}
+
+#[inline]
+fn starts_with_brace(expression: &Expression) -> bool {
+    matches!(*leftmost(expression),
+        Expression::Object(_)                   |
+        Expression::Pattern(Pattern::Object(_)))
+}
+
+/// Checks if the member chain of the operand of `new` contains a call.
+fn has_call(expression: &Expression) -> bool {
+    match *expression {
+        Expression::Call { .. }           |
+        Expression::TaggedTemplate { .. } |
+        Expression::Import { .. }         => true,
+
+        Expression::Member {
+            ref object,
+            ..
+        } |
+        Expression::ComputedMember {
+            ref object,
+            ..
+        } => has_call(object),
+
+        _ => false,
+    }
+}
+
+/// Checks if the expression is a number literal that a following `.`
+/// would become a part of.
+fn is_integer(expression: &Expression) -> bool {
+    match *expression {
+        Expression::Literal(LiteralInteger(_))       => true,
+        Expression::Literal(LiteralFloat(ref value)) => value.as_str().bytes().all(|byte| byte.is_ascii_digit()),
+        _                                            => false,
+    }
+}
+
+#[inline]
+fn is_word(operator: OperatorType) -> bool {
+    matches!(operator,
+        OperatorType::New        |
+        OperatorType::Typeof     |
+        OperatorType::Void       |
+        OperatorType::Delete     |
+        OperatorType::Instanceof |
+        OperatorType::In)
+}
+
+/// Checks if writing the `operand` right after the `operator` would merge
+/// their tokens, like `a - -b` turning into `a--b`, or `a / /b/` into
+/// a comment.
+fn needs_space(operator: OperatorType, operand: &Expression) -> bool {
+    let next = match *leftmost(operand) {
+        Expression::Prefix {
+            operator,
+            ..
+        } => operator,
+        Expression::Literal(LiteralRegEx { .. }) => return operator == OperatorType::Division,
+        _                                        => return false,
+    };
+
+    match operator {
+        OperatorType::Addition     |
+        OperatorType::Increment    => next == OperatorType::Addition || next == OperatorType::Increment,
+        OperatorType::Substraction |
+        OperatorType::Decrement    => next == OperatorType::Substraction || next == OperatorType::Decrement,
+        _                          => false,
+    }
+}
+
 impl Code for Statement {
     fn to_code(&self, gen: &mut Generator) {
         match *self {
@@ -535,7 +1067,7 @@ impl Code for Statement {
             } => {
                 gen.write(label);
                 gen.write_min(b": ", b":");
-                gen.write(body);
+                gen.write_body(body);
             },
 
             Statement::Block {
@@ -551,7 +1083,7 @@ impl Code for Statement {
             } => {
                 let mut iter = body.iter();
 
-                for statement in iter.next() {
+                if let Some(statement) = iter.next() {
                     gen.write(statement);
                 }
 
@@ -564,7 +1096,25 @@ impl Code for Statement {
             Statement::Expression {
                 ref value,
             } => {
-                gen.write(value);
+                // `{ a } = b;` would be read as a block and `function () {}();`
+                // as a declaration, parens are needed
+                let first = match value.item {
+                    Expression::Sequence(ref items) => items.first().unwrap_or(value),
+                    _                               => value,
+                };
+
+                let parens = match *leftmost(first) {
+                    Expression::Function { .. } => true,
+                    _                           => starts_with_brace(first),
+                };
+
+                if parens {
+                    gen.write_byte(b'(');
+                    gen.write_expression(value);
+                    gen.write_byte(b')');
+                } else {
+                    gen.write_expression(value);
+                }
                 gen.write_byte(b';');
             },
 
@@ -574,7 +1124,7 @@ impl Code for Statement {
                 gen.write_bytes(b"return");
                 if let Some(ref value) = *value {
                     gen.write_byte(b' ');
-                    gen.write(value);
+                    gen.write_expression(value);
                 }
                 gen.write_byte(b';');
             },
@@ -590,6 +1140,17 @@ impl Code for Statement {
                 gen.write_byte(b';');
             },
 
+            Statement::Continue {
+                ref label,
+            } => {
+                gen.write_bytes(b"continue");
+                if let Some(ref label) = *label {
+                    gen.write_byte(b' ');
+                    gen.write(label);
+                }
+                gen.write_byte(b';');
+            },
+
             Statement::VariableDeclaration {
                 ref kind,
                 ref declarators,
@@ -601,18 +1162,13 @@ impl Code for Statement {
             },
 
             Statement::Function {
-                ref name,
-                ref params,
-                ref body,
+                ..
+            } |
+            Statement::Class {
+                ..
             } => {
                 gen.new_line();
-                gen.write_bytes(b"function ");
-                gen.write(name);
-                gen.write_byte(b'(');
-                gen.write_list(params);
-                gen.write_min(b") {", b"){");
-                gen.write_block(body);
-                gen.write_byte(b'}');
+                write_declaration(gen, self);
                 gen.new_line();
             },
 
@@ -622,13 +1178,17 @@ impl Code for Statement {
                 ref alternate,
             } => {
                 gen.write_min(b"if (", b"if(");
-                gen.write(test);
+                gen.write_expression(test);
                 gen.write_min(b") ", b")");
-                gen.write(consequent);
+                gen.write_body(consequent);
 
                 if let Some(ref alternate) = *alternate {
-                    gen.write_bytes(b" else ");
-                    gen.write(alternate);
+                    gen.write_min(b" else", b"else");
+                    match alternate.item {
+                        Statement::Block { .. } => gen.write_min(b" ", b""),
+                        _                       => gen.write_byte(b' '),
+                    }
+                    gen.write_body(alternate);
                 };
             },
 
@@ -637,9 +1197,35 @@ impl Code for Statement {
                 ref body,
             } => {
                 gen.write_min(b"while (", b"while(");
-                gen.write(test);
+                gen.write_expression(test);
                 gen.write_min(b") ", b")");
-                gen.write(body);
+                gen.write_body(body);
+            },
+
+            Statement::DoWhile {
+                ref body,
+                ref test,
+            } => {
+                gen.write_bytes(b"do");
+                match body.item {
+                    Statement::Block { .. } => gen.write_min(b" ", b""),
+                    _                       => gen.write_byte(b' '),
+                }
+                gen.write_body(body);
+                gen.write_min(b" while (", b"while(");
+                gen.write_expression(test);
+                gen.write_bytes(b");");
+            },
+
+            Statement::Switch {
+                ref discriminant,
+                ref cases,
+            } => {
+                gen.write_min(b"switch (", b"switch(");
+                gen.write_expression(discriminant);
+                gen.write_min(b") {", b"){");
+                gen.write_block(cases);
+                gen.write_byte(b'}');
             },
 
             Statement::For {
@@ -650,14 +1236,20 @@ impl Code for Statement {
             } => {
                 gen.write_min(b"for (", b"for(");
                 if let Some(ref init) = *init {
+                    gen.no_in = true;
                     gen.write_declaration_or_expression(init);
+                    gen.no_in = false;
                 }
                 gen.write_min(b"; ", b";");
-                gen.write(test);
+                if let Some(ref test) = *test {
+                    gen.write_expression(test);
+                }
                 gen.write_min(b"; ", b";");
-                gen.write(update);
+                if let Some(ref update) = *update {
+                    gen.write_expression(update);
+                }
                 gen.write_min(b") ", b")");
-                gen.write(body);
+                gen.write_body(body);
             },
 
             Statement::ForIn {
@@ -668,9 +1260,9 @@ impl Code for Statement {
                 gen.write_min(b"for (", b"for(");
                 gen.write_declaration_or_expression(left);
                 gen.write_bytes(b" in ");
-                gen.write(right);
+                gen.write_expression(right);
                 gen.write_min(b") ", b")");
-                gen.write(body);
+                gen.write_body(body);
             },
 
             Statement::ForOf {
@@ -683,33 +1275,141 @@ impl Code for Statement {
                 gen.write_bytes(b" of ");
                 gen.write(right);
                 gen.write_min(b") ", b")");
-                gen.write(body);
-            },
-
-            Statement::Class {
-                ref name,
-                ref extends,
-                ref body,
-            } => {
-                gen.new_line();
-                gen.write_bytes(b"class ");
-                gen.write(name);
-                if let &Some(ref super_class) = extends {
-                    gen.write_bytes(b" extends ");
-                    gen.write(super_class);
-                }
-                gen.write_min(b" {", b"{");
-                gen.write_block(body);
-                gen.write_byte(b'}');
-                gen.new_line();
+                gen.write_body(body);
             },
 
             Statement::Throw {
                 ref value,
             } => {
                 gen.write_bytes(b"throw ");
-                gen.write(value);
+                gen.write_expression(value);
+                gen.write_byte(b';');
+            },
+
+            Statement::ImportDeclaration {
+                ref specifiers,
+                ref source,
+            } => {
+                gen.write_bytes(b"import ");
+
+                let (named, unnamed): (Vec<ImportSpecifier>, Vec<ImportSpecifier>) = specifiers
+                    .iter()
+                    .partition(|specifier| matches!(**specifier, ImportSpecifier::Named { .. }));
+
+                gen.write_list(&unnamed);
+
+                if !named.is_empty() {
+                    if !unnamed.is_empty() {
+                        gen.write_min(b", ", b",");
+                    }
+                    gen.write_min(b"{ ", b"{");
+                    gen.write_list(&named);
+                    gen.write_min(b" }", b"}");
+                }
+
+                if !specifiers.is_empty() {
+                    gen.write_bytes(b" from ");
+                }
+                gen.write(source);
                 gen.write_byte(b';');
+            },
+
+            Statement::ExportDeclaration {
+                ref kind,
+            } => {
+                let declaration = match *kind {
+                    ExportKind::Declaration(ref statement) |
+                    ExportKind::Default(ref statement) => match statement.item {
+                        Statement::Function { .. } |
+                        Statement::Class { .. }    => Some(statement),
+                        _                          => None,
+                    },
+                    _ => None,
+                };
+
+                // Functions and classes are separated by blank lines,
+                // which have to go before the `export` keyword.
+                if declaration.is_some() {
+                    gen.new_line();
+                }
+
+                gen.write_bytes(b"export ");
+
+                if let ExportKind::Default(_) = *kind {
+                    gen.write_bytes(b"default ");
+                }
+
+                if let Some(statement) = declaration {
+                    gen.map_span(&statement.span, None);
+                    write_declaration(gen, statement);
+                    gen.new_line();
+                    return;
+                }
+
+                match *kind {
+                    ExportKind::Default(ref statement) => match statement.item {
+                        // Sequences have to be wrapped here
+                        Statement::Expression {
+                            ref value,
+                        } => {
+                            gen.write(value);
+                            gen.write_byte(b';');
+                        },
+                        _ => gen.write(statement),
+                    },
+
+                    ExportKind::Declaration(ref statement) => gen.write(statement),
+
+                    ExportKind::Named {
+                        ref specifiers,
+                        ref source,
+                    } => {
+                        gen.write_min(b"{ ", b"{");
+                        gen.write_list(specifiers);
+                        gen.write_min(b" }", b"}");
+                        if let Some(ref source) = *source {
+                            gen.write_min(b" from ", b"from");
+                            gen.write(source);
+                        }
+                        gen.write_byte(b';');
+                    },
+
+                    ExportKind::All {
+                        ref source,
+                    } => {
+                        gen.write_min(b"* from ", b"*from");
+                        gen.write(source);
+                        gen.write_byte(b';');
+                    },
+                }
+            },
+
+            Statement::Try {
+                ref block,
+                ref handler,
+                ref finalizer,
+            } => {
+                gen.write_min(b"try {", b"try{");
+                gen.write_block(block);
+                gen.write_byte(b'}');
+
+                if let Some(ref handler) = *handler {
+                    gen.write_min(b" catch", b"catch");
+                    if let Some(ref param) = handler.param {
+                        gen.write_min(b" (", b"(");
+                        gen.write(param);
+                        gen.write_byte(b')');
+                    }
+                    gen.write_min(b" {", b"{");
+                    gen.write_block(&handler.body);
+                    gen.write_byte(b'}');
+                }
+
+                if let Some(ref finalizer) = *finalizer {
+                    gen.write_min(b" finally {", b"finally{");
+                    gen.write_block(finalizer);
+                    gen.write_byte(b'}');
+                }
             }
         }
     }
@@ -726,6 +1426,23 @@ pub fn generate_code(program: Program, minify: bool) -> String {
     gen.consume()
 }
 
+/// Same as `generate_code`, but also produces a `SourceMap` pointing back
+/// to the original source code of the `program`. The `source` is the name
+/// of the original file as it should appear in the map.
+pub fn generate_code_with_source_map(program: Program, minify: bool, source: &str) -> (String, SourceMap) {
+    let mut gen = Generator::with_mappings(minify);
+    let content = program.source().to_string();
+
+    for statement in program.body {
+        gen.write(&statement);
+        gen.new_line();
+    }
+
+    let map = gen.mappings.take().unwrap_or_default().into_source_map(source, &content);
+
+    (gen.consume(), map)
+}
+
 
 // From: https://github.com/dtolnay/fastwrite/blob/master/src/lib.rs#L68
 //
@@ -739,13 +1456,13 @@ fn extend_from_slice(dst: &mut Vec<u8>, src: &[u8]) {
     dst.reserve(src_len);
 
     unsafe {
-        // We would have failed if `reserve` overflowed
-        dst.set_len(dst_len + src_len);
-
         ptr::copy_nonoverlapping(
             src.as_ptr(),
-            dst.as_mut_ptr().offset(dst_len as isize),
+            dst.as_mut_ptr().add(dst_len),
             src_len);
+
+        // We would have failed if `reserve` overflowed
+        dst.set_len(dst_len + src_len);
     }
 }
 
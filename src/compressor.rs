@@ -0,0 +1,1830 @@
+use std::mem;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use error::{ DefineError, DefineResult };
+use grammar::*;
+use grammar::OperatorType::*;
+use parser;
+use scope::{ self, BindingKind, ScopeTree };
+
+/// State of the compressor, a pass over the transformed AST that makes
+/// the minified output smaller without changing what the program does.
+pub struct Compressor<'a> {
+    strings: &'a mut Strings,
+    defines: &'a [Define],
+
+    // Names declared anywhere in the program, which can't be globals
+    // replaced by a `Define`
+    declared: &'a HashSet<String>,
+
+    // Declarations of unused variables and functions, by the address of
+    // the declared name
+    unused: &'a HashSet<usize>,
+
+    // Number of unused declarations removed
+    removed: usize,
+
+    // Set while compressing the callee of a call, whose `this` would
+    // change if a conditional was folded to a member expression
+    callee: bool,
+}
+
+/// Global replaced with a literal before compressing, so that code which
+/// depends on it can be removed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Define {
+    path: Vec<String>,
+    value: Constant,
+}
+
+impl Define {
+    /// Reads a definition written as `NAME=value`, such as
+    /// `process.env.NODE_ENV="production"`.
+    pub fn parse(definition: &str) -> DefineResult<Define> {
+        let invalid_name = || DefineError::InvalidName {
+            definition: definition.to_string(),
+        };
+
+        let invalid_value = || DefineError::InvalidValue {
+            definition: definition.to_string(),
+        };
+
+        let position = definition.find('=').ok_or_else(invalid_name)?;
+        let path: Vec<String> = definition[.. position].split('.').map(String::from).collect();
+
+        if !path.iter().all(|name| is_identifier(name)) {
+            return Err(invalid_name());
+        }
+
+        let program = parser::parse(definition[position + 1 ..].to_string()).map_err(|_| invalid_value())?;
+
+        let value = match program.body.as_slice() {
+            [Loc { item: Statement::Expression { ref value }, .. }] => Constant::of(&value.item),
+            _                                                       => None,
+        };
+
+        Ok(Define {
+            path: path,
+            value: value.ok_or_else(invalid_value)?,
+        })
+    }
+
+    /// Checks if `expression` reads the defined global or property.
+    fn matches(&self, expression: &Expression) -> bool {
+        fn matches_path(expression: &Expression, path: &[String]) -> bool {
+            match *expression {
+                Expression::Identifier(ref name) => path.len() == 1 && name.as_str() == path[0],
+
+                Expression::Member {
+                    ref object,
+                    ref property,
+                } => match path.split_last() {
+                    Some((last, rest)) => property.as_str() == last && matches_path(&object.item, rest),
+                    None               => false,
+                },
+
+                _ => false,
+            }
+        }
+
+        matches_path(expression, &self.path)
+    }
+}
+
+impl<'a> Compressor<'a> {
+    #[inline]
+    fn new(strings: &'a mut Strings, defines: &'a [Define], declared: &'a HashSet<String>, unused: &'a HashSet<usize>) -> Self {
+        Compressor {
+            strings: strings,
+            defines: defines,
+            declared: declared,
+            unused: unused,
+            removed: 0,
+            callee: false,
+        }
+    }
+
+    /// Returns the literal replacing a global defined by a `Define`.
+    fn defined(&mut self, expression: &Expression) -> Option<Expression> {
+        let value = self.defines.iter()
+                                .find(|define| define.matches(expression) && !self.declared.contains(&define.path[0]))
+                                .map(|define| define.value.clone())?;
+
+        self.literal(value)
+    }
+
+    /// Compresses the children of an assignment target, which is never
+    /// replaced by a `Define`.
+    fn target(&mut self, target: &mut Loc<Expression>) {
+        match target.item {
+            Expression::Member {
+                ref mut object,
+                ..
+            } => object.compress(self),
+
+            Expression::ComputedMember {
+                ref mut object,
+                ref mut property,
+            } => {
+                object.compress(self);
+                property.compress(self);
+            },
+
+            Expression::Pattern(ref mut pattern) => pattern.compress(self),
+
+            _ => {},
+        }
+    }
+
+    /// Compresses a list of statements, removing the ones that never run
+    /// and declarations that are never used.
+    fn statements(&mut self, statements: &mut Vec<Loc<Statement>>) {
+        statements.compress(self);
+
+        // Blocks that don't declare anything of their own only group the
+        // statements they hold
+        let nested = mem::replace(statements, Vec::with_capacity(statements.len()));
+
+        for statement in nested {
+            match statement.item {
+                Statement::Block {
+                    ref body,
+                } if body.iter().any(|statement| is_declaration(&statement.item)) => statements.push(statement),
+
+                Statement::Block {
+                    body,
+                } |
+                Statement::Transparent {
+                    body,
+                } => statements.extend(body),
+
+                _ => statements.push(statement),
+            }
+        }
+
+        // Statements following a jump are unreachable, apart from their
+        // hoisted declarations
+        if let Some(index) = statements.iter().position(|statement| is_jump(&statement.item)) {
+            let unreachable = statements.split_off(index + 1);
+
+            statements.extend(hoisted(unreachable));
+        }
+
+        let unused = self.unused;
+        let mut removed = 0;
+
+        statements.retain_mut(|statement| match statement.item {
+            Statement::Function {
+                ref name,
+                ..
+            } => {
+                let used = !unused.contains(&address(name));
+
+                if !used {
+                    removed += 1;
+                }
+
+                used
+            },
+
+            Statement::VariableDeclaration {
+                ref mut declarators,
+                ..
+            } => {
+                declarators.retain(|declarator| {
+                    let used = match declarator.name {
+                        Pattern::Identifier(ref name) => {
+                            !unused.contains(&address(name)) || !declarator.value.as_ref().is_none_or(|value| value.is_pure())
+                        },
+                        _ => true,
+                    };
+
+                    if !used {
+                        removed += 1;
+                    }
+
+                    used
+                });
+
+                !declarators.is_empty()
+            },
+
+            _ => true,
+        });
+
+        self.removed += removed;
+
+        // Joining the directive prologue would turn it into an expression
+        let prologue = statements.iter().take_while(|statement| is_directive(&statement.item)).count();
+        let following = statements.split_off(prologue);
+
+        for mut statement in following {
+            let merged = statements.len() > prologue && statements
+                .last_mut()
+                .is_some_and(|previous| merge(&mut previous.item, &mut statement.item));
+
+            if !merged {
+                statements.push(statement);
+            }
+        }
+    }
+
+    /// Creates a literal with the value of `constant`. Numbers that can't
+    /// be written as a short decimal literal are left alone.
+    fn literal(&mut self, constant: Constant) -> Option<Expression> {
+        let value = match constant {
+            Constant::Undefined        => return Some(Expression::Literal(LiteralUndefined)),
+            Constant::Null             => return Some(Expression::Literal(LiteralNull)),
+            Constant::Boolean(true)    => return Some(Expression::Literal(LiteralTrue)),
+            Constant::Boolean(false)   => return Some(Expression::Literal(LiteralFalse)),
+            Constant::String(ref value) => {
+                return Some(Expression::Literal(LiteralString(self.strings.alloc(quote(value)))));
+            },
+            Constant::Number(value)    => value,
+        };
+
+        if !value.is_finite() {
+            return None;
+        }
+
+        // Display never uses the exponent notation, which the tokenizer
+        // can't read back
+        let digits = format!("{}", value.abs());
+
+        if digits.len() > 21 {
+            return None;
+        }
+
+        let literal = Expression::Literal(LiteralFloat(self.strings.alloc(digits)));
+
+        // `-0` keeps its sign
+        if value.is_sign_negative() {
+            Some(Expression::Prefix {
+                operator: Substraction,
+                operand: Box::new(literal.into()),
+            })
+        } else {
+            Some(literal)
+        }
+    }
+
+    /// Compresses an expression whose value is only ever converted to
+    /// a boolean, such as the test of an `if` statement.
+    fn condition(&mut self, test: &mut Loc<Expression>) {
+        test.compress(self);
+
+        loop {
+            test.item = match test.item {
+                Expression::Prefix {
+                    operator: LogicalNot,
+                    ref mut operand,
+                } => match operand.item {
+                    Expression::Prefix {
+                        operator: LogicalNot,
+                        ref mut operand,
+                    } => take(operand).item,
+                    _ => break,
+                },
+                _ => break,
+            };
+        }
+
+        let value = match test.item {
+            Expression::Literal(LiteralTrue) |
+            Expression::Literal(LiteralFalse) => return,
+            ref expression                    => Constant::of(expression),
+        };
+
+        if let Some(value) = value {
+            test.item = Expression::Literal(if value.is_truthy() { LiteralTrue } else { LiteralFalse });
+        }
+    }
+}
+
+/// Value of an expression known at compile time.
+#[derive(Debug, PartialEq, Clone)]
+enum Constant {
+    Undefined,
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+}
+
+impl Constant {
+    /// Reads the value of a literal, or a negative number. Literals that
+    /// could be read differently by an engine, like legacy octal numbers
+    /// or escapes for lone surrogates, have no known value.
+    fn of(expression: &Expression) -> Option<Constant> {
+        match *expression {
+            Expression::Literal(ref literal) => match *literal {
+                LiteralUndefined         => Some(Constant::Undefined),
+                LiteralNull              => Some(Constant::Null),
+                LiteralTrue              => Some(Constant::Boolean(true)),
+                LiteralFalse             => Some(Constant::Boolean(false)),
+                LiteralInteger(value)    => Some(Constant::Number(value as f64)),
+                LiteralFloat(ref value)  => number_literal(value.as_str()).map(Constant::Number),
+                LiteralString(ref value) => unquote(value.as_str()).map(Constant::String),
+                LiteralRegEx { .. }      => None,
+            },
+
+            Expression::Prefix {
+                operator: Substraction,
+                ref operand,
+            } => match Constant::of(&operand.item) {
+                Some(Constant::Number(value)) => Some(Constant::Number(-value)),
+                _                             => None,
+            },
+
+            _ => None,
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match *self {
+            Constant::Undefined         |
+            Constant::Null              => false,
+            Constant::Boolean(value)    => value,
+            Constant::Number(value)     => !(value == 0.0 || value.is_nan()),
+            Constant::String(ref value) => !value.is_empty(),
+        }
+    }
+
+    /// The `ToNumber` conversion, `None` for strings that might not
+    /// convert the same way.
+    fn to_number(&self) -> Option<f64> {
+        match *self {
+            Constant::Undefined         => Some(f64::NAN),
+            Constant::Null              => Some(0.0),
+            Constant::Boolean(value)    => Some(if value { 1.0 } else { 0.0 }),
+            Constant::Number(value)     => Some(value),
+            Constant::String(ref value) => string_to_number(value),
+        }
+    }
+
+    /// The `ToString` conversion.
+    fn to_js_string(&self) -> String {
+        match *self {
+            Constant::Undefined         => "undefined".to_string(),
+            Constant::Null              => "null".to_string(),
+            Constant::Boolean(value)    => value.to_string(),
+            Constant::Number(value)     => number_to_string(value),
+            Constant::String(ref value) => value.clone(),
+        }
+    }
+
+    fn type_of(&self) -> &'static str {
+        match *self {
+            Constant::Undefined  => "undefined",
+            Constant::Null       => "object",
+            Constant::Boolean(_) => "boolean",
+            Constant::Number(_)  => "number",
+            Constant::String(_)  => "string",
+        }
+    }
+
+    fn strict_equals(&self, other: &Constant) -> bool {
+        match (self, other) {
+            (&Constant::Number(left), &Constant::Number(right)) => left == right,
+            (left, right)                                       => left == right,
+        }
+    }
+
+    fn loose_equals(&self, other: &Constant) -> Option<bool> {
+        match (self, other) {
+            (&Constant::Undefined, &Constant::Undefined) |
+            (&Constant::Undefined, &Constant::Null)      |
+            (&Constant::Null, &Constant::Undefined)      |
+            (&Constant::Null, &Constant::Null)           => Some(true),
+
+            (&Constant::Undefined, _) |
+            (&Constant::Null, _)      |
+            (_, &Constant::Undefined) |
+            (_, &Constant::Null)      => Some(false),
+
+            (Constant::String(left), Constant::String(right)) => Some(left == right),
+
+            (left, right) => Some(left.to_number()? == right.to_number()?),
+        }
+    }
+
+    /// Orders two values like the relational operators do, strings by
+    /// their UTF-16 code units. The inner `None` stands for a comparison
+    /// with `NaN`, which is always false.
+    fn compare(&self, other: &Constant) -> Option<Option<Ordering>> {
+        match (self, other) {
+            (Constant::String(left), Constant::String(right)) => {
+                Some(Some(left.encode_utf16().cmp(right.encode_utf16())))
+            },
+
+            (left, right) => Some(left.to_number()?.partial_cmp(&right.to_number()?)),
+        }
+    }
+}
+
+/// Evaluates a binary operation on two known values.
+fn fold_binary(left: &Constant, operator: OperatorType, right: &Constant) -> Option<Constant> {
+    let value = match operator {
+        Addition => match (left, right) {
+            (&Constant::String(_), _) |
+            (_, &Constant::String(_)) => return Some(Constant::String(left.to_js_string() + &right.to_js_string())),
+            _                         => left.to_number()? + right.to_number()?,
+        },
+
+        Substraction   => left.to_number()? - right.to_number()?,
+        Multiplication => left.to_number()? * right.to_number()?,
+        Division       => left.to_number()? / right.to_number()?,
+        Remainder      => left.to_number()? % right.to_number()?,
+
+        BitwiseAnd     => (to_int32(left.to_number()?) & to_int32(right.to_number()?)) as f64,
+        BitwiseOr      => (to_int32(left.to_number()?) | to_int32(right.to_number()?)) as f64,
+        BitwiseXor     => (to_int32(left.to_number()?) ^ to_int32(right.to_number()?)) as f64,
+        BitShiftLeft   => to_int32(left.to_number()?).wrapping_shl(to_int32(right.to_number()?) as u32 & 31) as f64,
+        BitShiftRight  => to_int32(left.to_number()?).wrapping_shr(to_int32(right.to_number()?) as u32 & 31) as f64,
+        UBitShiftRight => (to_int32(left.to_number()?) as u32).wrapping_shr(to_int32(right.to_number()?) as u32 & 31) as f64,
+
+        StrictEquality   => return Some(Constant::Boolean(left.strict_equals(right))),
+        StrictInequality => return Some(Constant::Boolean(!left.strict_equals(right))),
+        Equality         => return left.loose_equals(right).map(Constant::Boolean),
+        Inequality       => return left.loose_equals(right).map(|equal| Constant::Boolean(!equal)),
+
+        Lesser        => return left.compare(right).map(|order| Constant::Boolean(order == Some(Ordering::Less))),
+        Greater       => return left.compare(right).map(|order| Constant::Boolean(order == Some(Ordering::Greater))),
+        LesserEquals  => return left.compare(right).map(|order| Constant::Boolean(order.is_some_and(|order| order != Ordering::Greater))),
+        GreaterEquals => return left.compare(right).map(|order| Constant::Boolean(order.is_some_and(|order| order != Ordering::Less))),
+
+        _ => return None,
+    };
+
+    Some(Constant::Number(value))
+}
+
+/// Checks if calling the expression passes `this` or makes a direct `eval`,
+/// so it can't be folded from a conditional into the callee of a call.
+fn is_reference(expression: &Expression) -> bool {
+    match *expression {
+        Expression::Member { .. }         |
+        Expression::ComputedMember { .. } => true,
+        Expression::Identifier(ref name)  => name.as_str() == "eval",
+        _                                 => false,
+    }
+}
+
+/// The `Compressible` trait provides an interface for instances of grammar
+/// to be compressed, children first.
+trait Compressible {
+    fn compress(&mut self, ctx: &mut Compressor);
+}
+
+impl<T: Compressible> Compressible for Vec<T> {
+    #[inline]
+    fn compress(&mut self, ctx: &mut Compressor) {
+        for item in self.iter_mut() {
+            item.compress(ctx);
+        }
+    }
+}
+
+impl<T: Compressible> Compressible for Option<T> {
+    #[inline]
+    fn compress(&mut self, ctx: &mut Compressor) {
+        if let Some(ref mut value) = *self {
+            value.compress(ctx);
+        }
+    }
+}
+
+impl<T: Compressible> Compressible for Box<T> {
+    #[inline]
+    fn compress(&mut self, ctx: &mut Compressor) {
+        self.as_mut().compress(ctx)
+    }
+}
+
+impl<T: Compressible> Compressible for Loc<T> {
+    #[inline]
+    fn compress(&mut self, ctx: &mut Compressor) {
+        self.item.compress(ctx)
+    }
+}
+
+impl Compressible for Parameter {
+    #[inline]
+    fn compress(&mut self, ctx: &mut Compressor) {
+        self.name.compress(ctx);
+        self.default.compress(ctx);
+    }
+}
+
+impl Compressible for PropertyPattern {
+    #[inline]
+    fn compress(&mut self, ctx: &mut Compressor) {
+        self.value.compress(ctx);
+    }
+}
+
+impl Compressible for Pattern {
+    fn compress(&mut self, ctx: &mut Compressor) {
+        match *self {
+            Pattern::Identifier(_) => {},
+
+            Pattern::Object(ref mut properties) => properties.compress(ctx),
+
+            Pattern::Array(ref mut elements) => elements.compress(ctx),
+
+            Pattern::Rest(ref mut pattern) => pattern.compress(ctx),
+
+            Pattern::Default {
+                ref mut pattern,
+                ref mut value,
+            } => {
+                pattern.compress(ctx);
+                value.compress(ctx);
+            },
+
+            Pattern::Expression(ref mut expression) => ctx.target(expression),
+        }
+    }
+}
+
+impl Compressible for VariableDeclarator {
+    #[inline]
+    fn compress(&mut self, ctx: &mut Compressor) {
+        self.name.compress(ctx);
+        self.value.compress(ctx);
+    }
+}
+
+impl Compressible for ObjectMember {
+    fn compress(&mut self, ctx: &mut Compressor) {
+        match *self {
+            ObjectMember::Shorthand { .. } => {},
+
+            ObjectMember::Literal {
+                ref mut value,
+                ..
+            } => value.compress(ctx),
+
+            ObjectMember::Computed {
+                ref mut key,
+                ref mut value,
+            } => {
+                key.compress(ctx);
+                value.compress(ctx);
+            },
+
+            ObjectMember::Method {
+                ref mut params,
+                ref mut body,
+                ..
+            } |
+            ObjectMember::Setter {
+                ref mut params,
+                ref mut body,
+                ..
+            } => {
+                params.compress(ctx);
+                ctx.statements(body);
+            },
+
+            ObjectMember::ComputedMethod {
+                ref mut name,
+                ref mut params,
+                ref mut body,
+            } => {
+                name.compress(ctx);
+                params.compress(ctx);
+                ctx.statements(body);
+            },
+
+            ObjectMember::Getter {
+                ref mut body,
+                ..
+            } => ctx.statements(body),
+        }
+    }
+}
+
+impl Compressible for ClassMember {
+    fn compress(&mut self, ctx: &mut Compressor) {
+        match *self {
+            ClassMember::Constructor {
+                ref mut params,
+                ref mut body,
+            } |
+            ClassMember::Method {
+                ref mut params,
+                ref mut body,
+                ..
+            } |
+            ClassMember::Setter {
+                ref mut params,
+                ref mut body,
+                ..
+            } => {
+                params.compress(ctx);
+                ctx.statements(body);
+            },
+
+            ClassMember::Property {
+                ref mut value,
+                ..
+            } => value.compress(ctx),
+
+            ClassMember::Getter {
+                ref mut body,
+                ..
+            } => ctx.statements(body),
+        }
+    }
+}
+
+impl Compressible for SwitchCase {
+    #[inline]
+    fn compress(&mut self, ctx: &mut Compressor) {
+        self.test.compress(ctx);
+        ctx.statements(&mut self.consequent);
+    }
+}
+
+impl Compressible for CatchClause {
+    #[inline]
+    fn compress(&mut self, ctx: &mut Compressor) {
+        self.param.compress(ctx);
+        ctx.statements(&mut self.body);
+    }
+}
+
+impl Compressible for Statement {
+    fn compress(&mut self, ctx: &mut Compressor) {
+        *self = match *self {
+            Statement::Block {
+                ref mut body,
+            } |
+            Statement::Transparent {
+                ref mut body,
+            } => {
+                ctx.statements(body);
+                return;
+            },
+
+            Statement::Labeled {
+                ref mut body,
+                ..
+            } => {
+                body.compress(ctx);
+                unwrap_block(body);
+                return;
+            },
+
+            Statement::VariableDeclaration {
+                ref mut declarators,
+                ..
+            } => {
+                declarators.compress(ctx);
+                return;
+            },
+
+            Statement::Expression {
+                ref mut value,
+            } |
+            Statement::Throw {
+                ref mut value,
+            } => {
+                value.compress(ctx);
+                return;
+            },
+
+            Statement::Return {
+                ref mut value,
+            } => {
+                value.compress(ctx);
+                return;
+            },
+
+            Statement::Break { .. }    |
+            Statement::Continue { .. } => return,
+
+            Statement::Function {
+                ref mut params,
+                ref mut body,
+                ..
+            } => {
+                params.compress(ctx);
+                ctx.statements(body);
+                return;
+            },
+
+            Statement::If {
+                ref mut test,
+                ref mut consequent,
+                ref mut alternate,
+            } => {
+                ctx.condition(test);
+                consequent.compress(ctx);
+                alternate.compress(ctx);
+
+                let value = match test.item {
+                    Expression::Literal(LiteralTrue)  => true,
+                    Expression::Literal(LiteralFalse) => false,
+                    _                                 => {
+                        unwrap_block(consequent);
+
+                        if let Some(ref mut alternate) = *alternate {
+                            unwrap_block(alternate);
+
+                            // The `else` would belong to the inner `if`
+                            if is_open_if(&consequent.item) {
+                                let inner = take_statement(consequent);
+
+                                consequent.item = Statement::Block {
+                                    body: vec![inner],
+                                };
+                            }
+                        }
+
+                        if let Some(value) = if_expression(test, consequent, alternate) {
+                            *self = Statement::Expression {
+                                value: value,
+                            };
+                        }
+
+                        return;
+                    },
+                };
+
+                let consequent = take_statement(consequent);
+                let alternate = alternate.take().map(|alternate| *alternate);
+
+                let (taken, skipped) = if value {
+                    (Some(consequent), alternate)
+                } else {
+                    (alternate, Some(consequent))
+                };
+
+                let mut body: Vec<Loc<Statement>> = taken.into_iter().collect();
+
+                body.extend(hoisted(skipped.into_iter().collect()));
+
+                statement_of(body)
+            },
+
+            Statement::While {
+                ref mut test,
+                ref mut body,
+            } => {
+                ctx.condition(test);
+                body.compress(ctx);
+
+                if test.item != Expression::Literal(LiteralFalse) {
+                    unwrap_block(body);
+                    return;
+                }
+
+                statement_of(hoisted(vec![take_statement(body)]))
+            },
+
+            Statement::DoWhile {
+                ref mut body,
+                ref mut test,
+            } => {
+                body.compress(ctx);
+                unwrap_block(body);
+                ctx.condition(test);
+                return;
+            },
+
+            Statement::Switch {
+                ref mut discriminant,
+                ref mut cases,
+            } => {
+                discriminant.compress(ctx);
+                cases.compress(ctx);
+                return;
+            },
+
+            Statement::For {
+                ref mut init,
+                ref mut test,
+                ref mut update,
+                ref mut body,
+            } => {
+                init.compress(ctx);
+
+                if let Some(ref mut test) = *test {
+                    ctx.condition(test);
+                }
+
+                update.compress(ctx);
+                body.compress(ctx);
+                unwrap_block(body);
+                return;
+            },
+
+            Statement::ForIn {
+                ref mut left,
+                ref mut right,
+                ref mut body,
+            } |
+            Statement::ForOf {
+                ref mut left,
+                ref mut right,
+                ref mut body,
+            } => {
+                left.compress(ctx);
+                right.compress(ctx);
+                body.compress(ctx);
+                unwrap_block(body);
+                return;
+            },
+
+            Statement::Class {
+                ref mut body,
+                ..
+            } => {
+                body.compress(ctx);
+                return;
+            },
+
+            Statement::Try {
+                ref mut block,
+                ref mut handler,
+                ref mut finalizer,
+            } => {
+                ctx.statements(block);
+                handler.compress(ctx);
+
+                if let Some(ref mut finalizer) = *finalizer {
+                    ctx.statements(finalizer);
+                }
+
+                return;
+            },
+
+            Statement::ImportDeclaration { .. } => return,
+
+            Statement::ExportDeclaration {
+                ref mut kind,
+            } => {
+                match *kind {
+                    ExportKind::Declaration(ref mut statement) |
+                    ExportKind::Default(ref mut statement)     => statement.compress(ctx),
+                    _                                          => {},
+                }
+
+                return;
+            },
+        }
+    }
+}
+
+impl Compressible for Expression {
+    fn compress(&mut self, ctx: &mut Compressor) {
+        let callee = mem::replace(&mut ctx.callee, false);
+
+        *self = match *self {
+            Expression::This              |
+            Expression::Super             |
+            Expression::Literal(_)        => return,
+
+            Expression::Identifier(_) => match ctx.defined(self) {
+                Some(value) => value,
+                None        => return,
+            },
+
+            Expression::Array(ref mut items)    |
+            Expression::Sequence(ref mut items) => {
+                items.compress(ctx);
+                return;
+            },
+
+            Expression::Object(ref mut members) => {
+                members.compress(ctx);
+                return;
+            },
+
+            Expression::Member {
+                ref mut object,
+                ..
+            } => {
+                object.compress(ctx);
+
+                match ctx.defined(self) {
+                    Some(value) => value,
+                    None        => return,
+                }
+            },
+
+            Expression::ComputedMember {
+                ref mut object,
+                ref mut property,
+            } => {
+                object.compress(ctx);
+                property.compress(ctx);
+                return;
+            },
+
+            Expression::Call {
+                callee: ref mut function,
+                ref mut arguments,
+            } => {
+                ctx.callee = true;
+                function.compress(ctx);
+                arguments.compress(ctx);
+                return;
+            },
+
+            Expression::TaggedTemplate {
+                ref mut tag,
+                ref mut expressions,
+                ..
+            } => {
+                ctx.callee = true;
+                tag.compress(ctx);
+                expressions.compress(ctx);
+                return;
+            },
+
+            Expression::Binary {
+                ref mut left,
+                operator: operator @ LogicalAnd,
+                ref mut right,
+            } |
+            Expression::Binary {
+                ref mut left,
+                operator: operator @ LogicalOr,
+                ref mut right,
+            } => {
+                left.compress(ctx);
+                ctx.callee = callee;
+                right.compress(ctx);
+
+                // A known left side either short circuits, or evaluates
+                // to the right side
+                let value = match Constant::of(&left.item) {
+                    Some(value) => value,
+                    None        => return,
+                };
+
+                let result = if value.is_truthy() == (operator == LogicalAnd) { right } else { left };
+
+                if callee && is_reference(&result.item) {
+                    return;
+                }
+
+                take(result).item
+            },
+
+            Expression::Binary {
+                ref mut left,
+                operator,
+                ref mut right,
+            } => {
+                if operator.assignment() {
+                    ctx.target(left);
+                } else {
+                    left.compress(ctx);
+                }
+
+                right.compress(ctx);
+
+                let right_value = match Constant::of(&right.item) {
+                    Some(value) => value,
+                    None        => return,
+                };
+
+                if let Some(left_value) = Constant::of(&left.item) {
+                    match fold_binary(&left_value, operator, &right_value).and_then(|value| ctx.literal(value)) {
+                        Some(folded) => folded,
+                        None         => return,
+                    }
+                } else {
+                    // `x + "a" + "b"` is a concatenation even if `x` isn't
+                    // a string, so it is the same as `x + "ab"`
+                    if operator != Addition {
+                        return;
+                    }
+
+                    let concatenated = match left.item {
+                        Expression::Binary {
+                            operator: Addition,
+                            ref mut right,
+                            ..
+                        } => match Constant::of(&right.item) {
+                            Some(Constant::String(value)) => {
+                                let folded = Constant::String(value + &right_value.to_js_string());
+
+                                right.item = ctx.literal(folded).expect("Strings always have a literal");
+                                true
+                            },
+                            _ => false,
+                        },
+                        _ => false,
+                    };
+
+                    if !concatenated {
+                        return;
+                    }
+
+                    take(left).item
+                }
+            },
+
+            Expression::Prefix {
+                operator: LogicalNot,
+                ref mut operand,
+            } => {
+                ctx.condition(operand);
+
+                match Constant::of(&operand.item) {
+                    Some(value) => Expression::Literal(if value.is_truthy() { LiteralFalse } else { LiteralTrue }),
+                    None        => return,
+                }
+            },
+
+            Expression::Prefix {
+                operator,
+                ref mut operand,
+            } => {
+                match operator {
+                    Increment |
+                    Decrement |
+                    Delete    => {
+                        ctx.target(operand);
+                        return;
+                    },
+                    _         => operand.compress(ctx),
+                }
+
+                let function = matches!(operand.item,
+                    Expression::Function { .. }      |
+                    Expression::ArrowFunction { .. });
+
+                let value = match Constant::of(&operand.item) {
+                    Some(value)                            => value,
+                    None if function && operator == Typeof => Constant::String("function".to_string()),
+                    None                                   => return,
+                };
+
+                let folded = match operator {
+                    Typeof if function => value,
+                    Typeof     => Constant::String(value.type_of().to_string()),
+                    Void       => Constant::Undefined,
+                    Addition   => Constant::Number(match value.to_number() {
+                        Some(value) => value,
+                        None        => return,
+                    }),
+                    BitwiseNot => Constant::Number(match value.to_number() {
+                        Some(value) => !to_int32(value) as f64,
+                        None        => return,
+                    }),
+                    // Negative numbers are already as short as they get
+                    Substraction => match operand.item {
+                        Expression::Literal(LiteralInteger(_)) |
+                        Expression::Literal(LiteralFloat(_))   => return,
+                        _ => Constant::Number(match value.to_number() {
+                            Some(value) => -value,
+                            None        => return,
+                        }),
+                    },
+                    _ => return,
+                };
+
+                match ctx.literal(folded) {
+                    Some(folded) => folded,
+                    None         => return,
+                }
+            },
+
+            Expression::Postfix {
+                ref mut operand,
+                ..
+            } => {
+                ctx.target(operand);
+                return;
+            },
+
+            Expression::Conditional {
+                ref mut test,
+                ref mut consequent,
+                ref mut alternate,
+            } => {
+                ctx.condition(test);
+                ctx.callee = callee;
+                consequent.compress(ctx);
+                ctx.callee = callee;
+                alternate.compress(ctx);
+
+                // `!a ? b : c` is `a ? c : b`
+                if let Expression::Prefix {
+                    operator: LogicalNot,
+                    ref mut operand,
+                } = test.item {
+                    let operand = take(operand);
+
+                    mem::swap(consequent, alternate);
+                    **test = operand;
+
+                    return;
+                }
+
+                let value = match Constant::of(&test.item) {
+                    Some(value) => value,
+                    None        => return,
+                };
+
+                let result = if value.is_truthy() { consequent } else { alternate };
+
+                if callee && is_reference(&result.item) {
+                    return;
+                }
+
+                take(result).item
+            },
+
+            Expression::ArrowFunction {
+                ref mut params,
+                ref mut body,
+            } => {
+                params.compress(ctx);
+                body.compress(ctx);
+                return;
+            },
+
+            Expression::Function {
+                ref mut params,
+                ref mut body,
+                ..
+            } => {
+                params.compress(ctx);
+                ctx.statements(body);
+                return;
+            },
+
+            Expression::TemplateLiteral {
+                ref mut expressions,
+                ..
+            } => {
+                expressions.compress(ctx);
+                return;
+            },
+
+            Expression::Pattern(ref mut pattern) => {
+                pattern.compress(ctx);
+                return;
+            },
+
+            Expression::Import {
+                ref mut source,
+            } => {
+                source.compress(ctx);
+                return;
+            },
+        }
+    }
+}
+
+#[inline]
+fn take(expression: &mut Loc<Expression>) -> Loc<Expression> {
+    mem::replace(expression, Loc::new(expression.span, Expression::This))
+}
+
+/// The `ToInt32` conversion.
+fn to_int32(value: f64) -> i32 {
+    if !value.is_finite() {
+        return 0;
+    }
+
+    let value = value.trunc() % 4294967296.0;
+    let value = if value < 0.0 { value + 4294967296.0 } else { value };
+
+    value as u64 as u32 as i32
+}
+
+/// Reads a number literal as the tokenizer produced it.
+fn number_literal(literal: &str) -> Option<f64> {
+    let bytes = literal.as_bytes();
+
+    // Legacy octal literals like `017`
+    if bytes.len() > 1 && bytes[0] == b'0' && bytes[1].is_ascii_digit() {
+        return None;
+    }
+
+    literal.parse().ok()
+}
+
+/// Converts a string to a number the way `Number(string)` does, or
+/// returns `None` if the string isn't simple enough to be sure.
+fn string_to_number(value: &str) -> Option<f64> {
+    let value = value.trim_matches(|ch: char| ch == '\u{feff}' || (ch.is_whitespace() && ch != '\u{85}'));
+
+    if value.is_empty() {
+        return Some(0.0);
+    }
+
+    let radix = match value.get(..2) {
+        Some("0x") | Some("0X") => 16,
+        Some("0o") | Some("0O") => 8,
+        Some("0b") | Some("0B") => 2,
+        _                       => 10,
+    };
+
+    if radix != 10 {
+        let digits = &value[2..];
+
+        if digits.is_empty() || digits.len() > 15 {
+            return None;
+        }
+
+        if !digits.chars().all(|digit| digit.is_digit(radix)) {
+            return Some(f64::NAN);
+        }
+
+        return match u64::from_str_radix(digits, radix) {
+            Ok(number) => Some(number as f64),
+            Err(_)     => Some(f64::NAN),
+        };
+    }
+
+    match value {
+        "Infinity" | "+Infinity" => return Some(f64::INFINITY),
+        "-Infinity"              => return Some(f64::NEG_INFINITY),
+        _                        => {},
+    }
+
+    if !value.bytes().all(|byte| byte.is_ascii_digit() || b"+-.eE".contains(&byte)) {
+        return Some(f64::NAN);
+    }
+
+    value.parse().ok()
+}
+
+/// The `ToString` conversion of a number.
+fn number_to_string(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+
+    if value == 0.0 {
+        return "0".to_string();
+    }
+
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+
+    if value < 0.0 {
+        return format!("-{}", number_to_string(-value));
+    }
+
+    // Shortest digits that read back as the same number
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific.split_at(scientific.find('e').expect("Exponent notation"));
+    let digits = mantissa.replace('.', "");
+    let exponent: i32 = exponent[1..].parse().expect("Exponent notation");
+    let count = digits.len() as i32;
+    let point = exponent + 1;
+
+    if count <= point && point <= 21 {
+        format!("{}{}", digits, "0".repeat((point - count) as usize))
+    } else if 0 < point && point <= 21 {
+        format!("{}.{}", &digits[.. point as usize], &digits[point as usize ..])
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+
+        if count == 1 {
+            format!("{}e{}{}", digits, sign, exponent.abs())
+        } else {
+            format!("{}.{}e{}{}", &digits[..1], &digits[1..], sign, exponent.abs())
+        }
+    }
+}
+
+/// Reads the value of a quoted string literal.
+fn unquote(literal: &str) -> Option<String> {
+    let mut chars = literal.chars();
+    let quote = chars.next()?;
+
+    if (quote != '"' && quote != '\'') || chars.next_back()? != quote {
+        return None;
+    }
+
+    let mut value = String::with_capacity(literal.len());
+    let mut chars = chars.peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            value.push(ch);
+            continue;
+        }
+
+        let escaped = match chars.next()? {
+            'n'  => '\n',
+            't'  => '\t',
+            'r'  => '\r',
+            'b'  => '\u{8}',
+            'f'  => '\u{c}',
+            'v'  => '\u{b}',
+            '0' if !chars.peek().is_some_and(|next| next.is_ascii_digit()) => '\0',
+
+            'x' => {
+                let digits: String = chars.by_ref().take(2).collect();
+
+                if digits.len() != 2 {
+                    return None;
+                }
+
+                ::std::char::from_u32(u32::from_str_radix(&digits, 16).ok()?)?
+            },
+
+            'u' => {
+                let digits: String = if chars.peek() == Some(&'{') {
+                    chars.next();
+                    chars.by_ref().take_while(|&ch| ch != '}').collect()
+                } else {
+                    chars.by_ref().take(4).collect()
+                };
+
+                // Surrogates can't be held by a `String`
+                ::std::char::from_u32(u32::from_str_radix(&digits, 16).ok()?)?
+            },
+
+            // Line continuation
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                continue;
+            },
+            '\n' | '\u{2028}' | '\u{2029}' => continue,
+
+            // Legacy octal escapes
+            '0' ..= '9' => return None,
+
+            other => other,
+        };
+
+        value.push(escaped);
+    }
+
+    Some(value)
+}
+
+/// Writes a string literal with the given value, in whichever quotes
+/// need less escaping.
+fn quote(value: &str) -> String {
+    let quote = if value.matches('"').count() > value.matches('\'').count() { '\'' } else { '"' };
+    let mut literal = String::with_capacity(value.len() + 2);
+    let mut chars = value.chars().peekable();
+
+    literal.push(quote);
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\'       => literal.push_str("\\\\"),
+            '\n'       => literal.push_str("\\n"),
+            '\t'       => literal.push_str("\\t"),
+            '\r'       => literal.push_str("\\r"),
+            '\u{2028}' => literal.push_str("\\u2028"),
+            '\u{2029}' => literal.push_str("\\u2029"),
+            '\0' if !chars.peek().is_some_and(|next| next.is_ascii_digit()) => literal.push_str("\\0"),
+            ch if ch == quote => {
+                literal.push('\\');
+                literal.push(ch);
+            },
+            ch if ch < ' ' => literal.push_str(&format!("\\x{:02x}", ch as u32)),
+            ch => literal.push(ch),
+        }
+    }
+
+    literal.push(quote);
+
+    literal
+}
+
+/// Checks if the statement always jumps away from the statements
+/// following it.
+#[inline]
+fn is_jump(statement: &Statement) -> bool {
+    matches!(*statement,
+        Statement::Return { .. }   |
+        Statement::Throw { .. }    |
+        Statement::Break { .. }    |
+        Statement::Continue { .. })
+}
+
+/// Replaces statements that are never going to run with the declarations
+/// hoisted out of them: functions, and `var`s without their values.
+fn hoisted(statements: Vec<Loc<Statement>>) -> Vec<Loc<Statement>> {
+    let mut names = Vec::new();
+    let mut hoisted = Vec::new();
+
+    for statement in statements {
+        match statement.item {
+            Statement::Function { .. } => hoisted.push(statement),
+            _                          => var_names(&statement.item, &mut names),
+        }
+    }
+
+    if !names.is_empty() {
+        hoisted.push(Statement::VariableDeclaration {
+            kind: VariableDeclarationKind::Var,
+            declarators: names.into_iter().map(|name| VariableDeclarator {
+                name: Pattern::Identifier(name),
+                value: None,
+            }).collect(),
+        }.into());
+    }
+
+    hoisted
+}
+
+/// Collects the names declared with `var` by the statement, including
+/// functions declared in blocks.
+fn var_names(statement: &Statement, names: &mut Vec<OwnedSlice>) {
+    match *statement {
+        Statement::VariableDeclaration {
+            kind: VariableDeclarationKind::Var,
+            ref declarators,
+        } => {
+            for declarator in declarators {
+                declarator.name.names(names);
+            }
+        },
+
+        Statement::Function {
+            name,
+            ..
+        } => names.push(name),
+
+        Statement::Block {
+            ref body,
+        } |
+        Statement::Transparent {
+            ref body,
+        } => {
+            for statement in body {
+                var_names(statement, names);
+            }
+        },
+
+        Statement::Labeled {
+            ref body,
+            ..
+        } |
+        Statement::While {
+            ref body,
+            ..
+        } |
+        Statement::DoWhile {
+            ref body,
+            ..
+        } => var_names(body, names),
+
+        Statement::If {
+            ref consequent,
+            ref alternate,
+            ..
+        } => {
+            var_names(consequent, names);
+
+            if let Some(ref alternate) = *alternate {
+                var_names(alternate, names);
+            }
+        },
+
+        Statement::For {
+            ref init,
+            ref body,
+            ..
+        } => {
+            if let Some(ref init) = *init {
+                var_names(init, names);
+            }
+
+            var_names(body, names);
+        },
+
+        Statement::ForIn {
+            ref left,
+            ref body,
+            ..
+        } |
+        Statement::ForOf {
+            ref left,
+            ref body,
+            ..
+        } => {
+            var_names(left, names);
+            var_names(body, names);
+        },
+
+        Statement::Switch {
+            ref cases,
+            ..
+        } => {
+            for statement in cases.iter().flat_map(|case| &case.consequent) {
+                var_names(statement, names);
+            }
+        },
+
+        Statement::Try {
+            ref block,
+            ref handler,
+            ref finalizer,
+        } => {
+            let handler = handler.iter().flat_map(|handler| &handler.body);
+            let finalizer = finalizer.iter().flatten();
+
+            for statement in block.iter().chain(handler).chain(finalizer) {
+                var_names(statement, names);
+            }
+        },
+
+        _ => {},
+    }
+}
+
+/// Checks if the statement declares a name scoped to its block.
+fn is_declaration(statement: &Statement) -> bool {
+    match *statement {
+        Statement::VariableDeclaration {
+            kind,
+            ..
+        } => kind != VariableDeclarationKind::Var,
+
+        Statement::Function { .. } |
+        Statement::Class { .. }    => true,
+        _                          => false,
+    }
+}
+
+#[inline]
+fn is_directive(statement: &Statement) -> bool {
+    match *statement {
+        Statement::Expression {
+            ref value,
+        } => matches!(value.item, Expression::Literal(LiteralString(_))),
+        _ => false,
+    }
+}
+
+/// Checks if the statement ends with an `if` lacking an `else`, which
+/// would take the `else` of an `if` the statement is the body of.
+fn is_open_if(statement: &Statement) -> bool {
+    match *statement {
+        Statement::If {
+            alternate: None,
+            ..
+        } => true,
+
+        Statement::If {
+            alternate: Some(ref body),
+            ..
+        } |
+        Statement::Labeled {
+            ref body,
+            ..
+        } |
+        Statement::While {
+            ref body,
+            ..
+        } |
+        Statement::For {
+            ref body,
+            ..
+        } |
+        Statement::ForIn {
+            ref body,
+            ..
+        } |
+        Statement::ForOf {
+            ref body,
+            ..
+        } => is_open_if(&body.item),
+
+        _ => false,
+    }
+}
+
+/// Removes the braces of a block holding a single statement.
+fn unwrap_block(statement: &mut Loc<Statement>) {
+    let single = match statement.item {
+        Statement::Block {
+            ref mut body,
+        } if body.len() == 1 && !is_declaration(&body[0].item) => body.pop(),
+        _ => None,
+    };
+
+    if let Some(single) = single {
+        *statement = single;
+    }
+}
+
+/// Turns an `if` with expression statements as its branches into a single
+/// expression: `a && b`, `a || b` if the test is negated, or `a ? b : c`.
+fn if_expression(
+    test: &mut Loc<Expression>,
+    consequent: &mut Loc<Statement>,
+    alternate: &mut Option<Box<Loc<Statement>>>,
+) -> Option<Loc<Expression>> {
+    let consequent = match consequent.item {
+        Statement::Expression {
+            ref mut value,
+        } => value,
+        _ => return None,
+    };
+
+    let alternate = match *alternate {
+        Some(ref mut alternate) => match alternate.item {
+            Statement::Expression {
+                ref mut value,
+            } => Some(value),
+            _ => return None,
+        },
+        None => None,
+    };
+
+    let consequent = take(consequent);
+    let alternate = alternate.map(take);
+    let test = take(test);
+    let span = test.span;
+
+    let (test, negated) = match test.item {
+        Expression::Prefix {
+            operator: LogicalNot,
+            operand,
+        } => (*operand, true),
+        item => (Loc::new(span, item), false),
+    };
+
+    let value = match alternate {
+        Some(alternate) => {
+            let (consequent, alternate) = if negated {
+                (alternate, consequent)
+            } else {
+                (consequent, alternate)
+            };
+
+            Expression::Conditional {
+                test: Box::new(test),
+                consequent: Box::new(consequent),
+                alternate: Box::new(alternate),
+            }
+        },
+        None => Expression::Binary {
+            left: Box::new(test),
+            operator: if negated { LogicalOr } else { LogicalAnd },
+            right: Box::new(consequent),
+        },
+    };
+
+    Some(Loc::new(span, value))
+}
+
+/// Merges the statement into the previous one if both are expressions,
+/// which get joined into a sequence, or declarations of the same kind.
+fn merge(previous: &mut Statement, statement: &mut Statement) -> bool {
+    match *previous {
+        Statement::Expression {
+            value: ref mut previous,
+        } => {
+            let value = match *statement {
+                Statement::Expression {
+                    ref mut value,
+                } => take(value),
+                _ => return false,
+            };
+
+            let sequence = matches!(previous.item, Expression::Sequence(_));
+
+            if !sequence {
+                let first = take(previous);
+
+                *previous = Loc::new(first.span, Expression::Sequence(vec![first]));
+            }
+
+            if let Expression::Sequence(ref mut items) = previous.item {
+                match value.item {
+                    Expression::Sequence(following) => items.extend(following),
+                    item                            => items.push(Loc::new(value.span, item)),
+                }
+            }
+
+            true
+        },
+
+        Statement::VariableDeclaration {
+            kind,
+            declarators: ref mut previous,
+        } => match *statement {
+            Statement::VariableDeclaration {
+                kind: following,
+                ref mut declarators,
+            } if following == kind => {
+                previous.append(declarators);
+                true
+            },
+            _ => false,
+        },
+
+        _ => false,
+    }
+}
+
+/// Turns a list of statements into a single one.
+fn statement_of(mut statements: Vec<Loc<Statement>>) -> Statement {
+    if statements.len() == 1 {
+        statements.remove(0).item
+    } else {
+        Statement::Block {
+            body: statements,
+        }
+    }
+}
+
+#[inline]
+fn take_statement(statement: &mut Loc<Statement>) -> Loc<Statement> {
+    mem::replace(statement, Loc::new(statement.span, Statement::Block { body: Vec::new() }))
+}
+
+#[inline]
+fn address(name: &OwnedSlice) -> usize {
+    name.as_ptr() as usize
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first) if first.is_alphabetic() || first == '_' || first == '$' => {
+            chars.all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '$')
+        },
+        _ => false,
+    }
+}
+
+/// Finds the declarations of variables and functions that are never used.
+/// Bindings of the program scope can be used by other scripts, and any of
+/// the bindings could be read by `eval`, so they are kept.
+fn unused_declarations(tree: &ScopeTree) -> HashSet<usize> {
+    let mut used = HashSet::new();
+    let mut unused = HashSet::new();
+
+    let evaluates = tree.references.iter().any(|reference| {
+        reference.binding.is_none() && reference.name.as_str() == "eval"
+    });
+
+    if evaluates {
+        return unused;
+    }
+
+    for binding in &tree.bindings {
+        let removable = match binding.kind {
+            BindingKind::Variable(_) |
+            BindingKind::Function    => binding.references.is_empty() && tree.function_scope(binding.scope) != 0,
+            _                        => false,
+        };
+
+        let declarations = binding.declarations.iter().map(address);
+
+        if removable {
+            unused.extend(declarations);
+        } else {
+            used.extend(declarations);
+        }
+    }
+
+    // Names generated by the transformer can share their address
+    &unused - &used
+}
+
+/// Folds constant expressions, replaces the globals given by `defines`,
+/// removes code that is either never run or never used, and joins the
+/// remaining statements into as few as possible.
+pub fn compress(program: &mut Program, defines: &[Define]) {
+    let tree = scope::analyze(program);
+    let declared: HashSet<String> = tree.bindings.iter().map(|binding| binding.name.to_string()).collect();
+    let mut unused = HashSet::new();
+
+    // Removing a declaration can leave other ones unused
+    loop {
+        let removed = {
+            let mut ctx = Compressor::new(&mut program.strings, defines, &declared, &unused);
+
+            ctx.statements(&mut program.body);
+            ctx.removed
+        };
+
+        if removed == 0 && !unused.is_empty() {
+            break;
+        }
+
+        unused = unused_declarations(&scope::analyze(program));
+
+        if unused.is_empty() {
+            break;
+        }
+    }
+}
@@ -1,18 +1,319 @@
-#[derive(Copy, Clone, Debug)]
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use lexicon::Token;
+
+/// Error produced by the tokenizer when it can't read a token from source.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Error {
     UnexpectedEndOfProgram,
     UnexpectedToken {
         start: usize,
         end: usize,
     },
+    InvalidRegExFlags {
+        start: usize,
+        end: usize,
+    },
 }
 
-pub enum ParsingError {
-    UnexpectedEndOfProgram,
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// Describes a token, or a class of tokens, the parser would have accepted
+/// at the position where a `ParseError` occured.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Expected {
+    Token(Token),
+    Identifier,
+    Expression,
+    Statement,
+    ObjectKey,
+    ModuleSource,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Expected::Token(ref token) => write!(f, "`{}`", token),
+            Expected::Identifier       => f.write_str("identifier"),
+            Expected::Expression       => f.write_str("expression"),
+            Expected::Statement        => f.write_str("statement"),
+            Expected::ObjectKey        => f.write_str("object key"),
+            Expected::ModuleSource     => f.write_str("module source string"),
+        }
+    }
+}
+
+/// Error returned from `parser::parse`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    /// The source has ended while parser still expected more tokens.
+    UnexpectedEndOfProgram {
+        expected: Vec<Expected>,
+    },
+
+    /// Source contains a character that can't start any valid token.
+    UnexpectedCharacter {
+        start: usize,
+        end: usize,
+    },
+
+    /// Flags of a regular expression literal are either unknown or repeated.
+    InvalidRegExFlags {
+        start: usize,
+        end: usize,
+    },
+
+    /// Expression used as a destructuring pattern can't be one.
+    InvalidPattern {
+        start: usize,
+        end: usize,
+    },
+
+    /// A valid token was found in a place where grammar doesn't allow it.
+    /// Since the error can outlive the source, `token` holds a copy of the
+    /// token's source code.
     UnexpectedToken {
+        token: String,
         start: usize,
         end: usize,
+        expected: Vec<Expected>,
     },
 }
 
-pub type Result<T> = ::std::result::Result<T, Error>;
+impl From<Error> for ParseError {
+    #[inline]
+    fn from(err: Error) -> Self {
+        match err {
+            Error::UnexpectedEndOfProgram => ParseError::UnexpectedEndOfProgram {
+                expected: Vec::new(),
+            },
+            Error::UnexpectedToken {
+                start,
+                end,
+            } => ParseError::UnexpectedCharacter {
+                start: start,
+                end: end,
+            },
+            Error::InvalidRegExFlags {
+                start,
+                end,
+            } => ParseError::InvalidRegExFlags {
+                start: start,
+                end: end,
+            },
+        }
+    }
+}
+
+fn write_expected(f: &mut fmt::Formatter, expected: &[Expected]) -> fmt::Result {
+    let mut iter = expected.iter();
+
+    if let Some(first) = iter.next() {
+        write!(f, ", expected {}", first)?;
+    }
+
+    for item in iter {
+        write!(f, " or {}", item)?;
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::UnexpectedEndOfProgram {
+                ref expected,
+            } => {
+                f.write_str("Unexpected end of program")?;
+                write_expected(f, expected)
+            },
+
+            ParseError::UnexpectedCharacter {
+                start,
+                end,
+            } => write!(f, "Unexpected character at {}..{}", start, end),
+
+            ParseError::InvalidRegExFlags {
+                start,
+                end,
+            } => write!(f, "Invalid regular expression flags at {}..{}", start, end),
+
+            ParseError::InvalidPattern {
+                start,
+                end,
+            } => write!(f, "Invalid destructuring pattern at {}..{}", start, end),
+
+            ParseError::UnexpectedToken {
+                ref token,
+                start,
+                end,
+                ref expected,
+            } => {
+                write!(f, "Unexpected token `{}` at {}..{}", token, start, end)?;
+                write_expected(f, expected)
+            },
+        }
+    }
+}
+
+pub type ParseResult<T> = ::std::result::Result<T, ParseError>;
+
+/// Error returned from `transformer::transform`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransformError {
+    /// A `const` binding is assigned to, incremented or decremented. Since
+    /// the binding is lowered to a `var`, the error can't be left for the
+    /// runtime to throw.
+    ConstAssignment {
+        name: String,
+        start: usize,
+        end: usize,
+    },
+
+    /// An imported binding is assigned to, incremented or decremented.
+    /// Since imported bindings are read from the required module, the write
+    /// would change the exports of that module instead of failing.
+    ImportAssignment {
+        name: String,
+        start: usize,
+        end: usize,
+    },
+
+    /// `super` is used in a class without `extends`, or outside of classes,
+    /// where there's no parent class to lower it to.
+    SuperWithoutParent {
+        start: usize,
+        end: usize,
+    },
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransformError::ConstAssignment {
+                ref name,
+                start,
+                end,
+            } => write!(f, "Assignment to constant `{}` at {}..{}", name, start, end),
+
+            TransformError::ImportAssignment {
+                ref name,
+                start,
+                end,
+            } => write!(f, "Assignment to imported binding `{}` at {}..{}", name, start, end),
+
+            TransformError::SuperWithoutParent {
+                start,
+                end,
+            } => write!(f, "`super` without a parent class at {}..{}", start, end),
+        }
+    }
+}
+
+pub type TransformResult<T> = ::std::result::Result<T, TransformError>;
+
+/// Error returned from `compressor::Define::parse`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DefineError {
+    /// The definition isn't written as `NAME=value`, where `NAME` is
+    /// a global or a chain of its properties.
+    InvalidName {
+        definition: String,
+    },
+
+    /// The value of a definition isn't a literal.
+    InvalidValue {
+        definition: String,
+    },
+}
+
+impl fmt::Display for DefineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DefineError::InvalidName {
+                ref definition,
+            } => write!(f, "Invalid definition `{}`, expected NAME=value", definition),
+
+            DefineError::InvalidValue {
+                ref definition,
+            } => write!(f, "Value of `{}` must be a literal, strings need quotes", definition),
+        }
+    }
+}
+
+pub type DefineResult<T> = ::std::result::Result<T, DefineError>;
+
+/// Error returned from `bundler::bundle`.
+#[derive(Debug)]
+pub enum BundleError {
+    /// A file of the bundle couldn't be read.
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+
+    /// A file of the bundle isn't valid JavaScript.
+    Parse {
+        path: PathBuf,
+        error: ParseError,
+    },
+
+    /// A file of the bundle can't be compiled.
+    Transform {
+        path: PathBuf,
+        error: TransformError,
+    },
+
+    /// A module `specifier` used in the file at `path` doesn't resolve
+    /// to any file.
+    Unresolved {
+        specifier: String,
+        path: PathBuf,
+    },
+
+    /// A `require` call or a dynamic `import()` in the file at `path` loads
+    /// a module with a specifier that isn't a string literal, so the module
+    /// can't be known before the bundle runs.
+    Dynamic {
+        path: PathBuf,
+        start: usize,
+        end: usize,
+    },
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BundleError::Io {
+                ref path,
+                ref error,
+            } => write!(f, "Couldn't read {}: {}", path.display(), error),
+
+            BundleError::Parse {
+                ref path,
+                ref error,
+            } => write!(f, "{}: {}", path.display(), error),
+
+            BundleError::Transform {
+                ref path,
+                ref error,
+            } => write!(f, "{}: {}", path.display(), error),
+
+            BundleError::Unresolved {
+                ref specifier,
+                ref path,
+            } => write!(f, "Can't resolve `{}` from {}", specifier, path.display()),
+
+            BundleError::Dynamic {
+                ref path,
+                start,
+                end,
+            } => write!(f, "{}: Module specifier at {}..{} isn't a string literal", path.display(), start, end),
+        }
+    }
+}
+
+pub type BundleResult<T> = ::std::result::Result<T, BundleError>;
@@ -1,5 +1,6 @@
 use std::{ str, slice, fmt };
-use std::ops::Deref;
+use std::hash::{ Hash, Hasher };
+use std::ops::{ Deref, DerefMut };
 
 #[derive(Clone, Copy)]
 pub struct OwnedSlice {
@@ -8,6 +9,9 @@ pub struct OwnedSlice {
 }
 
 impl OwnedSlice {
+    /// # Safety
+    ///
+    /// The returned slice doesn't borrow `source`, which has to outlive it.
     #[inline]
     pub unsafe fn from_str(source: &str) -> Self {
         OwnedSlice {
@@ -65,6 +69,15 @@ impl PartialEq for OwnedSlice {
     }
 }
 
+impl Eq for OwnedSlice {}
+
+impl Hash for OwnedSlice {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
 impl fmt::Debug for OwnedSlice {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -79,6 +92,84 @@ impl fmt::Display for OwnedSlice {
     }
 }
 
+/// Location of a piece of grammar in the source code. `start` and `end`
+/// are byte offsets, while `line` (counting from 1) and `column` (counting
+/// UTF-16 code units from 0, like source maps) describe the position of
+/// `start`. Grammar created by the
+/// transformer, that doesn't exist in the source, has a default `Span` with
+/// `line` set to 0.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// Returns true if the `Span` points to a location in the source code.
+    #[inline]
+    pub fn is_located(&self) -> bool {
+        self.line != 0
+    }
+}
+
+/// Wraps an item of grammar with its `Span`. Spans are not taken into
+/// account when comparing two `Loc`s, nor are they included in the `Debug`
+/// output, so that trees produced from different sources can be compared.
+#[derive(Clone)]
+pub struct Loc<T> {
+    pub span: Span,
+    pub item: T,
+}
+
+impl<T> Loc<T> {
+    #[inline]
+    pub fn new(span: Span, item: T) -> Self {
+        Loc {
+            span: span,
+            item: item,
+        }
+    }
+}
+
+impl<T> From<T> for Loc<T> {
+    #[inline]
+    fn from(item: T) -> Self {
+        Loc::new(Span::default(), item)
+    }
+}
+
+impl<T> Deref for Loc<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.item
+    }
+}
+
+impl<T> DerefMut for Loc<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.item
+    }
+}
+
+impl<T: PartialEq> PartialEq for Loc<T> {
+    #[inline]
+    fn eq(&self, other: &Loc<T>) -> bool {
+        self.item == other.item
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Loc<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.item, f)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum LiteralValue {
     LiteralUndefined,
@@ -88,12 +179,128 @@ pub enum LiteralValue {
     LiteralInteger(u64),
     LiteralFloat(OwnedSlice),
     LiteralString(OwnedSlice),
+    LiteralRegEx {
+        pattern: OwnedSlice,
+        flags: OwnedSlice,
+    },
 }
 pub use self::LiteralValue::*;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+impl fmt::Display for LiteralValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LiteralUndefined          => f.write_str("undefined"),
+            LiteralNull               => f.write_str("null"),
+            LiteralTrue               => f.write_str("true"),
+            LiteralFalse              => f.write_str("false"),
+            LiteralInteger(num)       => write!(f, "{}", num),
+            LiteralFloat(ref num)     => f.write_str(num),
+            LiteralString(ref string) => f.write_str(string),
+            LiteralRegEx {
+                ref pattern,
+                ref flags,
+            }                         => write!(f, "/{}/{}", pattern.as_str(), flags.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Parameter {
-    pub name: OwnedSlice,
+    pub name: Pattern,
+    pub default: Option<Loc<Expression>>,
+    // `...name`, collecting the remaining arguments into an array
+    pub rest: bool,
+}
+
+impl Parameter {
+    /// Checks if the parameter is a plain identifier, without a default
+    /// value or the rest syntax.
+    #[inline]
+    pub fn is_simple(&self) -> bool {
+        self.name.is_identifier() && self.default.is_none() && !self.rest
+    }
+}
+
+impl<P: Into<Pattern>> From<P> for Parameter {
+    #[inline]
+    fn from(name: P) -> Self {
+        Parameter {
+            name: name.into(),
+            default: None,
+            rest: false,
+        }
+    }
+}
+
+/// Property of an object destructuring pattern, `{ key: value }`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PropertyPattern {
+    pub key: OwnedSlice,
+    pub value: Pattern,
+}
+
+/// Target of a declaration, parameter or an assignment, which can either
+/// be a simple identifier or destructure the value it's given.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    Identifier(OwnedSlice),
+    Object(Vec<PropertyPattern>),
+    Array(Vec<Option<Pattern>>),
+    Rest(Box<Pattern>),
+    Default {
+        pattern: Box<Pattern>,
+        value: Box<Loc<Expression>>,
+    },
+    // Member expression, only valid as a target of an assignment
+    Expression(Box<Loc<Expression>>),
+}
+
+impl Pattern {
+    #[inline]
+    pub fn is_identifier(&self) -> bool {
+        matches!(*self, Pattern::Identifier(_))
+    }
+
+    /// Collects the names of all variables bound by the pattern.
+    pub fn names(&self, names: &mut Vec<OwnedSlice>) {
+        match *self {
+            Pattern::Identifier(name) => names.push(name),
+
+            Pattern::Object(ref properties) => {
+                for property in properties {
+                    property.value.names(names);
+                }
+            },
+
+            Pattern::Array(ref elements) => {
+                for element in elements.iter().flatten() {
+                    element.names(names);
+                }
+            },
+
+            Pattern::Rest(ref pattern)   |
+            Pattern::Default {
+                ref pattern,
+                ..
+            } => pattern.names(names),
+
+            Pattern::Expression(_) => {},
+        }
+    }
+}
+
+impl From<&'static str> for Pattern {
+    #[inline]
+    fn from(name: &'static str) -> Self {
+        Pattern::Identifier(OwnedSlice::from_static(name))
+    }
+}
+
+impl From<OwnedSlice> for Pattern {
+    #[inline]
+    fn from(name: OwnedSlice) -> Self {
+        Pattern::Identifier(name)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -151,6 +358,61 @@ pub enum OperatorType {
 use self::OperatorType::*;
 
 impl OperatorType {
+    /// Source representation of the operator
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            FatArrow         => "=>",
+            Accessor         => ".",
+            New              => "new",
+            Increment        => "++",
+            Decrement        => "--",
+            LogicalNot       => "!",
+            BitwiseNot       => "~",
+            Typeof           => "typeof",
+            Void             => "void",
+            Delete           => "delete",
+            Multiplication   => "*",
+            Division         => "/",
+            Remainder        => "%",
+            Exponent         => "**",
+            Addition         => "+",
+            Substraction     => "-",
+            BitShiftLeft     => "<<",
+            BitShiftRight    => ">>",
+            UBitShiftRight   => ">>>",
+            Lesser           => "<",
+            LesserEquals     => "<=",
+            Greater          => ">",
+            GreaterEquals    => ">=",
+            Instanceof       => "instanceof",
+            In               => "in",
+            StrictEquality   => "===",
+            StrictInequality => "!==",
+            Equality         => "==",
+            Inequality       => "!=",
+            BitwiseAnd       => "&",
+            BitwiseXor       => "^",
+            BitwiseOr        => "|",
+            LogicalAnd       => "&&",
+            LogicalOr        => "||",
+            Conditional      => "?",
+            Assign           => "=",
+            AddAssign        => "+=",
+            SubstractAssign  => "-=",
+            ExponentAssign   => "**=",
+            MultiplyAssign   => "*=",
+            DivideAssign     => "/=",
+            RemainderAssign  => "%=",
+            BSLAssign        => "<<=",
+            BSRAssign        => ">>=",
+            UBSRAssign       => ">>>=",
+            BitAndAssign     => "&=",
+            BitXorAssign     => "^=",
+            BitOrAssign      => "|=",
+            Spread           => "...",
+        }
+    }
+
     /// According to the Operator Precedence Table
     /// Note: Unary opearotrs default to 15!
     pub fn binding_power(&self) -> u8 {
@@ -167,12 +429,12 @@ impl OperatorType {
             BitwiseNot       |
             Typeof           |
             Void             |
-            Delete           => 15,
+            Delete           |
+            Exponent         => 15,
 
             Multiplication   |
             Division         |
-            Remainder        |
-            Exponent         => 14,
+            Remainder        => 14,
 
             Addition         |
             Substraction     => 13,
@@ -219,7 +481,7 @@ impl OperatorType {
     }
 
     pub fn prefix(&self) -> bool {
-        match *self {
+        matches!(*self,
             LogicalNot       |
             BitwiseNot       |
             Typeof           |
@@ -230,14 +492,11 @@ impl OperatorType {
             Increment        |
             Decrement        |
             Addition         |
-            Substraction     => true,
-
-            _                => false
-        }
+            Substraction)
     }
 
     pub fn infix(&self) -> bool {
-        match *self {
+        matches!(*self,
             FatArrow         |
             Accessor         |
             Multiplication   |
@@ -277,14 +536,20 @@ impl OperatorType {
             UBSRAssign       |
             BitAndAssign     |
             BitXorAssign     |
-            BitOrAssign      => true,
+            BitOrAssign)
+    }
+
+    pub fn right_associative(&self) -> bool {
+        match *self {
+            Exponent         |
+            Conditional      => true,
 
-            _                => false
+            _                => self.assignment()
         }
     }
 
     pub fn assignment(&self) -> bool {
-        match *self {
+        matches!(*self,
             Assign           |
             AddAssign        |
             SubstractAssign  |
@@ -297,75 +562,104 @@ impl OperatorType {
             UBSRAssign       |
             BitAndAssign     |
             BitXorAssign     |
-            BitOrAssign      => true,
-
-            _                => false
-        }
+            BitOrAssign)
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     This,
+    // `super` in a call or a property access
+    Super,
     Identifier(OwnedSlice),
     Literal(LiteralValue),
-    Array(Vec<Expression>),
-    Sequence(Vec<Expression>),
-    Object(Vec<ObjectMember>),
+    Array(Vec<Loc<Expression>>),
+    Sequence(Vec<Loc<Expression>>),
+    Object(Vec<Loc<ObjectMember>>),
     Member {
-        object: Box<Expression>,
+        object: Box<Loc<Expression>>,
         property: OwnedSlice,
     },
     ComputedMember {
-        object: Box<Expression>,
-        property: Box<Expression>,
+        object: Box<Loc<Expression>>,
+        property: Box<Loc<Expression>>,
     },
     Call {
-        callee: Box<Expression>,
-        arguments: Vec<Expression>,
+        callee: Box<Loc<Expression>>,
+        arguments: Vec<Loc<Expression>>,
     },
     Binary {
-        left: Box<Expression>,
+        left: Box<Loc<Expression>>,
         operator: OperatorType,
-        right: Box<Expression>,
+        right: Box<Loc<Expression>>,
     },
     Prefix {
         operator: OperatorType,
-        operand: Box<Expression>,
+        operand: Box<Loc<Expression>>,
     },
     Postfix {
         operator: OperatorType,
-        operand: Box<Expression>,
+        operand: Box<Loc<Expression>>,
     },
     Conditional {
-        test: Box<Expression>,
-        consequent: Box<Expression>,
-        alternate: Box<Expression>,
+        test: Box<Loc<Expression>>,
+        consequent: Box<Loc<Expression>>,
+        alternate: Box<Loc<Expression>>,
     },
     ArrowFunction {
         params: Vec<Parameter>,
-        body: Box<Statement>,
+        body: Box<Loc<Statement>>,
     },
     Function {
         name: Option<OwnedSlice>,
         params: Vec<Parameter>,
-        body: Vec<Statement>,
-    }
+        body: Vec<Loc<Statement>>,
+    },
+    TemplateLiteral {
+        quasis: Vec<OwnedSlice>,
+        expressions: Vec<Loc<Expression>>,
+    },
+    TaggedTemplate {
+        tag: Box<Loc<Expression>>,
+        quasis: Vec<OwnedSlice>,
+        expressions: Vec<Loc<Expression>>,
+    },
+    // Destructuring pattern on the left side of an assignment
+    Pattern(Pattern),
+    // Dynamic `import(source)`
+    Import {
+        source: Box<Loc<Expression>>,
+    },
 }
 
 impl Expression {
+    /// How tightly the expression holds together, so that it can be
+    /// wrapped in parens when it's the operand of a stronger operator.
     pub fn binding_power(&self) -> u8 {
         match *self {
             Expression::Member {
                 ..
             }
             |
-            Expression::ArrowFunction {
+            Expression::ComputedMember {
                 ..
             } => 18,
 
             Expression::Call {
                 ..
+            }
+            |
+            Expression::TaggedTemplate {
+                ..
+            }
+            |
+            Expression::Import {
+                ..
+            } => 17,
+
+            Expression::Prefix {
+                operator: OperatorType::New,
+                ..
             } => 17,
 
             Expression::Prefix {
@@ -386,12 +680,84 @@ impl Expression {
                 ..
             } => 4,
 
+            Expression::ArrowFunction {
+                ..
+            } => 3,
+
+            // Minified to `!0`, `!1` and `void 0`
+            Expression::Literal(LiteralTrue)      |
+            Expression::Literal(LiteralFalse)     |
+            Expression::Literal(LiteralUndefined) => 15,
+
             _  => 100,
         }
     }
 
+    /// Checks if evaluating the expression can't have any observable
+    /// effects, other than producing its value. Property access, calls
+    /// and assignments are never considered pure.
+    pub fn is_pure(&self) -> bool {
+        match *self {
+            Expression::This                 |
+            Expression::Identifier(_)        |
+            Expression::Literal(_)           |
+            Expression::Function { .. }      |
+            Expression::ArrowFunction { .. } => true,
+
+            Expression::Array(ref items) |
+            Expression::Sequence(ref items) => items.iter().all(|item| item.is_pure()),
+
+            Expression::Object(ref members) => members.iter().all(|member| match member.item {
+                ObjectMember::Shorthand { .. } |
+                ObjectMember::Method { .. }    |
+                ObjectMember::Getter { .. }    |
+                ObjectMember::Setter { .. }    => true,
+                ObjectMember::Literal {
+                    ref value,
+                    ..
+                } => value.is_pure(),
+                ObjectMember::Computed {
+                    ref key,
+                    ref value,
+                } => key.is_pure() && value.is_pure(),
+                ObjectMember::ComputedMethod {
+                    ref name,
+                    ..
+                } => name.is_pure(),
+            }),
+
+            Expression::Binary {
+                ref left,
+                operator,
+                ref right,
+            } => !operator.assignment() && left.is_pure() && right.is_pure(),
+
+            Expression::Prefix {
+                operator,
+                ref operand,
+            } => operand.is_pure() && matches!(operator,
+                LogicalNot | BitwiseNot | Addition | Substraction | Typeof | Void
+            ),
+
+            Expression::Conditional {
+                ref test,
+                ref consequent,
+                ref alternate,
+            } => test.is_pure() && consequent.is_pure() && alternate.is_pure(),
+
+            Expression::TemplateLiteral {
+                ref expressions,
+                ..
+            } => expressions.iter().all(|expression| expression.is_pure()),
+
+            _ => false,
+        }
+    }
+
     #[inline]
-    pub fn binary<E: Into<Expression>>(left: E, operator: OperatorType, right: E) -> Self {
+    pub fn binary<L, R>(left: L, operator: OperatorType, right: R) -> Self
+        where L: Into<Loc<Expression>>, R: Into<Loc<Expression>>
+    {
         Expression::Binary {
             operator: operator,
             left: Box::new(left.into()),
@@ -400,7 +766,7 @@ impl Expression {
     }
 
     #[inline]
-    pub fn member<E: Into<Expression>, S: Into<OwnedSlice>>(object: E, property: S) -> Self {
+    pub fn member<E: Into<Loc<Expression>>, S: Into<OwnedSlice>>(object: E, property: S) -> Self {
         Expression::Member {
             object: Box::new(object.into()),
             property: property.into(),
@@ -408,7 +774,7 @@ impl Expression {
     }
 
     #[inline]
-    pub fn call<E: Into<Expression>>(callee: E, arguments: Vec<Expression>) -> Self {
+    pub fn call<E: Into<Loc<Expression>>>(callee: E, arguments: Vec<Loc<Expression>>) -> Self {
         Expression::Call {
             callee: Box::new(callee.into()),
             arguments: arguments,
@@ -437,6 +803,27 @@ impl<'a> From<&'a OwnedSlice> for Expression {
     }
 }
 
+impl From<&'static str> for Loc<Expression> {
+    #[inline]
+    fn from(ident: &'static str) -> Self {
+        Loc::from(Expression::from(ident))
+    }
+}
+
+impl From<OwnedSlice> for Loc<Expression> {
+    #[inline]
+    fn from(ident: OwnedSlice) -> Self {
+        Loc::from(Expression::Identifier(ident))
+    }
+}
+
+impl<'a> From<&'a OwnedSlice> for Loc<Expression> {
+    #[inline]
+    fn from(ident: &'a OwnedSlice) -> Self {
+        Loc::from(Expression::Identifier(*ident))
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum ObjectMember {
     Shorthand {
@@ -444,41 +831,61 @@ pub enum ObjectMember {
     },
     Literal {
         key: OwnedSlice,
-        value: Expression,
+        value: Loc<Expression>,
     },
     Computed {
-        key: Expression,
-        value: Expression,
+        key: Loc<Expression>,
+        value: Loc<Expression>,
     },
     Method {
         name: OwnedSlice,
         params: Vec<Parameter>,
-        body: Vec<Statement>,
+        body: Vec<Loc<Statement>>,
     },
     ComputedMethod {
-        name: Expression,
+        name: Loc<Expression>,
         params: Vec<Parameter>,
-        body: Vec<Statement>,
-    }
+        body: Vec<Loc<Statement>>,
+    },
+    Getter {
+        name: OwnedSlice,
+        body: Vec<Loc<Statement>>,
+    },
+    Setter {
+        name: OwnedSlice,
+        params: Vec<Parameter>,
+        body: Vec<Loc<Statement>>,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ClassMember {
     Constructor {
         params: Vec<Parameter>,
-        body: Vec<Statement>,
+        body: Vec<Loc<Statement>>,
     },
     Method {
         is_static: bool,
         name: OwnedSlice,
         params: Vec<Parameter>,
-        body: Vec<Statement>,
+        body: Vec<Loc<Statement>>,
     },
     Property {
         is_static: bool,
         name: OwnedSlice,
-        value: Expression,
-    }
+        value: Loc<Expression>,
+    },
+    Getter {
+        is_static: bool,
+        name: OwnedSlice,
+        body: Vec<Loc<Statement>>,
+    },
+    Setter {
+        is_static: bool,
+        name: OwnedSlice,
+        params: Vec<Parameter>,
+        body: Vec<Loc<Statement>>,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -488,101 +895,245 @@ pub enum VariableDeclarationKind {
     Const,
 }
 
+impl VariableDeclarationKind {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            VariableDeclarationKind::Var   => "var",
+            VariableDeclarationKind::Let   => "let",
+            VariableDeclarationKind::Const => "const",
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct VariableDeclarator {
-    pub name: OwnedSlice,
-    pub value: Option<Expression>,
+    pub name: Pattern,
+    pub value: Option<Loc<Expression>>,
+}
+
+/// Binding created by an `import` declaration.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ImportSpecifier {
+    // `import local from "source"`
+    Default {
+        local: OwnedSlice,
+    },
+    // `import * as local from "source"`
+    Namespace {
+        local: OwnedSlice,
+    },
+    // `import { imported as local } from "source"`
+    Named {
+        imported: OwnedSlice,
+        local: OwnedSlice,
+    },
+}
+
+/// Name in the `export { local as exported }` list.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ExportSpecifier {
+    pub local: OwnedSlice,
+    pub exported: OwnedSlice,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExportKind {
+    // `export var a = 1;`, `export function a() {}`, `export class A {}`
+    Declaration(Box<Loc<Statement>>),
+    // `export default …`, either a function or class declaration or
+    // an expression statement
+    Default(Box<Loc<Statement>>),
+    // `export { a, b as c };` or a re-export `export { a } from "source";`
+    Named {
+        specifiers: Vec<ExportSpecifier>,
+        source: Option<OwnedSlice>,
+    },
+    // `export * from "source";`
+    All {
+        source: OwnedSlice,
+    },
+}
+
+/// A single `case` of a `switch` statement, `test` is `None` for
+/// the `default` case.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SwitchCase {
+    pub test: Option<Loc<Expression>>,
+    pub consequent: Vec<Loc<Statement>>,
+}
+
+/// The `catch` part of a `try` statement, binding the error to `param`
+/// unless the binding is omitted.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CatchClause {
+    pub param: Option<Pattern>,
+    pub body: Vec<Loc<Statement>>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
     Block {
-        body: Vec<Statement>,
+        body: Vec<Loc<Statement>>,
     },
     // `Transparent` is not part of the language grammar, just a helper that
     // allows the transformer to replace a single statement with mutliple
     // statements without messing with parent array.
     Transparent {
-        body: Vec<Statement>,
+        body: Vec<Loc<Statement>>,
     },
     Labeled {
         label: OwnedSlice,
-        body: Box<Statement>,
+        body: Box<Loc<Statement>>,
     },
     VariableDeclaration {
         kind: VariableDeclarationKind,
         declarators: Vec<VariableDeclarator>,
     },
     Expression {
-        value: Expression
+        value: Loc<Expression>
     },
     Return {
-        value: Option<Expression>,
+        value: Option<Loc<Expression>>,
     },
     Break {
         label: Option<OwnedSlice>,
     },
+    Continue {
+        label: Option<OwnedSlice>,
+    },
     Function {
         name: OwnedSlice,
         params: Vec<Parameter>,
-        body: Vec<Statement>,
+        body: Vec<Loc<Statement>>,
     },
     If {
-        test: Expression,
-        consequent: Box<Statement>,
-        alternate: Option<Box<Statement>>,
+        test: Loc<Expression>,
+        consequent: Box<Loc<Statement>>,
+        alternate: Option<Box<Loc<Statement>>>,
     },
     While {
-        test: Expression,
-        body: Box<Statement>,
+        test: Loc<Expression>,
+        body: Box<Loc<Statement>>,
+    },
+    DoWhile {
+        body: Box<Loc<Statement>>,
+        test: Loc<Expression>,
+    },
+    Switch {
+        discriminant: Loc<Expression>,
+        cases: Vec<SwitchCase>,
     },
     For {
-        init: Option<Box<Statement>>,
-        test: Option<Expression>,
-        update: Option<Expression>,
-        body: Box<Statement>,
+        init: Option<Box<Loc<Statement>>>,
+        test: Option<Loc<Expression>>,
+        update: Option<Loc<Expression>>,
+        body: Box<Loc<Statement>>,
     },
     ForIn {
-        left: Box<Statement>,
-        right: Expression,
-        body: Box<Statement>,
+        left: Box<Loc<Statement>>,
+        right: Loc<Expression>,
+        body: Box<Loc<Statement>>,
     },
     ForOf {
-        left: Box<Statement>,
-        right: Expression,
-        body: Box<Statement>,
+        left: Box<Loc<Statement>>,
+        right: Loc<Expression>,
+        body: Box<Loc<Statement>>,
     },
+    // `name` is empty for an anonymous class in `export default`
     Class {
         name: OwnedSlice,
         extends: Option<OwnedSlice>,
-        body: Vec<ClassMember>,
+        body: Vec<Loc<ClassMember>>,
     },
     Throw {
-        value: Expression
+        value: Loc<Expression>
+    },
+    Try {
+        block: Vec<Loc<Statement>>,
+        handler: Option<CatchClause>,
+        finalizer: Option<Vec<Loc<Statement>>>,
+    },
+    // Module `source` is kept as a quoted string literal
+    ImportDeclaration {
+        specifiers: Vec<ImportSpecifier>,
+        source: OwnedSlice,
+    },
+    ExportDeclaration {
+        kind: ExportKind,
     },
 }
 
 impl From<Expression> for Statement {
     #[inline]
     fn from(expression: Expression) -> Self {
+        Statement::Expression {
+            value: Loc::from(expression)
+        }
+    }
+}
+
+impl From<Loc<Expression>> for Statement {
+    #[inline]
+    fn from(expression: Loc<Expression>) -> Self {
         Statement::Expression {
             value: expression
         }
     }
 }
 
+impl From<Expression> for Loc<Statement> {
+    #[inline]
+    fn from(expression: Expression) -> Self {
+        Loc::from(Statement::from(expression))
+    }
+}
+
+impl From<Loc<Expression>> for Loc<Statement> {
+    /// Wraps the expression in a statement sharing the same `Span`.
+    #[inline]
+    fn from(expression: Loc<Expression>) -> Self {
+        Loc::new(expression.span, Statement::from(expression))
+    }
+}
+
+/// Owns strings created after the source was parsed, such as names
+/// generated by the transformer, so that `OwnedSlice`s pointing to them
+/// remain valid for as long as the `Program` is alive.
+#[derive(Debug, PartialEq, Default)]
+pub struct Strings(Vec<String>);
+
+impl Strings {
+    #[inline]
+    pub fn alloc(&mut self, string: String) -> OwnedSlice {
+        // Moving the `String` into the `Vec` doesn't move its buffer
+        let slice = unsafe { OwnedSlice::from_str(&string) };
+
+        self.0.push(string);
+
+        slice
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Program {
     source: String,
-    pub body: Vec<Statement>,
+    pub strings: Strings,
+    pub body: Vec<Loc<Statement>>,
 }
 
 impl Program {
     #[inline]
-    pub fn new(source: String, body: Vec<Statement>) -> Self {
+    pub fn new(source: String, body: Vec<Loc<Statement>>) -> Self {
         Program {
             source: source,
+            strings: Strings::default(),
             body: body,
         }
     }
+
+    /// Returns the source code the `Program` was parsed from.
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
 }
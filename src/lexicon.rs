@@ -1,3 +1,5 @@
+use std::fmt;
+
 use grammar::OwnedSlice;
 use grammar::LiteralValue;
 use grammar::OperatorType;
@@ -49,4 +51,56 @@ pub enum Token {
     Reserved(ReservedKind),
     Identifier(OwnedSlice),
     Literal(LiteralValue),
+    TemplateOpen(OwnedSlice),   // Part of a template string followed by `${`
+    TemplateClosed(OwnedSlice), // Part of a template string ending it
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Token::EndOfProgram          => "end of program",
+            Token::Control(byte)         => return write!(f, "{}", byte as char),
+            Token::Operator(ref op)      => op.as_str(),
+            Token::Declaration(ref kind) => kind.as_str(),
+            Token::Break                 => "break",
+            Token::Do                    => "do",
+            Token::Case                  => "case",
+            Token::Else                  => "else",
+            Token::Catch                 => "catch",
+            Token::Export                => "export",
+            Token::Class                 => "class",
+            Token::Extends               => "extends",
+            Token::Return                => "return",
+            Token::While                 => "while",
+            Token::Finally               => "finally",
+            Token::Super                 => "super",
+            Token::With                  => "with",
+            Token::Continue              => "continue",
+            Token::For                   => "for",
+            Token::Switch                => "switch",
+            Token::Yield                 => "yield",
+            Token::Debugger              => "debugger",
+            Token::Function              => "function",
+            Token::This                  => "this",
+            Token::Default               => "default",
+            Token::If                    => "if",
+            Token::Throw                 => "throw",
+            Token::Import                => "import",
+            Token::Try                   => "try",
+            Token::Static                => "static",
+            Token::Reserved(kind)        => match kind {
+                ReservedKind::Enum       => "enum",
+                ReservedKind::Implements => "implements",
+                ReservedKind::Package    => "package",
+                ReservedKind::Protected  => "protected",
+                ReservedKind::Interface  => "interface",
+                ReservedKind::Private    => "private",
+                ReservedKind::Public     => "public",
+            },
+            Token::Identifier(ref ident) => ident.as_str(),
+            Token::Literal(ref value)    => return write!(f, "{}", value),
+            Token::TemplateOpen(quasi)   => return write!(f, "`{}${{", quasi.as_str()),
+            Token::TemplateClosed(quasi) => return write!(f, "`{}`", quasi.as_str()),
+        })
+    }
 }
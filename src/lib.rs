@@ -4,4 +4,11 @@ pub mod tokenizer;
 pub mod parser;
 pub mod grammar;
 pub mod codegen;
+pub mod sourcemap;
 pub mod transformer;
+pub mod visitor;
+pub mod bundler;
+pub mod treeshaker;
+pub mod scope;
+pub mod mangler;
+pub mod compressor;
@@ -5,8 +5,10 @@ use std::process;
 use std::io::prelude::*;
 use std::io::Error;
 use std::fs::File;
+use std::path::Path;
 use std::time::{ Instant, Duration };
 use docopt::Docopt;
+use rustc_serialize::{ Decodable, Decoder };
 
 pub mod error;
 pub mod lexicon;
@@ -14,7 +16,14 @@ pub mod tokenizer;
 pub mod parser;
 pub mod grammar;
 pub mod transformer;
+pub mod visitor;
+pub mod bundler;
+pub mod treeshaker;
+pub mod scope;
+pub mod mangler;
+pub mod compressor;
 pub mod codegen;
+pub mod sourcemap;
 
 fn print_ms(label: &str, duration: &Duration) {
     let delta = ((duration.as_secs() as f64) * 1000.0) +
@@ -23,12 +32,13 @@ fn print_ms(label: &str, duration: &Duration) {
     println!("{} {}ms", label, delta);
 }
 
-const VERSION: &'static str = env!("CARGO_PKG_VERSION");
-const USAGE: &'static str = "
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const USAGE: &str = "
 honeybadger
 
 Usage:
-  badger [options]
+  badger [options] [--define=DEF]...
+  badger bundle <entry> [options] [--define=DEF]...
   badger --version
 
 Options:
@@ -39,10 +49,21 @@ Options:
   -o FILE, --output=FILE       Specifies the output file.
   --pretty                     Don't minify the output.
   --ast                        Print out the Abstract Syntax Tree of the input.
+  --source-map                 Generate a source map, written next to the output
+                               file with a `.map` extension.
+  --commonjs                   Compile ES modules to CommonJS `require` calls
+                               and `exports`.
+  --tree-shake                 Remove unused exports and modules from the bundle,
+                               printing out what was removed.
+  --mangle                     Rename the variables declared in functions to
+                               the shortest names available.
+  --define=DEF                 Replace a global, or a property of one, with
+                               a literal in the minified output, written as
+                               NAME=value, strings in quotes.
 ";
 
 fn read_file(path: &str) -> Result<String, Error> {
-    let mut f = try!(File::open(path));
+    let mut f = File::open(path)?;
     let mut s = String::new();
     match f.read_to_string(&mut s) {
         Ok(_) => Ok(s),
@@ -51,21 +72,120 @@ fn read_file(path: &str) -> Result<String, Error> {
 }
 
 fn write_file(filename: &str, program: String) -> Result<(), Error> {
-    let mut f = try!(File::create(filename));
+    let mut f = File::create(filename)?;
     match f.write_all(&program.into_bytes()[..]) {
         Ok(_) => Ok(()),
         Err(err) => Err(err)
     }
 }
 
-#[derive(Debug, RustcDecodable)]
+#[derive(Debug)]
 struct Args {
+    cmd_bundle: bool,
+    arg_entry: Option<String>,
     flag_file: Option<String>,
     flag_output: Option<String>,
     flag_version: bool,
     flag_ast: bool,
     flag_pretty: bool,
     flag_string: Option<String>,
+    flag_source_map: bool,
+    flag_commonjs: bool,
+    flag_tree_shake: bool,
+    flag_mangle: bool,
+    flag_define: Vec<String>,
+}
+
+impl Decodable for Args {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Args, D::Error> {
+        d.read_struct("Args", 13, |d| Ok(Args {
+            cmd_bundle: d.read_struct_field("cmd_bundle", 0, Decodable::decode)?,
+            arg_entry: d.read_struct_field("arg_entry", 1, Decodable::decode)?,
+            flag_file: d.read_struct_field("flag_file", 2, Decodable::decode)?,
+            flag_output: d.read_struct_field("flag_output", 3, Decodable::decode)?,
+            flag_version: d.read_struct_field("flag_version", 4, Decodable::decode)?,
+            flag_ast: d.read_struct_field("flag_ast", 5, Decodable::decode)?,
+            flag_pretty: d.read_struct_field("flag_pretty", 6, Decodable::decode)?,
+            flag_string: d.read_struct_field("flag_string", 7, Decodable::decode)?,
+            flag_source_map: d.read_struct_field("flag_source_map", 8, Decodable::decode)?,
+            flag_commonjs: d.read_struct_field("flag_commonjs", 9, Decodable::decode)?,
+            flag_tree_shake: d.read_struct_field("flag_tree_shake", 10, Decodable::decode)?,
+            flag_mangle: d.read_struct_field("flag_mangle", 11, Decodable::decode)?,
+            flag_define: d.read_struct_field("flag_define", 12, Decodable::decode)?,
+        }))
+    }
+}
+
+fn defines(args: &Args) -> Vec<compressor::Define> {
+    args.flag_define.iter().map(|definition| {
+        match compressor::Define::parse(definition) {
+            Ok(define) => define,
+            Err(err)   => {
+                println!("ERR {}", err);
+                process::exit(1);
+            }
+        }
+    }).collect()
+}
+
+fn bundle(args: &Args) -> ! {
+    let entry = args.arg_entry.as_ref().expect("docopt requires <entry>");
+
+    let start = Instant::now();
+    let mut graph = match bundler::ModuleGraph::build(Path::new(entry)) {
+        Ok(graph) => graph,
+        Err(err)  => {
+            println!("ERR {}", err);
+            process::exit(1);
+        }
+    };
+
+    if args.flag_tree_shake {
+        print!("{}", treeshaker::shake(&mut graph));
+    }
+
+    graph.defines = defines(args);
+    graph.mangle = args.flag_mangle;
+
+    let bundle = match graph.generate_code(!args.flag_pretty) {
+        Ok(bundle) => bundle,
+        Err(err)   => {
+            println!("ERR {}", err);
+            process::exit(1);
+        }
+    };
+    let bundle_duration = Instant::now().duration_since(start);
+
+    match args.flag_output {
+        Some(ref output) => {
+            // Chunks are written next to the entry script
+            let directory = Path::new(output).parent().unwrap_or_else(|| Path::new(""));
+
+            for chunk in bundle.chunks {
+                let path = directory.join(&chunk.name);
+
+                if let Err(err) = write_file(&path.to_string_lossy(), chunk.code) {
+                    println!("ERR Writing {} {}", path.display(), err);
+                    process::exit(1);
+                }
+            }
+
+            if let Err(err) = write_file(output, bundle.code) {
+                println!("ERR Writing {} {}", output, err);
+                process::exit(1);
+            }
+        },
+        None => {
+            println!("{}", bundle.code);
+
+            for chunk in bundle.chunks {
+                println!("// {}\n{}", chunk.name, chunk.code);
+            }
+        },
+    }
+
+    print_ms("Bundling", &bundle_duration);
+    process::exit(0);
 }
 
 fn main() {
@@ -78,6 +198,11 @@ fn main() {
         process::exit(0);
     }
 
+    if args.cmd_bundle {
+        bundle(&args);
+    }
+
+    let defines = defines(&args);
 
     if args.flag_string.is_none() && args.flag_file.is_none() {
         println!("{}", USAGE);
@@ -85,10 +210,15 @@ fn main() {
     }
 
 
+    let source_name = match args.flag_file {
+        Some(ref path) => path.clone(),
+        None           => "input.js".to_string(),
+    };
+
     let input = match args.flag_string {
         Some(source) => source,
         None => {
-                match read_file(&args.flag_file.unwrap()) {
+                match read_file(&source_name) {
                     Ok(file) => file,
                     Err(err) => {
                         println!("ERR Couldn't read file: {:?}", err);
@@ -99,7 +229,13 @@ fn main() {
     };
 
     let start = Instant::now();
-    let mut ast = parser::parse(input);
+    let mut ast = match parser::parse(input) {
+        Ok(ast)  => ast,
+        Err(err) => {
+            println!("ERR {}", err);
+            process::exit(1);
+        }
+    };
     let parse_duration = Instant::now().duration_since(start);
 
     if args.flag_ast {
@@ -108,16 +244,36 @@ fn main() {
         process::exit(0);
     }
 
+    let mut settings = transformer::Settings::target_es5();
+
+    settings.transform_commonjs = args.flag_commonjs;
+    settings.mangle = args.flag_mangle;
+
     let start = Instant::now();
-    transformer::transform(&mut ast, transformer::Settings::target_es5());
+    if let Err(err) = transformer::transform(&mut ast, settings) {
+        println!("ERR {}", err);
+        process::exit(1);
+    }
+    if !args.flag_pretty {
+        compressor::compress(&mut ast, &defines);
+    }
     let transform_duration = Instant::now().duration_since(start);
 
     let start = Instant::now();
-    let program = codegen::generate_code(ast, !args.flag_pretty);
+    let (mut program, source_map) = if args.flag_source_map {
+        let (program, map) = codegen::generate_code_with_source_map(ast, !args.flag_pretty, &source_name);
+
+        (program, Some(map))
+    } else {
+        (codegen::generate_code(ast, !args.flag_pretty), None)
+    };
     let codegen_duration = Instant::now().duration_since(start);
 
     if args.flag_output.is_none() {
         println!("{}", program);
+        if let Some(map) = source_map {
+            println!("{}", map.to_json());
+        }
         print_ms("Parsing        ", &parse_duration);
         print_ms("Transformation ", &transform_duration);
         print_ms("Code generation", &codegen_duration);
@@ -125,7 +281,28 @@ fn main() {
         process::exit(0);
     }
 
-    match write_file(&args.flag_output.unwrap(), program) {
+    let output = args.flag_output.unwrap();
+
+    if let Some(mut map) = source_map {
+        let map_path = format!("{}.map", output);
+        let file_name = Path::new(&output).file_name()
+                                          .and_then(|name| name.to_str())
+                                          .unwrap_or(&output)
+                                          .to_string();
+
+        if !program.ends_with('\n') {
+            program.push('\n');
+        }
+        program.push_str(&format!("//# sourceMappingURL={}.map\n", file_name));
+        map.file = Some(file_name);
+
+        if let Err(err) = write_file(&map_path, map.to_json()) {
+            println!("ERR Writing {} {}", map_path, err);
+            process::exit(1);
+        }
+    }
+
+    match write_file(&output, program) {
         Ok(()) => {
             print_ms("Parsing        ", &parse_duration);
             print_ms("Transformation ", &transform_duration);
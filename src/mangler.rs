@@ -0,0 +1,136 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use grammar::OwnedSlice;
+use scope::{ BindingId, ScopeId, ScopeKind, ScopeTree };
+
+/// Characters a generated name can start with. Leaving out `_` keeps
+/// the names apart from the ones introduced by the transformer.
+const FIRST_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ$";
+
+/// Characters following the first one in a generated name.
+const NEXT_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ$0123456789_";
+
+/// Names a binding can't be given, either reserved words or globals that
+/// transformed code refers to from within functions.
+const RESERVED: &[&str] = &[
+    "break", "case", "catch", "class", "const", "continue", "debugger",
+    "default", "delete", "do", "else", "enum", "export", "extends", "false",
+    "finally", "for", "function", "if", "implements", "import", "in",
+    "instanceof", "interface", "let", "new", "null", "package", "private",
+    "protected", "public", "return", "static", "super", "switch", "this",
+    "throw", "true", "try", "typeof", "var", "void", "while", "with",
+    "yield", "await", "async", "of", "get", "set",
+
+    "arguments", "eval", "undefined", "NaN", "Infinity", "Object", "Math",
+    "Promise", "Symbol", "require", "module", "exports",
+];
+
+/// Picks the shortest possible names for the bindings declared inside of
+/// functions, giving the shortest ones to the most used bindings. `names`
+/// holds the current name of each binding in `tree`, the returned vector
+/// has the new name of each mangled binding.
+///
+/// Bindings of the program scope are left alone since other scripts can
+/// see them, and so are the bindings of functions calling `eval`.
+pub fn mangle(tree: &ScopeTree, names: &[OwnedSlice]) -> Vec<Option<String>> {
+    let mut mangled: Vec<Option<String>> = vec![None; tree.bindings.len()];
+
+    // Bindings declared in blocks belong to their function, so that
+    // lowering them to `var` can't make two of them share a name
+    let mut declared: Vec<Vec<BindingId>> = vec![Vec::new(); tree.scopes.len()];
+
+    for (id, binding) in tree.bindings.iter().enumerate() {
+        declared[tree.function_scope(binding.scope)].push(id);
+    }
+
+    // References made within each function to variables declared outside
+    // of it, whose names it can't take
+    let mut outer: Vec<Vec<usize>> = vec![Vec::new(); tree.scopes.len()];
+    let mut evaluates = vec![false; tree.scopes.len()];
+
+    for (index, reference) in tree.references.iter().enumerate() {
+        let target = reference.binding.map(|binding| tree.function_scope(tree.bindings[binding].scope));
+        let eval = reference.binding.is_none() && reference.name.as_str() == "eval";
+        let mut function = Some(tree.function_scope(reference.scope));
+
+        while let Some(scope) = function {
+            if Some(scope) == target {
+                break;
+            }
+
+            outer[scope].push(index);
+            evaluates[scope] |= eval;
+            function = parent_function(tree, scope);
+        }
+    }
+
+    // Scopes are stored parents first, so the names of outer variables
+    // are final by the time the functions using them get mangled
+    for scope in 0..tree.scopes.len() {
+        if tree.scopes[scope].kind != ScopeKind::Function || evaluates[scope] {
+            continue;
+        }
+
+        let mut taken: HashSet<String> = outer[scope].iter().map(|&index| {
+            let reference = &tree.references[index];
+
+            match reference.binding {
+                Some(binding) => match mangled[binding] {
+                    Some(ref name) => name.clone(),
+                    None           => names[binding].to_string(),
+                },
+                None => reference.name.to_string(),
+            }
+        }).collect();
+
+        let mut bindings = declared[scope].clone();
+
+        bindings.sort_by_key(|&binding| {
+            let binding = &tree.bindings[binding];
+
+            Reverse(binding.references.len() + binding.declarations.len())
+        });
+
+        let mut count = 0;
+
+        for binding in bindings {
+            let name = loop {
+                let name = short_name(count);
+
+                count += 1;
+
+                if !taken.contains(&name) && !RESERVED.contains(&name.as_str()) {
+                    break name;
+                }
+            };
+
+            taken.insert(name.clone());
+            mangled[binding] = Some(name);
+        }
+    }
+
+    mangled
+}
+
+/// Finds the function or program scope enclosing the function `scope`.
+#[inline]
+fn parent_function(tree: &ScopeTree, scope: ScopeId) -> Option<ScopeId> {
+    tree.scopes[scope].parent.map(|parent| tree.function_scope(parent))
+}
+
+/// Returns the `index`th name in order of length.
+fn short_name(mut index: usize) -> String {
+    let mut name = String::new();
+
+    name.push(FIRST_CHARS[index % FIRST_CHARS.len()] as char);
+    index /= FIRST_CHARS.len();
+
+    while index > 0 {
+        index -= 1;
+        name.push(NEXT_CHARS[index % NEXT_CHARS.len()] as char);
+        index /= NEXT_CHARS.len();
+    }
+
+    name
+}
@@ -1,6 +1,7 @@
 use lexicon::Token;
 use lexicon::Token::*;
 use tokenizer::Tokenizer;
+use error::{ ParseError, ParseResult, Expected };
 use grammar::*;
 use grammar::OperatorType::*;
 
@@ -8,7 +9,7 @@ use grammar::OperatorType::*;
 /// true, else do nothing and return false
 macro_rules! allow {
     ($parser:ident, $p:pat) => {
-        match $parser.tokenizer.peek() {
+        match $parser.tokenizer.peek()? {
             $p => {
                 $parser.tokenizer.consume();
                 true
@@ -18,90 +19,119 @@ macro_rules! allow {
     };
 }
 
+/// Returns from the current function with an error pointing at the last
+/// token read by the tokenizer. Optionally takes a list of `Expected`
+/// items that would have been valid in that place.
 macro_rules! unexpected_token {
     ($parser:ident) => ({
-        unexpected_token!($parser, $parser.tokenizer.next());
+        let token = $parser.tokenizer.next()?;
+        unexpected_token!($parser, token)
     });
-    ($parser:ident, $token:expr) => {
-        panic!("Unexpected token {:?}", $token);
-    }
+    ($parser:ident, $token:expr) => (
+        unexpected_token!($parser, $token, [])
+    );
+    ($parser:ident, $token:expr, [ $( $expected:expr ),* ]) => (
+        return Err($parser.tokenizer.unexpected($token, &[ $( $expected ),* ]))
+    );
 }
 
 /// Evaluates the `$eval` expression, then expects a semicolon or
 /// end of program. If neither is found, but a LineTermination
 /// occured on previous token, parsing will continue as if a
-/// semicolon was present. In other cases cause a panic.
+/// semicolon was present. In other cases return an error.
 macro_rules! statement {
     ($parser:ident, $eval:expr) => ({
         let value = $eval;
 
-        $parser.tokenizer.expect_semicolon();
+        $parser.tokenizer.expect_semicolon()?;
 
-        value
+        Ok(value)
     })
 }
 
 macro_rules! surround {
     ($parser:ident, $b1:expr, $eval:expr, $b2:expr) => ({
-        $parser.tokenizer.expect_control($b1);
+        $parser.tokenizer.expect_control($b1)?;
         let value = $eval;
-        $parser.tokenizer.expect_control($b2);
+        $parser.tokenizer.expect_control($b2)?;
         value
     });
 }
 
 pub struct Parser<'a> {
     tokenizer: Tokenizer<'a>,
-    allow_asi: bool,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(source: &'a str) -> Self {
         Parser {
             tokenizer: Tokenizer::new(source),
-            allow_asi: false,
         }
     }
 
+    /// Wraps the `item` in a `Loc` with a `Span` from the `start` of its
+    /// first token to the end of the last consumed token.
     #[inline]
-    fn array_expression(&mut self) -> Expression {
-        Expression::Array(self.expression_list(b']'))
+    fn loc<T>(&self, start: Span, item: T) -> Loc<T> {
+        Loc::new(Span {
+            end: self.tokenizer.last_end(),
+            ..start
+        }, item)
     }
 
+    /// Expects either a `,` separator or the `terminator` control byte,
+    /// returns true if the list has been terminated.
     #[inline]
-    fn object_member_list(&mut self) -> Vec<ObjectMember> {
+    fn list_separator(&mut self, terminator: u8) -> ParseResult<bool> {
+        match self.tokenizer.next()? {
+            Control(b',')                         => Ok(false),
+            Control(byte) if byte == terminator   => Ok(true),
+            token => unexpected_token!(self, token, [
+                Expected::Token(Control(b',')),
+                Expected::Token(Control(terminator))
+            ])
+        }
+    }
+
+    #[inline]
+    fn array_expression(&mut self) -> ParseResult<Expression> {
+        Ok(Expression::Array(self.expression_list(b']')?))
+    }
+
+    #[inline]
+    fn object_member_list(&mut self) -> ParseResult<Vec<Loc<ObjectMember>>> {
         let mut list = Vec::new();
 
         loop {
-            if self.tokenizer.allow_control() == b'}' {
+            if self.tokenizer.allow_control()? == b'}' {
                 self.tokenizer.consume();
                 break;
             }
 
-            list.push(self.object_member());
+            list.push(self.object_member()?);
 
-            if self.tokenizer.allow_control() == b'}' {
-                self.tokenizer.consume();
+            if self.list_separator(b'}')? {
                 break;
             }
-
-            self.tokenizer.expect_control(b',');
         }
 
-        list
+        Ok(list)
     }
 
     #[inline]
-    fn object_member(&mut self) -> ObjectMember {
-        match self.tokenizer.next() {
+    fn object_member(&mut self) -> ParseResult<Loc<ObjectMember>> {
+        let token = self.tokenizer.next()?;
+        let start = self.tokenizer.token_span();
+
+        let member = match token {
             Identifier(key) | Literal(LiteralString(key)) => {
-                match self.tokenizer.peek() {
+                match self.tokenizer.peek()? {
                     Control(b':') => {
                         self.tokenizer.consume();
 
                         ObjectMember::Literal {
                             key: key,
-                            value: self.expression(0),
+                            value: self.expression(0)?,
                         }
                     },
 
@@ -110,8 +140,26 @@ impl<'a> Parser<'a> {
 
                         ObjectMember::Method {
                             name: key,
-                            params: self.parameter_list(),
-                            body: self.block_body()
+                            params: self.parameter_list()?,
+                            body: self.block_body()?
+                        }
+                    },
+
+                    Identifier(name) |
+                    Literal(LiteralString(name)) if is_accessor(key) => {
+                        self.tokenizer.consume();
+
+                        if key.as_str() == "get" {
+                            ObjectMember::Getter {
+                                name: name,
+                                body: self.getter_body()?,
+                            }
+                        } else {
+                            ObjectMember::Setter {
+                                name: name,
+                                params: self.setter_parameters()?,
+                                body: self.block_body()?,
+                            }
                         }
                     },
 
@@ -121,167 +169,199 @@ impl<'a> Parser<'a> {
                 }
             },
             Control(b'[') => {
-                let key = self.expression(0);
+                let key = self.expression(0)?;
 
-                self.tokenizer.expect_control(b']');
+                self.tokenizer.expect_control(b']')?;
 
-                match self.tokenizer.next() {
+                match self.tokenizer.next()? {
                     Control(b':') => ObjectMember::Computed {
                         key: key,
-                        value: self.expression(0),
+                        value: self.expression(0)?,
                     },
                     Control(b'(') => ObjectMember::ComputedMethod {
                         name: key,
-                        params: self.parameter_list(),
-                        body: self.block_body(),
+                        params: self.parameter_list()?,
+                        body: self.block_body()?,
                     },
-                    token => unexpected_token!(self, token),
+                    token => unexpected_token!(self, token, [
+                        Expected::Token(Control(b':')),
+                        Expected::Token(Control(b'('))
+                    ]),
                 }
             },
-            token => {
-                panic!("Expected object key, got {:?}", token)
-            }
-        }
+            token => unexpected_token!(self, token, [Expected::ObjectKey])
+        };
+
+        Ok(self.loc(start, member))
     }
 
     #[inline]
-    fn object_expression(&mut self) -> Expression {
-        Expression::Object(self.object_member_list())
+    fn object_expression(&mut self) -> ParseResult<Expression> {
+        Ok(Expression::Object(self.object_member_list()?))
     }
 
     #[inline]
-    fn block_or_statement(&mut self) -> Statement {
-        match self.tokenizer.allow_control() {
+    fn block_or_statement(&mut self) -> ParseResult<Loc<Statement>> {
+        match self.tokenizer.allow_control()? {
             b'{' => {
+                let start = self.tokenizer.token_span();
                 self.tokenizer.consume();
 
-                Statement::Block {
-                    body: self.block_body_tail()
-                }
+                let block = Statement::Block {
+                    body: self.block_body_tail()?
+                };
+
+                Ok(self.loc(start, block))
             },
-            _ => {
-                let token = self.tokenizer.next();
-                self.expression_statement(token)
-            }
+            _ => self.expect_statement(),
         }
     }
 
     #[inline]
-    fn block_statement(&mut self) -> Statement {
-        Statement::Block {
-            body: self.block_body_tail(),
-        }
+    fn block_statement(&mut self) -> ParseResult<Statement> {
+        Ok(Statement::Block {
+            body: self.block_body_tail()?,
+        })
     }
 
     #[inline]
-    fn block_body_tail(&mut self) -> Vec<Statement> {
+    fn block_body_tail(&mut self) -> ParseResult<Vec<Loc<Statement>>> {
         let mut body = Vec::new();
 
         loop {
-            if self.tokenizer.allow_control() == b'}' {
+            if self.tokenizer.allow_control()? == b'}' {
                 self.tokenizer.consume();
 
                 break;
             }
 
-            body.push(
-                self.statement().expect("Unexpected end of statements block")
-            )
+            match self.statement()? {
+                Some(statement) => body.push(statement),
+                None            => return Err(ParseError::UnexpectedEndOfProgram {
+                    expected: vec![Expected::Token(Control(b'}'))]
+                }),
+            }
         }
 
-        body
+        Ok(body)
     }
 
     #[inline]
-    fn block_body(&mut self) -> Vec<Statement> {
-        self.tokenizer.expect_control(b'{');
+    fn block_body(&mut self) -> ParseResult<Vec<Loc<Statement>>> {
+        self.tokenizer.expect_control(b'{')?;
         self.block_body_tail()
     }
 
-    fn arrow_function_expression(&mut self, p: Option<Expression>) -> Expression {
+    fn arrow_function_expression(&mut self, p: Option<Loc<Expression>>) -> ParseResult<Expression> {
         let params: Vec<Parameter> = match p {
             None => Vec::new(),
-            Some(Expression::Identifier(name)) => {
-                vec![Parameter { name: name }]
-            },
-            Some(Expression::Sequence(mut list)) => {
-                list.drain(..).map(|expression| {
-                    match expression {
-                        Expression::Identifier(name) => Parameter { name: name },
-                        _ => panic!("Cannot cast {:?} to a parameter", expression),
+            Some(Loc {
+                item: Expression::Sequence(mut list),
+                ..
+            }) => {
+                let mut params = Vec::with_capacity(list.len());
+                let last = list.len() - 1;
+
+                for (index, expression) in list.drain(..).enumerate() {
+                    let span = expression.span;
+                    let parameter = self.parameter_from_expression(expression)?;
+
+                    // Rest parameter has to be the last one
+                    if parameter.rest && index != last {
+                        return Err(ParseError::InvalidPattern {
+                            start: span.start,
+                            end: span.end,
+                        });
                     }
-                }).collect()
+
+                    params.push(parameter);
+                }
+
+                params
             },
-            _ =>
-                panic!("Cannot cast {:?} to parameters", p),
+            Some(expression) => vec![self.parameter_from_expression(expression)?],
         };
 
-        let body = match self.tokenizer.allow_control() {
+        let body = match self.tokenizer.allow_control()? {
             b'{' => {
+                let start = self.tokenizer.token_span();
                 self.tokenizer.consume();
 
-                Statement::Block {
-                    body: self.block_body_tail()
-                }
+                let block = Statement::Block {
+                    body: self.block_body_tail()?
+                };
+
+                self.loc(start, block)
             }
-            _    => self.expression(0).into()
+            _    => self.expression(0)?.into()
         };
 
-        Expression::ArrowFunction {
+        Ok(Expression::ArrowFunction {
             params: params,
             body: Box::new(body)
-        }
+        })
     }
 
     #[inline]
-    fn prefix_expression(&mut self, operator: OperatorType) -> Expression {
+    fn prefix_expression(&mut self, operator: OperatorType) -> ParseResult<Expression> {
         if !operator.prefix() {
-            panic!("Unexpected operator {:?}", operator);
+            unexpected_token!(self, Operator(operator), [Expected::Expression]);
         }
 
-        Expression::Prefix {
+        // The arguments following `new x` are parsed as a call of it
+        let bp = match operator {
+            New    => 17,
+            Spread => Assign.binding_power(),
+            _      => 15,
+        };
+
+        Ok(Expression::Prefix {
             operator: operator,
-            operand: Box::new(self.expression(15)),
-        }
+            operand: Box::new(self.expression(bp)?),
+        })
     }
 
     #[inline]
-    fn infix_expression(&mut self, left: Expression, bp: u8, op: OperatorType) -> Expression {
-        match op {
+    fn infix_expression(&mut self, left: Loc<Expression>, bp: u8, op: OperatorType) -> ParseResult<Expression> {
+        Ok(match op {
             Increment | Decrement => Expression::Postfix {
                 operator: op,
                 operand: Box::new(left),
             },
 
-            Accessor => Expression::member(left, self.tokenizer.expect_identifier()),
+            Accessor => Expression::member(left, self.tokenizer.expect_property_name()?),
 
+            // Both branches can be assignments
             Conditional => Expression::Conditional {
                 test: Box::new(left),
-                consequent: Box::new(self.expression(bp)),
+                consequent: Box::new(self.expression(Assign.binding_power())?),
                 alternate: {
-                    self.tokenizer.expect_control(b':');
-                    Box::new(self.expression(bp))
+                    self.tokenizer.expect_control(b':')?;
+                    Box::new(self.expression(Assign.binding_power())?)
                 }
             },
 
-            FatArrow => self.arrow_function_expression(Some(left)),
+            FatArrow => self.arrow_function_expression(Some(left))?,
 
             _ => {
                 if !op.infix() {
-                    panic!("Unexpected operator {:?}", op);
+                    unexpected_token!(self, Operator(op));
                 }
 
-                if op.assignment() {
-                    // TODO: verify that left is assignable
-                }
+                // TODO: verify that left is assignable when `op.assignment()`
+
+                let left = match op {
+                    Assign => self.assignment_target(left)?,
+                    _      => left,
+                };
 
-                Expression::binary(left, op, self.expression(bp))
+                Expression::binary(left, op, self.expression(bp)?)
             }
-        }
+        })
     }
 
-    fn function_expression(&mut self) -> Expression {
-        let name = match self.tokenizer.peek() {
+    fn function_expression(&mut self) -> ParseResult<Expression> {
+        let name = match self.tokenizer.peek()? {
             Identifier(name) => {
                 self.tokenizer.consume();
 
@@ -290,126 +370,198 @@ impl<'a> Parser<'a> {
             _                => None
         };
 
-        Expression::Function {
+        self.tokenizer.expect_control(b'(')?;
+
+        Ok(Expression::Function {
             name: name,
-            params: self.parameter_list(),
-            body: self.block_body(),
-        }
+            params: self.parameter_list()?,
+            body: self.block_body()?,
+        })
     }
 
     #[inline]
-    fn paren_expression(&mut self) -> Expression {
-        if self.tokenizer.allow_control() == b')' {
+    fn paren_expression(&mut self) -> ParseResult<Expression> {
+        if self.tokenizer.allow_control()? == b')' {
             self.tokenizer.consume();
 
-            match self.tokenizer.next() {
+            match self.tokenizer.next()? {
                 Operator(FatArrow) => {},
-                token              => unexpected_token!(self, token)
+                token              => unexpected_token!(self, token, [
+                    Expected::Token(Operator(FatArrow))
+                ])
             }
 
             return self.arrow_function_expression(None);
         }
 
-        let expression = self.sequence_or_expression();
-        self.tokenizer.expect_control(b')');
+        let expression = self.sequence_or_expression()?;
+        self.tokenizer.expect_control(b')')?;
 
-        expression
+        Ok(expression.item)
     }
 
     #[inline]
-    fn sequence_or_expression_from_token(&mut self, token: Token) -> Expression {
-        let first = self.expression_from_token(token, 0);
+    /// Reads the parts of a template string, starting with the `token`
+    /// containing its first part.
+    fn template(&mut self, mut token: Token) -> ParseResult<(Vec<OwnedSlice>, Vec<Loc<Expression>>)> {
+        let mut quasis = Vec::new();
+        let mut expressions = Vec::new();
+
+        loop {
+            match token {
+                TemplateOpen(quasi) => {
+                    quasis.push(quasi);
+                    expressions.push(self.sequence_or_expression()?);
+
+                    self.tokenizer.expect_control(b'}')?;
+
+                    token = self.tokenizer.template_continuation()?;
+                },
+
+                TemplateClosed(quasi) => {
+                    quasis.push(quasi);
+
+                    return Ok((quasis, expressions));
+                },
+
+                _ => unexpected_token!(self, token)
+            }
+        }
+    }
+
+    fn sequence_or_expression_from_token(&mut self, token: Token) -> ParseResult<Loc<Expression>> {
+        let first = self.expression_from_token(token, 0)?;
         self.sequence_or(first)
     }
 
     #[inline]
-    fn sequence_or(&mut self, first: Expression) -> Expression {
-        match self.tokenizer.allow_control() {
+    fn sequence_or(&mut self, first: Loc<Expression>) -> ParseResult<Loc<Expression>> {
+        Ok(match self.tokenizer.allow_control()? {
             b',' => {
                 self.tokenizer.consume();
 
-                let mut list = vec![first, self.expression(0)];
+                let start = first.span;
+                let mut list = vec![first, self.expression(0)?];
 
-                while self.tokenizer.allow_control() == b',' {
+                while self.tokenizer.allow_control()? == b',' {
                     self.tokenizer.consume();
 
-                    list.push(self.expression(0));
+                    list.push(self.expression(0)?);
                 }
 
-                Expression::Sequence(list)
+                self.loc(start, Expression::Sequence(list))
             },
             _ => first
-        }
+        })
     }
 
     #[inline]
-    fn sequence_or_expression(&mut self) -> Expression {
-        let token = self.tokenizer.next();
+    fn sequence_or_expression(&mut self) -> ParseResult<Loc<Expression>> {
+        let token = self.tokenizer.next()?;
         self.sequence_or_expression_from_token(token)
     }
 
-    fn expression_list(&mut self, terminator: u8) -> Vec<Expression> {
+    fn expression_list(&mut self, terminator: u8) -> ParseResult<Vec<Loc<Expression>>> {
         let mut list = Vec::new();
 
         loop {
-            if self.tokenizer.allow_control() == terminator {
+            if self.tokenizer.allow_control()? == terminator {
                 self.tokenizer.consume();
                 break;
             }
 
-            list.push(self.expression(0));
+            list.push(self.expression(0)?);
 
-            if self.tokenizer.allow_control() == terminator {
-                self.tokenizer.consume();
+            if self.list_separator(terminator)? {
                 break;
             }
-
-            self.tokenizer.expect_control(b',');
         }
 
-        list
+        Ok(list)
     }
 
     #[inline]
-    fn expression(&mut self, lbp: u8) -> Expression {
-        let token = self.tokenizer.next();
+    fn expression(&mut self, lbp: u8) -> ParseResult<Loc<Expression>> {
+        let token = self.tokenizer.next()?;
         self.expression_from_token(token, lbp)
     }
 
     #[inline]
-    fn expression_from_token(&mut self, token: Token, lbp: u8) -> Expression {
+    fn expression_from_token(&mut self, token: Token, lbp: u8) -> ParseResult<Loc<Expression>> {
+        let start = self.tokenizer.token_span();
+
         let left = match token {
-            This              => Expression::This,
-            Literal(value)    => Expression::Literal(value),
-            Identifier(value) => value.into(),
-            Operator(optype)  => self.prefix_expression(optype),
-            Control(b'(')     => self.paren_expression(),
-            Control(b'[')     => self.array_expression(),
-            Control(b'{')     => self.object_expression(),
-            Function          => self.function_expression(),
-            token             => unexpected_token!(self, token)
+            This                   => Expression::This,
+            Super                  => Expression::Super,
+            Literal(value)         => Expression::Literal(value),
+            TemplateOpen(_)        |
+            TemplateClosed(_)      => {
+                let (quasis, expressions) = self.template(token)?;
+
+                Expression::TemplateLiteral {
+                    quasis: quasis,
+                    expressions: expressions,
+                }
+            },
+            Identifier(value)      => value.into(),
+            // Division after `)` or `}` that is actually a regular expression
+            Operator(Division)     |
+            Operator(DivideAssign) => {
+                let token = self.tokenizer.reread_as_regex()?;
+
+                return self.expression_from_token(token, lbp);
+            },
+            Operator(optype)       => self.prefix_expression(optype)?,
+            Control(b'(')          => self.paren_expression()?,
+            Control(b'[')          => self.array_expression()?,
+            Control(b'{')          => self.object_expression()?,
+            Function               => self.function_expression()?,
+            Import                 => self.import_expression()?,
+            token                  => unexpected_token!(self, token, [Expected::Expression])
         };
 
+        let left = self.loc(start, left);
+
         self.complex_expression(left, lbp)
     }
 
-    fn complex_expression(&mut self, mut left: Expression, lbp: u8) -> Expression {
+    fn complex_expression(&mut self, mut left: Loc<Expression>, lbp: u8) -> ParseResult<Loc<Expression>> {
         loop {
-            left = match self.tokenizer.peek() {
+            let start = left.span;
+
+            let expression = match self.tokenizer.peek()? {
                 Operator(op) => {
                     let rbp = op.binding_power();
 
-                    if lbp > rbp {
+                    if lbp > rbp || (lbp == rbp && !op.right_associative()) {
                         break;
                     }
 
                     self.tokenizer.consume();
 
-                    self.infix_expression(left, rbp, op)
+                    self.infix_expression(left, rbp, op)?
+                },
+
+                // Calls and tagged templates bind like `new` with arguments,
+                // member access binds tighter than either
+                TemplateOpen(_)   |
+                TemplateClosed(_) => {
+                    if lbp >= 17 {
+                        break;
+                    }
+
+                    let token = self.tokenizer.next()?;
+                    let (quasis, expressions) = self.template(token)?;
+
+                    Expression::TaggedTemplate {
+                        tag: Box::new(left),
+                        quasis: quasis,
+                        expressions: expressions,
+                    }
                 },
 
                 Control(b'(') => {
-                    if lbp > 0 {
+                    if lbp >= 17 {
                         break;
                     }
 
@@ -417,20 +569,20 @@ impl<'a> Parser<'a> {
 
                     Expression::Call {
                         callee: Box::new(left),
-                        arguments: self.expression_list(b')'),
+                        arguments: self.expression_list(b')')?,
                     }
                 },
 
                 Control(b'[') => {
-                    if lbp > 0 {
+                    if lbp >= 18 {
                         break;
                     }
 
                     self.tokenizer.consume();
 
-                    let property = self.sequence_or_expression();
+                    let property = self.sequence_or_expression()?;
 
-                    self.tokenizer.expect_control(b']');
+                    self.tokenizer.expect_control(b']')?;
 
                     Expression::ComputedMember {
                         object: Box::new(left),
@@ -439,32 +591,250 @@ impl<'a> Parser<'a> {
                 },
 
                 _ => break
+            };
+
+            left = self.loc(start, expression);
+        }
+
+        Ok(left)
+    }
+
+    /// Converts an array or object expression that turned out to be on the
+    /// left side of an assignment into a destructuring pattern.
+    #[inline]
+    fn assignment_target(&self, expression: Loc<Expression>) -> ParseResult<Loc<Expression>> {
+        match expression.item {
+            Expression::Array(_)  |
+            Expression::Object(_) => {
+                let span = expression.span;
+                let pattern = self.pattern_from_expression(expression, true)?;
+
+                Ok(Loc::new(span, Expression::Pattern(pattern)))
+            },
+            _ => Ok(expression)
+        }
+    }
+
+    /// Reinterprets an already parsed `expression` as a pattern. Member
+    /// expressions are only valid targets if the pattern is a part of an
+    /// `assignment`, rather than a list of parameters.
+    fn pattern_from_expression(&self, expression: Loc<Expression>, assignment: bool) -> ParseResult<Pattern> {
+        let span = expression.span;
+
+        Ok(match expression.item {
+            Expression::Identifier(name) => Pattern::Identifier(name),
+
+            Expression::Pattern(pattern) => pattern,
+
+            Expression::Array(items) => {
+                let mut elements = Vec::with_capacity(items.len());
+
+                for item in items {
+                    elements.push(Some(self.pattern_from_expression(item, assignment)?));
+                }
+
+                Pattern::Array(elements)
+            },
+
+            Expression::Object(members) => {
+                let mut properties = Vec::with_capacity(members.len());
+
+                for member in members {
+                    let span = member.span;
+
+                    properties.push(match member.item {
+                        ObjectMember::Shorthand {
+                            key,
+                        } => PropertyPattern {
+                            key: key,
+                            value: Pattern::Identifier(key),
+                        },
+
+                        ObjectMember::Literal {
+                            key,
+                            value,
+                        } => PropertyPattern {
+                            key: key,
+                            value: self.pattern_from_expression(value, assignment)?,
+                        },
+
+                        _ => return Err(ParseError::InvalidPattern {
+                            start: span.start,
+                            end: span.end,
+                        })
+                    });
+                }
+
+                Pattern::Object(properties)
+            },
+
+            Expression::Prefix {
+                operator: Spread,
+                operand,
+            } => Pattern::Rest(Box::new(self.pattern_from_expression(*operand, assignment)?)),
+
+            Expression::Binary {
+                left,
+                operator: Assign,
+                right,
+            } => Pattern::Default {
+                pattern: Box::new(self.pattern_from_expression(*left, assignment)?),
+                value: right,
+            },
+
+            item @ Expression::Member { .. }         |
+            item @ Expression::ComputedMember { .. } if assignment => {
+                Pattern::Expression(Box::new(Loc::new(span, item)))
+            },
+
+            _ => return Err(ParseError::InvalidPattern {
+                start: span.start,
+                end: span.end,
+            })
+        })
+    }
+
+    /// Reinterprets an expression in the head of an arrow function as
+    /// a parameter, which can have a default value or be a rest parameter.
+    fn parameter_from_expression(&self, expression: Loc<Expression>) -> ParseResult<Parameter> {
+        Ok(match expression.item {
+            Expression::Binary {
+                left,
+                operator: Assign,
+                right,
+            } => Parameter {
+                name: self.pattern_from_expression(*left, false)?,
+                default: Some(*right),
+                rest: false,
+            },
+
+            Expression::Prefix {
+                operator: Spread,
+                operand,
+            } => Parameter {
+                name: self.pattern_from_expression(*operand, false)?,
+                default: None,
+                rest: true,
+            },
+
+            _ => self.pattern_from_expression(expression, false)?.into(),
+        })
+    }
+
+    /// Reads a binding pattern of a declaration or a parameter.
+    fn pattern(&mut self) -> ParseResult<Pattern> {
+        match self.tokenizer.next()? {
+            Identifier(name) => Ok(Pattern::Identifier(name)),
+            Control(b'[')    => self.array_pattern(),
+            Control(b'{')    => self.object_pattern(),
+            token            => unexpected_token!(self, token, [
+                Expected::Identifier,
+                Expected::Token(Control(b'[')),
+                Expected::Token(Control(b'{'))
+            ])
+        }
+    }
+
+    /// Wraps the `pattern` in a `Pattern::Default` if it's followed
+    /// by an `=` and a default value.
+    #[inline]
+    fn pattern_default(&mut self, pattern: Pattern) -> ParseResult<Pattern> {
+        if !allow!(self, Operator(Assign)) {
+            return Ok(pattern);
+        }
+
+        Ok(Pattern::Default {
+            pattern: Box::new(pattern),
+            value: Box::new(self.expression(0)?),
+        })
+    }
+
+    fn array_pattern(&mut self) -> ParseResult<Pattern> {
+        let mut elements = Vec::new();
+
+        loop {
+            match self.tokenizer.allow_control()? {
+                b']' => {
+                    self.tokenizer.consume();
+                    break;
+                },
+                b',' => {
+                    self.tokenizer.consume();
+                    elements.push(None);
+                    continue;
+                },
+                _ => {}
+            }
+
+            if allow!(self, Operator(Spread)) {
+                let rest = self.pattern()?;
+
+                elements.push(Some(Pattern::Rest(Box::new(rest))));
+                self.tokenizer.expect_control(b']')?;
+                break;
+            }
+
+            let element = self.pattern()?;
+
+            elements.push(Some(self.pattern_default(element)?));
+
+            if self.list_separator(b']')? {
+                break;
             }
         }
 
-        left
+        Ok(Pattern::Array(elements))
+    }
+
+    fn object_pattern(&mut self) -> ParseResult<Pattern> {
+        let mut properties = Vec::new();
+
+        loop {
+            let key = match self.tokenizer.next()? {
+                Control(b'}') => break,
+                Identifier(key) | Literal(LiteralString(key)) => key,
+                token => unexpected_token!(self, token, [Expected::ObjectKey])
+            };
+
+            let value = if allow!(self, Control(b':')) {
+                self.pattern()?
+            } else {
+                Pattern::Identifier(key)
+            };
+
+            properties.push(PropertyPattern {
+                key: key,
+                value: self.pattern_default(value)?,
+            });
+
+            if self.list_separator(b'}')? {
+                break;
+            }
+        }
+
+        Ok(Pattern::Object(properties))
     }
 
     /// Helper for the `for` loops that doesn't consume semicolons
     fn variable_declaration(
         &mut self, kind: VariableDeclarationKind
-    ) -> Statement {
+    ) -> ParseResult<Statement> {
         let mut declarators = Vec::new();
 
         loop {
             declarators.push(VariableDeclarator {
-                name: self.tokenizer.expect_identifier(),
-                value: match self.tokenizer.peek() {
+                name: self.pattern()?,
+                value: match self.tokenizer.peek()? {
                     Operator(Assign) => {
                         self.tokenizer.consume();
 
-                        Some(self.expression(0))
+                        Some(self.expression(0)?)
                     },
                     _ => None
                 }
             });
 
-            if self.tokenizer.allow_control() == b',' {
+            if self.tokenizer.allow_control()? == b',' {
                 self.tokenizer.consume();
 
                 continue;
@@ -473,351 +843,873 @@ impl<'a> Parser<'a> {
             break;
         }
 
-        Statement::VariableDeclaration {
+        Ok(Statement::VariableDeclaration {
             kind: kind,
             declarators: declarators,
-        }
+        })
     }
 
     #[inline]
     fn variable_declaration_statement(
         &mut self, kind: VariableDeclarationKind
-    ) -> Statement {
-        statement!(self, self.variable_declaration(kind))
+    ) -> ParseResult<Statement> {
+        statement!(self, self.variable_declaration(kind)?)
     }
 
     #[inline]
-    fn labeled_or_expression_statement(&mut self, label: OwnedSlice) -> Statement {
-        match self.tokenizer.allow_control() {
+    fn labeled_or_expression_statement(&mut self, label: OwnedSlice) -> ParseResult<Statement> {
+        let start = self.tokenizer.token_span();
+
+        match self.tokenizer.allow_control()? {
             b':' => {
                 self.tokenizer.consume();
 
-                Statement::Labeled {
+                Ok(Statement::Labeled {
                     label: label,
-                    body: Box::new(self.statement().expect("Expected statement")),
-                }
+                    body: Box::new(self.expect_statement()?),
+                })
             },
             _ => {
-                let first = self.complex_expression(label.into(), 0);
+                let first = self.loc(start, label.into());
+                let first = self.complex_expression(first, 0)?;
 
-                statement!(self, self.sequence_or(first).into())
+                statement!(self, self.sequence_or(first)?.into())
             }
         }
     }
 
     #[inline]
-    fn expression_statement(&mut self, token: Token) -> Statement {
-        statement!(self, self.sequence_or_expression_from_token(token).into())
+    fn expression_statement(&mut self, token: Token) -> ParseResult<Statement> {
+        statement!(self, self.sequence_or_expression_from_token(token)?.into())
     }
 
+    /// Checks if the statement ends after current keyword, either by
+    /// a semicolon, closing brace, end of program or line termination.
     #[inline]
-    fn return_statement(&mut self) -> Statement {
+    fn statement_ends(&mut self) -> ParseResult<bool> {
+        Ok(match self.tokenizer.peek()? {
+            EndOfProgram  |
+            Control(b';') |
+            Control(b'}') => true,
+            _             => self.tokenizer.line_terminated(),
+        })
+    }
+
+    #[inline]
+    fn return_statement(&mut self) -> ParseResult<Statement> {
         statement!(self, Statement::Return {
-            value: match self.tokenizer.peek() {
-                EndOfProgram  => None,
-                Control(b';') => None,
-                _             => {
-                    if self.allow_asi {
-                        None
-                    } else {
-                        Some(self.sequence_or_expression())
-                    }
-                }
+            value: if self.statement_ends()? {
+                None
+            } else {
+                Some(self.sequence_or_expression()?)
             }
         })
     }
 
     #[inline]
-    fn throw_statement(&mut self) -> Statement {
+    fn throw_statement(&mut self) -> ParseResult<Statement> {
         statement!(self, Statement::Throw {
-            value: self.sequence_or_expression()
+            value: self.sequence_or_expression()?
+        })
+    }
+
+    fn try_statement(&mut self) -> ParseResult<Statement> {
+        let block = self.block_body()?;
+
+        let handler = if allow!(self, Catch) {
+            let param = if self.tokenizer.allow_control()? == b'(' {
+                Some(surround!(self, b'(', self.pattern()?, b')'))
+            } else {
+                None
+            };
+
+            Some(CatchClause {
+                param: param,
+                body: self.block_body()?,
+            })
+        } else {
+            None
+        };
+
+        let finalizer = if allow!(self, Finally) {
+            Some(self.block_body()?)
+        } else {
+            None
+        };
+
+        if handler.is_none() && finalizer.is_none() {
+            let token = self.tokenizer.next()?;
+
+            unexpected_token!(self, token, [
+                Expected::Token(Catch),
+                Expected::Token(Finally)
+            ]);
+        }
+
+        Ok(Statement::Try {
+            block: block,
+            handler: handler,
+            finalizer: finalizer,
         })
     }
 
     #[inline]
-    fn break_statement(&mut self) -> Statement {
+    fn break_statement(&mut self) -> ParseResult<Statement> {
         statement!(self, Statement::Break {
-            label: match self.tokenizer.peek() {
-                EndOfProgram  => None,
-                Control(b';') => None,
-                _             => {
-                    if self.allow_asi {
-                        None
-                    } else {
-                        Some(self.tokenizer.expect_identifier())
-                    }
-                }
+            label: if self.statement_ends()? {
+                None
+            } else {
+                Some(self.tokenizer.expect_identifier()?)
             }
         })
     }
 
-    fn if_statement(&mut self) -> Statement {
-        let test = surround!(self, b'(', self.expression(0), b')');
-        let consequent = Box::new(self.block_or_statement());
+    #[inline]
+    fn continue_statement(&mut self) -> ParseResult<Statement> {
+        statement!(self, Statement::Continue {
+            label: if self.statement_ends()? {
+                None
+            } else {
+                Some(self.tokenizer.expect_identifier()?)
+            }
+        })
+    }
+
+    fn if_statement(&mut self) -> ParseResult<Statement> {
+        let test = surround!(self, b'(', self.expression(0)?, b')');
+        let consequent = Box::new(self.block_or_statement()?);
         let alternate = if allow!(self, Else) {
             if allow!(self, If) {
-                Some(Box::new(self.if_statement()))
+                let start = self.tokenizer.token_span();
+                let alternate = self.if_statement()?;
+
+                Some(Box::new(self.loc(start, alternate)))
             } else {
-                Some(Box::new(self.block_or_statement()))
+                Some(Box::new(self.block_or_statement()?))
             }
         } else {
             None
         };
 
-        Statement::If {
+        Ok(Statement::If {
             test: test,
             consequent: consequent,
             alternate: alternate,
-        }
+        })
+    }
+
+    #[inline]
+    fn while_statement(&mut self) -> ParseResult<Statement> {
+        Ok(Statement::While {
+            test: surround!(self, b'(', self.expression(0)?, b')'),
+            body: Box::new(self.block_or_statement()?),
+        })
     }
 
     #[inline]
-    fn while_statement(&mut self) -> Statement {
-        Statement::While {
-            test: surround!(self, b'(', self.expression(0), b')'),
-            body: Box::new(self.block_or_statement()),
+    fn do_while_statement(&mut self) -> ParseResult<Statement> {
+        let body = Box::new(self.block_or_statement()?);
+
+        match self.tokenizer.next()? {
+            While => {},
+            token => unexpected_token!(self, token, [Expected::Token(While)])
         }
+
+        let test = surround!(self, b'(', self.sequence_or_expression()?, b')');
+
+        // Semicolon after `do-while` is inserted even on the same line
+        allow!(self, Control(b';'));
+
+        Ok(Statement::DoWhile {
+            body: body,
+            test: test,
+        })
+    }
+
+    fn switch_statement(&mut self) -> ParseResult<Statement> {
+        let discriminant = surround!(self, b'(', self.sequence_or_expression()?, b')');
+        let mut cases = Vec::new();
+
+        self.tokenizer.expect_control(b'{')?;
+
+        loop {
+            let test = match self.tokenizer.next()? {
+                Case          => Some(self.sequence_or_expression()?),
+                Default       => None,
+                Control(b'}') => break,
+                token         => unexpected_token!(self, token, [
+                    Expected::Token(Case),
+                    Expected::Token(Default),
+                    Expected::Token(Control(b'}'))
+                ])
+            };
+
+            self.tokenizer.expect_control(b':')?;
+
+            let mut consequent = Vec::new();
+
+            loop {
+                match self.tokenizer.peek()? {
+                    Case | Default | Control(b'}') => break,
+                    _                              => {}
+                }
+
+                consequent.push(self.expect_statement()?);
+            }
+
+            cases.push(SwitchCase {
+                test: test,
+                consequent: consequent,
+            });
+        }
+
+        Ok(Statement::Switch {
+            discriminant: discriminant,
+            cases: cases,
+        })
     }
 
     #[inline]
-    fn for_statement(&mut self) -> Statement {
-        self.tokenizer.expect_control(b'(');
+    fn for_statement(&mut self) -> ParseResult<Statement> {
+        self.tokenizer.expect_control(b'(')?;
+
+        let token = self.tokenizer.next()?;
+        let start = self.tokenizer.token_span();
 
-        let init = match self.tokenizer.next() {
+        let init = match token {
             Control(b';')     => None,
 
-            Declaration(kind) => Some(Box::new(self.variable_declaration(kind))),
+            Declaration(kind) => {
+                let declaration = self.variable_declaration(kind)?;
+
+                Some(Box::new(self.loc(start, declaration)))
+            },
 
             token             => {
-                let expression = self.sequence_or_expression_from_token(token);
+                let mut expression = self.sequence_or_expression_from_token(token)?;
 
                 if let Expression::Binary {
                     left,
                     operator: In,
                     right,
-                } = expression {
+                } = expression.item {
                     return self.for_in_statement_from_expressions(*left, *right);
                 }
 
+                if let Identifier(ident) = self.tokenizer.peek()? {
+                    if ident.as_str() == "of" {
+                        expression = self.assignment_target(expression)?;
+                    }
+                }
+
                 Some(Box::new(expression.into()))
             },
         };
-        if init.is_some() {
-            match self.tokenizer.next() {
+
+        if let Some(init) = init {
+            match self.tokenizer.next()? {
                 Operator(In)      => return self.for_in_statement(init),
-                Identifier(ident) => {
-                    let slice = ident.as_str();
-                    if slice != "of" {
-                        panic!("Unexpected identifier {}", slice);
-                    }
-                    return self.for_of_statement(init.unwrap());
+                Identifier(ident) if ident.as_str() == "of" => {
+                    return self.for_of_statement(init);
                 },
-                Control(b';')     => {},
-                token             => unexpected_token!(self, token),
+                Control(b';')     => return self.for_statement_tail(Some(init)),
+                token             => unexpected_token!(self, token, [
+                    Expected::Token(Control(b';')),
+                    Expected::Token(Operator(In)),
+                    Expected::Token(Identifier("of".into()))
+                ]),
             }
         }
 
-        let test = match self.tokenizer.next() {
+        self.for_statement_tail(None)
+    }
+
+    /// Parses the remainder of a regular `for` loop, after the semicolon
+    /// terminating its `init` part.
+    fn for_statement_tail(&mut self, init: Option<Box<Loc<Statement>>>) -> ParseResult<Statement> {
+        let test = match self.tokenizer.next()? {
             Control(b';') => None,
-            token         => Some(self.sequence_or_expression_from_token(token)),
+            token         => Some(self.sequence_or_expression_from_token(token)?),
         };
-        if !test.is_none() {
-            self.tokenizer.expect_control(b';')
+        if test.is_some() {
+            self.tokenizer.expect_control(b';')?;
         }
 
-        let update = match self.tokenizer.next() {
+        let update = match self.tokenizer.next()? {
             Control(b')') => None,
-            token         => Some(self.sequence_or_expression_from_token(token)),
+            token         => Some(self.sequence_or_expression_from_token(token)?),
         };
-        if !update.is_none() {
-            self.tokenizer.expect_control(b')');
+        if update.is_some() {
+            self.tokenizer.expect_control(b')')?;
         }
 
-        Statement::For {
+        Ok(Statement::For {
             init: init,
             test: test,
             update: update,
-            body: Box::new(self.block_or_statement()),
-        }
+            body: Box::new(self.block_or_statement()?),
+        })
     }
 
     fn for_in_statement_from_expressions(
-        &mut self, left: Expression, right: Expression
-    ) -> Statement {
-        let left = Box::new(left.into());
-        self.tokenizer.expect_control(b')');
+        &mut self, left: Loc<Expression>, right: Loc<Expression>
+    ) -> ParseResult<Statement> {
+        let left = Box::new(self.assignment_target(left)?.into());
+        self.tokenizer.expect_control(b')')?;
 
-        Statement::ForIn {
+        Ok(Statement::ForIn {
             left: left,
             right: right,
-            body: Box::new(self.block_or_statement()),
-        }
+            body: Box::new(self.block_or_statement()?),
+        })
     }
 
-    fn for_in_statement(&mut self, left: Option<Box<Statement>>) -> Statement {
-        let left = left.unwrap();
-        let right = self.sequence_or_expression();
-        self.tokenizer.expect_control(b')');
+    fn for_in_statement(&mut self, left: Box<Loc<Statement>>) -> ParseResult<Statement> {
+        let right = self.sequence_or_expression()?;
+        self.tokenizer.expect_control(b')')?;
 
-        Statement::ForIn {
+        Ok(Statement::ForIn {
             left: left,
             right: right,
-            body: Box::new(self.block_or_statement()),
-        }
+            body: Box::new(self.block_or_statement()?),
+        })
     }
 
-    fn for_of_statement(&mut self, left: Box<Statement>) -> Statement {
-        let right = self.sequence_or_expression();
-        self.tokenizer.expect_control(b')');
+    fn for_of_statement(&mut self, left: Box<Loc<Statement>>) -> ParseResult<Statement> {
+        let right = self.sequence_or_expression()?;
+        self.tokenizer.expect_control(b')')?;
 
-        Statement::ForOf {
+        Ok(Statement::ForOf {
             left: left,
             right: right,
-            body: Box::new(self.block_or_statement()),
-        }
+            body: Box::new(self.block_or_statement()?),
+        })
     }
 
-    fn parameter_list(&mut self) -> Vec<Parameter> {
+    fn parameter_list(&mut self) -> ParseResult<Vec<Parameter>> {
         let mut list = Vec::new();
 
         loop {
-            if self.tokenizer.allow_control() == b')' {
+            if self.tokenizer.allow_control()? == b')' {
                 self.tokenizer.consume();
                 break;
             }
 
-            list.push(self.parameter());
+            let parameter = self.parameter()?;
+            let rest = parameter.rest;
 
-            if self.tokenizer.allow_control() == b')' {
-                self.tokenizer.consume();
+            list.push(parameter);
+
+            // Rest parameter has to be the last one
+            if rest {
+                self.tokenizer.expect_control(b')')?;
                 break;
             }
 
-            self.tokenizer.expect_control(b',');
+            if self.list_separator(b')')? {
+                break;
+            }
         }
 
-        list
+        Ok(list)
     }
 
     #[inline]
-    fn parameter(&mut self) -> Parameter {
-        Parameter {
-            name: self.tokenizer.expect_identifier()
-        }
+    fn parameter(&mut self) -> ParseResult<Parameter> {
+        let rest = allow!(self, Operator(Spread));
+        let name = self.pattern()?;
+        let default = if !rest && allow!(self, Operator(Assign)) {
+            Some(self.expression(0)?)
+        } else {
+            None
+        };
+
+        Ok(Parameter {
+            name: name,
+            default: default,
+            rest: rest,
+        })
     }
 
     #[inline]
-    fn function_statement(&mut self) -> Statement {
-        let name = self.tokenizer.expect_identifier();
+    fn function_statement(&mut self) -> ParseResult<Statement> {
+        let name = self.tokenizer.expect_identifier()?;
 
-        self.tokenizer.expect_control(b'(');
+        self.tokenizer.expect_control(b'(')?;
 
-        Statement::Function {
+        Ok(Statement::Function {
             name: name,
-            params: self.parameter_list(),
-            body: self.block_body(),
-        }
+            params: self.parameter_list()?,
+            body: self.block_body()?,
+        })
     }
 
-    fn class_member(&mut self, name: OwnedSlice, is_static: bool) -> ClassMember {
-        match self.tokenizer.peek() {
+    fn class_member(&mut self, name: OwnedSlice, is_static: bool) -> ParseResult<ClassMember> {
+        Ok(match self.tokenizer.next()? {
+            Identifier(accessor) if is_accessor(name) => {
+                if name.as_str() == "get" {
+                    ClassMember::Getter {
+                        is_static: is_static,
+                        name: accessor,
+                        body: self.getter_body()?,
+                    }
+                } else {
+                    ClassMember::Setter {
+                        is_static: is_static,
+                        name: accessor,
+                        params: self.setter_parameters()?,
+                        body: self.block_body()?,
+                    }
+                }
+            },
             Control(b'(') => {
-                self.tokenizer.consume();
-
                 if !is_static && name.as_str() == "constructor" {
                     ClassMember::Constructor {
-                        params: self.parameter_list(),
-                        body: self.block_body(),
+                        params: self.parameter_list()?,
+                        body: self.block_body()?,
                     }
                 } else {
                     ClassMember::Method {
                         is_static: is_static,
                         name: name,
-                        params: self.parameter_list(),
-                        body: self.block_body(),
+                        params: self.parameter_list()?,
+                        body: self.block_body()?,
                     }
                 }
             },
             Operator(Assign) => {
-                self.tokenizer.consume();
-
                 ClassMember::Property {
                     is_static: is_static,
                     name: name,
-                    value: self.expression(0),
+                    value: self.expression(0)?,
                 }
             },
-            _ => unexpected_token!(self),
-        }
+            token => unexpected_token!(self, token, [
+                Expected::Token(Control(b'(')),
+                Expected::Token(Operator(Assign))
+            ]),
+        })
     }
 
+    /// Parses the empty parameter list and the body of a getter.
     #[inline]
-    fn class_statement(&mut self) -> Statement {
-        let name = self.tokenizer.expect_identifier();
-        let super_class = match self.tokenizer.next() {
+    fn getter_body(&mut self) -> ParseResult<Vec<Loc<Statement>>> {
+        self.tokenizer.expect_control(b'(')?;
+        self.tokenizer.expect_control(b')')?;
+        self.block_body()
+    }
+
+    /// Parses the parameter list of a setter, which takes one argument.
+    #[inline]
+    fn setter_parameters(&mut self) -> ParseResult<Vec<Parameter>> {
+        self.tokenizer.expect_control(b'(')?;
+
+        let param = self.parameter()?;
+
+        self.tokenizer.expect_control(b')')?;
+
+        Ok(vec![param])
+    }
+
+    #[inline]
+    fn class_statement(&mut self) -> ParseResult<Statement> {
+        let name = self.tokenizer.expect_identifier()?;
+
+        self.class(name)
+    }
+
+    /// Parses the rest of a class following its `name`.
+    fn class(&mut self, name: OwnedSlice) -> ParseResult<Statement> {
+        let super_class = match self.tokenizer.next()? {
             Extends => {
-                let name = self.tokenizer.expect_identifier();
-                self.tokenizer.expect_control(b'{');
+                let name = self.tokenizer.expect_identifier()?;
+                self.tokenizer.expect_control(b'{')?;
                 Some(name)
             },
             Control(b'{') => None,
-            token         => unexpected_token!(self, token)
+            token         => unexpected_token!(self, token, [
+                Expected::Token(Extends),
+                Expected::Token(Control(b'{'))
+            ])
         };
 
         let mut members = Vec::new();
 
         loop {
-            members.push(match self.tokenizer.next() {
-                Identifier(name) => self.class_member(name, false),
+            let token = self.tokenizer.next()?;
+            let start = self.tokenizer.token_span();
+
+            let member = match token {
+                Identifier(name) => self.class_member(name, false)?,
                 Static           => {
-                    let name = self.tokenizer.expect_identifier();
-                    self.class_member(name, true)
+                    let name = self.tokenizer.expect_identifier()?;
+                    self.class_member(name, true)?
                 },
                 Control(b';')    => continue,
                 Control(b'}')    => break,
-                token            => unexpected_token!(self, token)
-            });
+                token            => unexpected_token!(self, token, [
+                    Expected::Identifier,
+                    Expected::Token(Static),
+                    Expected::Token(Control(b'}'))
+                ])
+            };
+
+            members.push(self.loc(start, member));
         }
 
-        Statement::Class {
+        Ok(Statement::Class {
             name: name,
             extends: super_class,
             body: members,
+        })
+    }
+
+    /// Expects a contextual keyword, such as `from` or `as`, which
+    /// is otherwise tokenized as a regular identifier.
+    #[inline]
+    fn expect_word(&mut self, word: &'static str) -> ParseResult<()> {
+        match self.tokenizer.next()? {
+            Identifier(ident) if ident.as_str() == word => Ok(()),
+            token => unexpected_token!(self, token, [Expected::Token(Identifier(word.into()))])
         }
     }
 
-    fn statement(&mut self) -> Option<Statement> {
-        let token = self.tokenizer.next();
+    /// Checks if the next token is the contextual keyword `word`,
+    /// consuming it if that's the case.
+    #[inline]
+    fn allow_word(&mut self, word: &'static str) -> ParseResult<bool> {
+        Ok(match self.tokenizer.peek()? {
+            Identifier(ident) if ident.as_str() == word => {
+                self.tokenizer.consume();
+                true
+            },
+            _ => false
+        })
+    }
 
-        Some(match token {
-            EndOfProgram      => return None,
-            Control(b';')     => Statement::Transparent { body: Vec::new() },
-            Control(b'{')     => self.block_statement(),
-            Declaration(kind) => self.variable_declaration_statement(kind),
-            Return            => self.return_statement(),
-            Break             => self.break_statement(),
-            Function          => self.function_statement(),
-            Class             => self.class_statement(),
-            If                => self.if_statement(),
-            While             => self.while_statement(),
-            For               => self.for_statement(),
-            Identifier(label) => self.labeled_or_expression_statement(label),
-            Throw             => self.throw_statement(),
-            token             => self.expression_statement(token),
+    /// Reads the name of an import or export, which unlike
+    /// a regular identifier can also be `default`.
+    #[inline]
+    fn module_name(&mut self) -> ParseResult<OwnedSlice> {
+        match self.tokenizer.next()? {
+            Identifier(name) => Ok(name),
+            Default          => Ok("default".into()),
+            token            => unexpected_token!(self, token, [Expected::Identifier])
+        }
+    }
+
+    #[inline]
+    fn module_source(&mut self) -> ParseResult<OwnedSlice> {
+        match self.tokenizer.next()? {
+            Literal(LiteralString(source)) => Ok(source),
+            token => unexpected_token!(self, token, [Expected::ModuleSource])
+        }
+    }
+
+    #[inline]
+    fn import_expression(&mut self) -> ParseResult<Expression> {
+        self.tokenizer.expect_control(b'(')?;
+
+        let source = self.expression(0)?;
+
+        self.tokenizer.expect_control(b')')?;
+
+        Ok(Expression::Import {
+            source: Box::new(source),
         })
     }
+
+    fn import_declaration(&mut self) -> ParseResult<Statement> {
+        let mut specifiers = Vec::new();
+
+        let token = match self.tokenizer.next()? {
+            Literal(LiteralString(source)) => {
+                return statement!(self, Statement::ImportDeclaration {
+                    specifiers: specifiers,
+                    source: source,
+                });
+            },
+
+            Identifier(local) => {
+                specifiers.push(ImportSpecifier::Default {
+                    local: local,
+                });
+
+                if !allow!(self, Control(b',')) {
+                    self.expect_word("from")?;
+
+                    return statement!(self, Statement::ImportDeclaration {
+                        specifiers: specifiers,
+                        source: self.module_source()?,
+                    });
+                }
+
+                self.tokenizer.next()?
+            },
+
+            token => token,
+        };
+
+        match token {
+            Operator(Multiplication) => {
+                self.expect_word("as")?;
+
+                specifiers.push(ImportSpecifier::Namespace {
+                    local: self.tokenizer.expect_identifier()?,
+                });
+            },
+
+            Control(b'{') => loop {
+                if allow!(self, Control(b'}')) {
+                    break;
+                }
+
+                let imported = self.module_name()?;
+                let local = if self.allow_word("as")? {
+                    self.tokenizer.expect_identifier()?
+                } else {
+                    imported
+                };
+
+                specifiers.push(ImportSpecifier::Named {
+                    imported: imported,
+                    local: local,
+                });
+
+                if self.list_separator(b'}')? {
+                    break;
+                }
+            },
+
+            token => unexpected_token!(self, token, [
+                Expected::Identifier,
+                Expected::Token(Operator(Multiplication)),
+                Expected::Token(Control(b'{')),
+                Expected::ModuleSource
+            ])
+        }
+
+        self.expect_word("from")?;
+
+        statement!(self, Statement::ImportDeclaration {
+            specifiers: specifiers,
+            source: self.module_source()?,
+        })
+    }
+
+    fn export_declaration(&mut self) -> ParseResult<Statement> {
+        let token = self.tokenizer.next()?;
+        let start = self.tokenizer.token_span();
+
+        let kind = match token {
+            Operator(Multiplication) => {
+                self.expect_word("from")?;
+
+                let source = self.module_source()?;
+
+                self.tokenizer.expect_semicolon()?;
+
+                ExportKind::All {
+                    source: source,
+                }
+            },
+
+            Control(b'{') => {
+                let mut specifiers = Vec::new();
+
+                loop {
+                    if allow!(self, Control(b'}')) {
+                        break;
+                    }
+
+                    let local = self.module_name()?;
+                    let exported = if self.allow_word("as")? {
+                        self.module_name()?
+                    } else {
+                        local
+                    };
+
+                    specifiers.push(ExportSpecifier {
+                        local: local,
+                        exported: exported,
+                    });
+
+                    if self.list_separator(b'}')? {
+                        break;
+                    }
+                }
+
+                let source = if self.allow_word("from")? {
+                    Some(self.module_source()?)
+                } else {
+                    None
+                };
+
+                self.tokenizer.expect_semicolon()?;
+
+                ExportKind::Named {
+                    specifiers: specifiers,
+                    source: source,
+                }
+            },
+
+            Default => {
+                let token = self.tokenizer.next()?;
+                let start = self.tokenizer.token_span();
+
+                let statement = match token {
+                    Function => match self.function_expression()? {
+                        Expression::Function {
+                            name: Some(name),
+                            params,
+                            body,
+                        } => Statement::Function {
+                            name: name,
+                            params: params,
+                            body: body,
+                        },
+                        function => function.into(),
+                    },
+                    Class => match self.tokenizer.peek()? {
+                        Identifier(_) => self.class_statement()?,
+                        _             => self.class("".into())?,
+                    },
+                    token => {
+                        let value = self.expression_from_token(token, 0)?;
+
+                        self.tokenizer.expect_semicolon()?;
+
+                        value.into()
+                    }
+                };
+
+                ExportKind::Default(Box::new(self.loc(start, statement)))
+            },
+
+            Declaration(kind) => {
+                let declaration = self.variable_declaration_statement(kind)?;
+
+                ExportKind::Declaration(Box::new(self.loc(start, declaration)))
+            },
+
+            Function => {
+                let function = self.function_statement()?;
+
+                ExportKind::Declaration(Box::new(self.loc(start, function)))
+            },
+
+            Class => {
+                let class = self.class_statement()?;
+
+                ExportKind::Declaration(Box::new(self.loc(start, class)))
+            },
+
+            token => unexpected_token!(self, token, [
+                Expected::Token(Operator(Multiplication)),
+                Expected::Token(Control(b'{')),
+                Expected::Token(Default),
+                Expected::Statement
+            ])
+        };
+
+        Ok(Statement::ExportDeclaration {
+            kind: kind,
+        })
+    }
+
+    /// Like `statement`, but also accepts `import` and `export`
+    /// declarations, which are only valid at the top level.
+    fn module_statement(&mut self) -> ParseResult<Option<Loc<Statement>>> {
+        let declaration = match self.tokenizer.peek()? {
+            Import => {
+                self.tokenizer.consume();
+                let start = self.tokenizer.token_span();
+
+                // `import(…)` starts an expression statement
+                if let Control(b'(') = self.tokenizer.peek()? {
+                    let import = self.import_expression()?;
+                    let import = self.loc(start, import);
+                    let value = self.complex_expression(import, 0)?;
+                    let value = self.sequence_or(value)?;
+
+                    self.tokenizer.expect_semicolon()?;
+
+                    (start, Statement::Expression { value: value })
+                } else {
+                    (start, self.import_declaration()?)
+                }
+            },
+            Export => {
+                self.tokenizer.consume();
+                let start = self.tokenizer.token_span();
+
+                (start, self.export_declaration()?)
+            },
+            _ => return self.statement(),
+        };
+
+        Ok(Some(self.loc(declaration.0, declaration.1)))
+    }
+
+    /// Like `statement`, but treats the end of program as an error.
+    #[inline]
+    fn expect_statement(&mut self) -> ParseResult<Loc<Statement>> {
+        match self.statement()? {
+            Some(statement) => Ok(statement),
+            None            => Err(ParseError::UnexpectedEndOfProgram {
+                expected: vec![Expected::Statement]
+            }),
+        }
+    }
+
+    fn statement(&mut self) -> ParseResult<Option<Loc<Statement>>> {
+        let token = self.tokenizer.next()?;
+        let start = self.tokenizer.token_span();
+
+        let statement = match token {
+            EndOfProgram      => return Ok(None),
+            Control(b';')     => Statement::Transparent { body: Vec::new() },
+            Control(b'{')     => self.block_statement()?,
+            Declaration(kind) => self.variable_declaration_statement(kind)?,
+            Return            => self.return_statement()?,
+            Break             => self.break_statement()?,
+            Continue          => self.continue_statement()?,
+            Function          => self.function_statement()?,
+            Class             => self.class_statement()?,
+            If                => self.if_statement()?,
+            While             => self.while_statement()?,
+            Do                => self.do_while_statement()?,
+            Switch            => self.switch_statement()?,
+            For               => self.for_statement()?,
+            Identifier(label) => self.labeled_or_expression_statement(label)?,
+            Throw             => self.throw_statement()?,
+            Try               => self.try_statement()?,
+            token             => self.expression_statement(token)?,
+        };
+
+        Ok(Some(self.loc(start, statement)))
+    }
+}
+
+/// Parses the `source` into a `Program`. Returns the first error
+/// encountered if the source isn't valid JavaScript.
+/// Checks if an identifier preceding the name of a method makes it
+/// a getter or a setter.
+#[inline]
+fn is_accessor(word: OwnedSlice) -> bool {
+    word.as_str() == "get" || word.as_str() == "set"
 }
 
-pub fn parse(source: String) -> Program {
+pub fn parse(source: String) -> ParseResult<Program> {
     let mut body = Vec::new();
 
     {
         let mut parser = Parser::new(&source);
 
-        while let Some(statement) = parser.statement() {
+        while let Some(statement) = parser.module_statement()? {
             body.push(statement);
         }
     }
 
-    Program::new(source, body)
+    Ok(Program::new(source, body))
 }
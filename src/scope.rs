@@ -0,0 +1,830 @@
+use grammar::*;
+
+/// Index of a `Scope` in `ScopeTree::scopes`.
+pub type ScopeId = usize;
+
+/// Index of a `Binding` in `ScopeTree::bindings`.
+pub type BindingId = usize;
+
+/// Syntax that introduced a `Scope`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ScopeKind {
+    // Top level of the program, also holding the imports
+    Program,
+    // Function, arrow function, method or constructor, holding the
+    // parameters and the `var` declarations of its body
+    Function,
+    // Block statement, head of a `for` loop or the body of a `switch`
+    Block,
+    // `catch` clause, holding the error parameter together with the body
+    Catch,
+    // Body of a class, the parent of its methods
+    Class,
+}
+
+/// How a `Binding` was declared.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BindingKind {
+    Variable(VariableDeclarationKind),
+    Function,
+    Class,
+    Parameter,
+    CatchParameter,
+    Import,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Scope {
+    pub kind: ScopeKind,
+
+    /// Enclosing scope, `None` only for the program scope
+    pub parent: Option<ScopeId>,
+
+    /// Nested scopes, in the order they appear in the source
+    pub children: Vec<ScopeId>,
+
+    /// Bindings declared directly in this scope
+    pub bindings: Vec<BindingId>,
+}
+
+/// A name declared in a `Scope`. Repeated declarations of the same name
+/// in one scope, such as `var a; var a;`, share a single binding.
+#[derive(Debug, PartialEq)]
+pub struct Binding {
+    pub name: OwnedSlice,
+    pub kind: BindingKind,
+    pub scope: ScopeId,
+
+    /// Identifiers of every declaration of the name, in source order
+    pub declarations: Vec<OwnedSlice>,
+
+    /// Indexes of all `ScopeTree::references` resolved to this binding
+    pub references: Vec<usize>,
+}
+
+/// An identifier reading or writing a variable.
+#[derive(Debug, PartialEq)]
+pub struct Reference {
+    pub name: OwnedSlice,
+
+    /// Scope the identifier appears in
+    pub scope: ScopeId,
+
+    /// Span of the expression containing the identifier
+    pub span: Span,
+
+    /// True for targets of assignments, `++` and `--`
+    pub write: bool,
+
+    /// Binding the identifier resolves to, `None` for globals
+    pub binding: Option<BindingId>,
+}
+
+/// Scopes of a `Program`, numbered in the order they are entered, with the
+/// program scope at index 0.
+#[derive(Debug, PartialEq)]
+pub struct ScopeTree {
+    pub scopes: Vec<Scope>,
+    pub bindings: Vec<Binding>,
+    pub references: Vec<Reference>,
+}
+
+impl ScopeTree {
+    /// Finds the binding of `name` declared directly in `scope`.
+    pub fn binding(&self, scope: ScopeId, name: &str) -> Option<BindingId> {
+        self.scopes[scope].bindings
+                          .iter()
+                          .cloned()
+                          .find(|&id| self.bindings[id].name.as_str() == name)
+    }
+
+    /// Finds the binding `name` refers to when used in `scope`, `None` if
+    /// the name is global.
+    pub fn resolve(&self, scope: ScopeId, name: &str) -> Option<BindingId> {
+        self.ancestors(scope).filter_map(|scope| self.binding(scope, name)).next()
+    }
+
+    /// Checks if `name` used in `scope` resolves to a binding that hides
+    /// another binding of the same name from one of the enclosing scopes.
+    pub fn is_shadowed(&self, scope: ScopeId, name: &str) -> bool {
+        self.ancestors(scope).filter(|&scope| self.binding(scope, name).is_some()).count() > 1
+    }
+
+    /// Lists the bindings declared outside of `scope` that are referenced
+    /// from within it or any of its nested scopes. Globals are not included.
+    pub fn free_variables(&self, scope: ScopeId) -> Vec<BindingId> {
+        let mut free = Vec::new();
+
+        for reference in &self.references {
+            let binding = match reference.binding {
+                Some(binding) => binding,
+                None          => continue,
+            };
+
+            if self.contains(scope, reference.scope)
+                && !self.contains(scope, self.bindings[binding].scope)
+                && !free.contains(&binding) {
+                free.push(binding);
+            }
+        }
+
+        free
+    }
+
+    /// Lists the names of all unresolved references, in order of their
+    /// first appearance.
+    pub fn globals(&self) -> Vec<OwnedSlice> {
+        let mut globals = Vec::new();
+
+        for reference in &self.references {
+            if reference.binding.is_none() && !globals.contains(&reference.name) {
+                globals.push(reference.name);
+            }
+        }
+
+        globals
+    }
+
+    /// Checks if `scope` is `ancestor` or one of the scopes nested in it.
+    pub fn contains(&self, ancestor: ScopeId, scope: ScopeId) -> bool {
+        self.ancestors(scope).any(|scope| scope == ancestor)
+    }
+
+    /// Finds the nearest function or program scope enclosing `scope`,
+    /// which is where its `var` declarations end up.
+    pub fn function_scope(&self, scope: ScopeId) -> ScopeId {
+        self.ancestors(scope)
+            .find(|&scope| matches!(self.scopes[scope].kind,
+                ScopeKind::Function |
+                ScopeKind::Program
+            ))
+            .unwrap_or(0)
+    }
+
+    /// Iterates over `scope` and all of its enclosing scopes, innermost first.
+    pub fn ancestors(&self, scope: ScopeId) -> Ancestors<'_> {
+        Ancestors {
+            tree: self,
+            next: Some(scope),
+        }
+    }
+}
+
+pub struct Ancestors<'a> {
+    tree: &'a ScopeTree,
+    next: Option<ScopeId>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = ScopeId;
+
+    #[inline]
+    fn next(&mut self) -> Option<ScopeId> {
+        let scope = self.next?;
+
+        self.next = self.tree.scopes[scope].parent;
+
+        Some(scope)
+    }
+}
+
+struct Builder {
+    tree: ScopeTree,
+    current: ScopeId,
+}
+
+impl Builder {
+    fn enter(&mut self, kind: ScopeKind) -> ScopeId {
+        let id = self.tree.scopes.len();
+        let parent = self.current;
+
+        self.tree.scopes.push(Scope {
+            kind: kind,
+            parent: Some(parent),
+            children: Vec::new(),
+            bindings: Vec::new(),
+        });
+        self.tree.scopes[parent].children.push(id);
+        self.current = id;
+
+        parent
+    }
+
+    #[inline]
+    fn exit(&mut self, parent: ScopeId) {
+        self.current = parent;
+    }
+
+    fn declare(&mut self, scope: ScopeId, name: OwnedSlice, kind: BindingKind) {
+        if let Some(id) = self.tree.binding(scope, &name) {
+            self.tree.bindings[id].declarations.push(name);
+            return;
+        }
+
+        let id = self.tree.bindings.len();
+
+        self.tree.bindings.push(Binding {
+            name: name,
+            kind: kind,
+            scope: scope,
+            declarations: vec![name],
+            references: Vec::new(),
+        });
+        self.tree.scopes[scope].bindings.push(id);
+    }
+
+    /// Declares all names bound by the pattern in `scope`, default values
+    /// are evaluated in the current scope.
+    fn declare_pattern(&mut self, scope: ScopeId, pattern: &Pattern, kind: BindingKind) {
+        match *pattern {
+            Pattern::Identifier(name) => self.declare(scope, name, kind),
+
+            Pattern::Object(ref properties) => {
+                for property in properties {
+                    self.declare_pattern(scope, &property.value, kind);
+                }
+            },
+
+            Pattern::Array(ref elements) => {
+                for element in elements.iter().filter_map(Option::as_ref) {
+                    self.declare_pattern(scope, element, kind);
+                }
+            },
+
+            Pattern::Rest(ref pattern) => self.declare_pattern(scope, pattern, kind),
+
+            Pattern::Default {
+                ref pattern,
+                ref value,
+            } => {
+                self.declare_pattern(scope, pattern, kind);
+                self.expression(value);
+            },
+
+            Pattern::Expression(ref expression) => self.expression(expression),
+        }
+    }
+
+    #[inline]
+    fn reference(&mut self, name: OwnedSlice, span: Span, write: bool) {
+        self.tree.references.push(Reference {
+            name: name,
+            scope: self.current,
+            span: span,
+            write: write,
+            binding: None,
+        });
+    }
+
+    /// Records the identifiers of a destructuring assignment as writes.
+    fn assign_pattern(&mut self, pattern: &Pattern, span: Span) {
+        match *pattern {
+            Pattern::Identifier(name) => self.reference(name, span, true),
+
+            Pattern::Object(ref properties) => {
+                for property in properties {
+                    self.assign_pattern(&property.value, span);
+                }
+            },
+
+            Pattern::Array(ref elements) => {
+                for element in elements.iter().filter_map(Option::as_ref) {
+                    self.assign_pattern(element, span);
+                }
+            },
+
+            Pattern::Rest(ref pattern) => self.assign_pattern(pattern, span),
+
+            Pattern::Default {
+                ref pattern,
+                ref value,
+            } => {
+                self.assign_pattern(pattern, span);
+                self.expression(value);
+            },
+
+            Pattern::Expression(ref expression) => self.expression(expression),
+        }
+    }
+
+    /// Target of an assignment, `++`, `--` or the left side of a `for…in`
+    /// and `for…of` loop.
+    fn assign(&mut self, target: &Loc<Expression>) {
+        match target.item {
+            Expression::Identifier(name) => self.reference(name, target.span, true),
+            Expression::Pattern(ref pattern) => self.assign_pattern(pattern, target.span),
+            _ => self.expression(target),
+        }
+    }
+
+    fn function(&mut self, name: Option<OwnedSlice>, params: &[Parameter], body: &[Loc<Statement>]) {
+        let parent = self.enter(ScopeKind::Function);
+        let scope = self.current;
+
+        if let Some(name) = name {
+            self.declare(scope, name, BindingKind::Function);
+        }
+
+        for param in params {
+            self.declare_pattern(scope, &param.name, BindingKind::Parameter);
+
+            if let Some(ref default) = param.default {
+                self.expression(default);
+            }
+        }
+
+        self.statements(body);
+        self.exit(parent);
+    }
+
+    fn block(&mut self, kind: ScopeKind, body: &[Loc<Statement>]) {
+        let parent = self.enter(kind);
+
+        self.statements(body);
+        self.exit(parent);
+    }
+
+    #[inline]
+    fn statements(&mut self, body: &[Loc<Statement>]) {
+        for statement in body {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Loc<Statement>) {
+        match statement.item {
+            Statement::Block {
+                ref body,
+            } => self.block(ScopeKind::Block, body),
+
+            Statement::Transparent {
+                ref body,
+            } => self.statements(body),
+
+            Statement::Labeled {
+                ref body,
+                ..
+            } => self.statement(body),
+
+            Statement::VariableDeclaration {
+                kind,
+                ref declarators,
+            } => {
+                let scope = match kind {
+                    VariableDeclarationKind::Var => self.tree.function_scope(self.current),
+                    _                            => self.current,
+                };
+
+                for declarator in declarators {
+                    self.declare_pattern(scope, &declarator.name, BindingKind::Variable(kind));
+
+                    if let Some(ref value) = declarator.value {
+                        self.expression(value);
+                    }
+                }
+            },
+
+            Statement::Expression {
+                ref value,
+            } |
+            Statement::Throw {
+                ref value,
+            } => self.expression(value),
+
+            Statement::Return {
+                ref value,
+            } => {
+                if let Some(ref value) = *value {
+                    self.expression(value);
+                }
+            },
+
+            Statement::Function {
+                name,
+                ref params,
+                ref body,
+            } => {
+                // Functions declared in blocks are also visible in the whole
+                // enclosing function in sloppy mode scripts
+                let scope = self.tree.function_scope(self.current);
+
+                self.declare(scope, name, BindingKind::Function);
+                self.function(None, params, body);
+            },
+
+            Statement::If {
+                ref test,
+                ref consequent,
+                ref alternate,
+            } => {
+                self.expression(test);
+                self.statement(consequent);
+
+                if let Some(ref alternate) = *alternate {
+                    self.statement(alternate);
+                }
+            },
+
+            Statement::While {
+                ref test,
+                ref body,
+            } |
+            Statement::DoWhile {
+                ref body,
+                ref test,
+            } => {
+                self.expression(test);
+                self.statement(body);
+            },
+
+            Statement::Switch {
+                ref discriminant,
+                ref cases,
+            } => {
+                self.expression(discriminant);
+
+                let parent = self.enter(ScopeKind::Block);
+
+                for case in cases {
+                    if let Some(ref test) = case.test {
+                        self.expression(test);
+                    }
+
+                    self.statements(&case.consequent);
+                }
+
+                self.exit(parent);
+            },
+
+            Statement::For {
+                ref init,
+                ref test,
+                ref update,
+                ref body,
+            } => {
+                let parent = self.enter(ScopeKind::Block);
+
+                if let Some(ref init) = *init {
+                    self.statement(init);
+                }
+
+                if let Some(ref test) = *test {
+                    self.expression(test);
+                }
+
+                if let Some(ref update) = *update {
+                    self.expression(update);
+                }
+
+                self.statement(body);
+                self.exit(parent);
+            },
+
+            Statement::ForIn {
+                ref left,
+                ref right,
+                ref body,
+            } |
+            Statement::ForOf {
+                ref left,
+                ref right,
+                ref body,
+            } => {
+                let parent = self.enter(ScopeKind::Block);
+
+                match left.item {
+                    Statement::Expression {
+                        ref value,
+                    } => self.assign(value),
+
+                    _ => self.statement(left),
+                }
+
+                self.expression(right);
+                self.statement(body);
+                self.exit(parent);
+            },
+
+            Statement::Class {
+                name,
+                extends,
+                ref body,
+            } => {
+                let scope = self.current;
+
+                if !name.as_str().is_empty() {
+                    self.declare(scope, name, BindingKind::Class);
+                }
+
+                if let Some(extends) = extends {
+                    self.reference(extends, statement.span, false);
+                }
+
+                let parent = self.enter(ScopeKind::Class);
+
+                for member in body {
+                    self.class_member(member);
+                }
+
+                self.exit(parent);
+            },
+
+            Statement::Try {
+                ref block,
+                ref handler,
+                ref finalizer,
+            } => {
+                self.block(ScopeKind::Block, block);
+
+                if let Some(ref handler) = *handler {
+                    let parent = self.enter(ScopeKind::Catch);
+                    let scope = self.current;
+
+                    if let Some(ref param) = handler.param {
+                        self.declare_pattern(scope, param, BindingKind::CatchParameter);
+                    }
+
+                    self.statements(&handler.body);
+                    self.exit(parent);
+                }
+
+                if let Some(ref finalizer) = *finalizer {
+                    self.block(ScopeKind::Block, finalizer);
+                }
+            },
+
+            Statement::ImportDeclaration {
+                ref specifiers,
+                ..
+            } => {
+                for specifier in specifiers {
+                    let local = match *specifier {
+                        ImportSpecifier::Default { local }   |
+                        ImportSpecifier::Namespace { local } |
+                        ImportSpecifier::Named { local, .. } => local,
+                    };
+
+                    self.declare(0, local, BindingKind::Import);
+                }
+            },
+
+            Statement::ExportDeclaration {
+                ref kind,
+            } => match *kind {
+                ExportKind::Declaration(ref statement) |
+                ExportKind::Default(ref statement) => self.statement(statement),
+
+                ExportKind::Named {
+                    ref specifiers,
+                    source: None,
+                } => {
+                    for specifier in specifiers {
+                        self.reference(specifier.local, statement.span, false);
+                    }
+                },
+
+                _ => {},
+            },
+
+            Statement::Break { .. } |
+            Statement::Continue { .. } => {},
+        }
+    }
+
+    fn expression(&mut self, expression: &Loc<Expression>) {
+        match expression.item {
+            Expression::Identifier(name) => self.reference(name, expression.span, false),
+
+            Expression::Array(ref items) |
+            Expression::Sequence(ref items) => {
+                for item in items {
+                    self.expression(item);
+                }
+            },
+
+            Expression::Object(ref members) => {
+                for member in members {
+                    self.object_member(member);
+                }
+            },
+
+            Expression::Member {
+                ref object,
+                ..
+            } => self.expression(object),
+
+            Expression::ComputedMember {
+                ref object,
+                ref property,
+            } => {
+                self.expression(object);
+                self.expression(property);
+            },
+
+            Expression::Call {
+                ref callee,
+                ref arguments,
+            } => {
+                self.expression(callee);
+
+                for argument in arguments {
+                    self.expression(argument);
+                }
+            },
+
+            Expression::Binary {
+                ref left,
+                operator,
+                ref right,
+            } => {
+                if operator.assignment() {
+                    self.assign(left);
+                } else {
+                    self.expression(left);
+                }
+
+                self.expression(right);
+            },
+
+            Expression::Prefix {
+                operator,
+                ref operand,
+            } |
+            Expression::Postfix {
+                operator,
+                ref operand,
+            } => match operator {
+                OperatorType::Increment |
+                OperatorType::Decrement => self.assign(operand),
+                _                       => self.expression(operand),
+            },
+
+            Expression::Conditional {
+                ref test,
+                ref consequent,
+                ref alternate,
+            } => {
+                self.expression(test);
+                self.expression(consequent);
+                self.expression(alternate);
+            },
+
+            Expression::ArrowFunction {
+                ref params,
+                ref body,
+            } => match body.item {
+                Statement::Block {
+                    ref body,
+                } => self.function(None, params, body),
+
+                _ => self.function(None, params, ::std::slice::from_ref(&**body)),
+            },
+
+            Expression::Function {
+                name,
+                ref params,
+                ref body,
+            } => self.function(name, params, body),
+
+            Expression::TemplateLiteral {
+                ref expressions,
+                ..
+            } => {
+                for expression in expressions {
+                    self.expression(expression);
+                }
+            },
+
+            Expression::TaggedTemplate {
+                ref tag,
+                ref expressions,
+                ..
+            } => {
+                self.expression(tag);
+
+                for expression in expressions {
+                    self.expression(expression);
+                }
+            },
+
+            Expression::Pattern(ref pattern) => self.assign_pattern(pattern, expression.span),
+
+            Expression::Import {
+                ref source,
+            } => self.expression(source),
+
+            Expression::This |
+            Expression::Super |
+            Expression::Literal(_) => {},
+        }
+    }
+
+    fn object_member(&mut self, member: &Loc<ObjectMember>) {
+        match member.item {
+            ObjectMember::Shorthand {
+                key,
+            } => self.reference(key, member.span, false),
+
+            ObjectMember::Literal {
+                ref value,
+                ..
+            } => self.expression(value),
+
+            ObjectMember::Computed {
+                ref key,
+                ref value,
+            } => {
+                self.expression(key);
+                self.expression(value);
+            },
+
+            ObjectMember::Method {
+                ref params,
+                ref body,
+                ..
+            } |
+            ObjectMember::Setter {
+                ref params,
+                ref body,
+                ..
+            } => self.function(None, params, body),
+
+            ObjectMember::Getter {
+                ref body,
+                ..
+            } => self.function(None, &[], body),
+
+            ObjectMember::ComputedMethod {
+                ref name,
+                ref params,
+                ref body,
+            } => {
+                self.expression(name);
+                self.function(None, params, body);
+            },
+        }
+    }
+
+    fn class_member(&mut self, member: &Loc<ClassMember>) {
+        match member.item {
+            ClassMember::Constructor {
+                ref params,
+                ref body,
+            } |
+            ClassMember::Method {
+                ref params,
+                ref body,
+                ..
+            } |
+            ClassMember::Setter {
+                ref params,
+                ref body,
+                ..
+            } => self.function(None, params, body),
+
+            ClassMember::Getter {
+                ref body,
+                ..
+            } => self.function(None, &[], body),
+
+            ClassMember::Property {
+                ref value,
+                ..
+            } => self.expression(value),
+        }
+    }
+}
+
+/// Builds the `ScopeTree` of the program, resolving every reference to
+/// its binding once all declarations, including hoisted ones, are known.
+pub fn analyze(program: &Program) -> ScopeTree {
+    let mut builder = Builder {
+        tree: ScopeTree {
+            scopes: vec![Scope {
+                kind: ScopeKind::Program,
+                parent: None,
+                children: Vec::new(),
+                bindings: Vec::new(),
+            }],
+            bindings: Vec::new(),
+            references: Vec::new(),
+        },
+        current: 0,
+    };
+
+    builder.statements(&program.body);
+
+    let mut tree = builder.tree;
+
+    for index in 0..tree.references.len() {
+        let binding = tree.resolve(tree.references[index].scope, &tree.references[index].name);
+
+        if let Some(binding) = binding {
+            tree.bindings[binding].references.push(index);
+        }
+
+        tree.references[index].binding = binding;
+    }
+
+    tree
+}
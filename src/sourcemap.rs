@@ -0,0 +1,203 @@
+extern crate rustc_serialize;
+
+use std::collections::{ BTreeMap, HashMap };
+
+use self::rustc_serialize::json::{ Json, ToJson };
+
+use grammar::Span;
+
+static BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A single segment of the mappings, connecting a position in the
+/// generated code with a position in the original source. Lines and
+/// columns are both counted from 0 here, as the spec requires.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Mapping {
+    generated_line: usize,
+    generated_column: usize,
+    source_line: usize,
+    source_column: usize,
+    name: Option<usize>,
+}
+
+/// Collects mappings while the code is being generated, and then
+/// encodes them into a `SourceMap`.
+pub struct Mappings {
+    mappings: Vec<Mapping>,
+    names: Vec<String>,
+    name_indexes: HashMap<String, usize>,
+}
+
+impl Mappings {
+    pub fn new() -> Self {
+        Mappings {
+            mappings: Vec::new(),
+            names: Vec::new(),
+            name_indexes: HashMap::new(),
+        }
+    }
+
+    /// Maps the generated position to the start of the `span`. Nested nodes
+    /// often start at the same generated position as their parents, in which
+    /// case the mapping of the innermost node wins.
+    pub fn add(&mut self, line: usize, column: usize, span: &Span, name: Option<&str>) {
+        let name = name.map(|name| self.name_index(name));
+
+        let mapping = Mapping {
+            generated_line: line,
+            generated_column: column,
+            source_line: span.line - 1,
+            source_column: span.column,
+            name: name,
+        };
+
+        if let Some(last) = self.mappings.last_mut() {
+            if last.generated_line == line && last.generated_column == column {
+                *last = mapping;
+                return;
+            }
+        }
+
+        self.mappings.push(mapping);
+    }
+
+    #[inline]
+    fn name_index(&mut self, name: &str) -> usize {
+        if let Some(index) = self.name_indexes.get(name) {
+            return *index;
+        }
+
+        let index = self.names.len();
+
+        self.names.push(name.to_string());
+        self.name_indexes.insert(name.to_string(), index);
+
+        index
+    }
+
+    /// Encodes all collected mappings into a `SourceMap` of a single `source`
+    /// file, with its original `content` embedded.
+    pub fn into_source_map(self, source: &str, content: &str) -> SourceMap {
+        let mut encoded = String::with_capacity(self.mappings.len() * 6);
+
+        let mut line = 0;
+        let mut previous = Mapping {
+            generated_line: 0,
+            generated_column: 0,
+            source_line: 0,
+            source_column: 0,
+            name: None,
+        };
+        let mut previous_name = 0;
+
+        for mapping in &self.mappings {
+            if mapping.generated_line != line {
+                while line < mapping.generated_line {
+                    encoded.push(';');
+                    line += 1;
+                }
+
+                previous.generated_column = 0;
+            } else if !encoded.is_empty() && !encoded.ends_with(';') {
+                encoded.push(',');
+            }
+
+            write_vlq(&mut encoded, mapping.generated_column, previous.generated_column);
+            // Index into `sources`, always 0 since there is only one
+            encoded.push('A');
+            write_vlq(&mut encoded, mapping.source_line, previous.source_line);
+            write_vlq(&mut encoded, mapping.source_column, previous.source_column);
+
+            if let Some(name) = mapping.name {
+                write_vlq(&mut encoded, name, previous_name);
+                previous_name = name;
+            }
+
+            previous = *mapping;
+        }
+
+        SourceMap {
+            file: None,
+            sources: vec![source.to_string()],
+            sources_content: vec![content.to_string()],
+            names: self.names,
+            mappings: encoded,
+        }
+    }
+}
+
+impl Default for Mappings {
+    #[inline]
+    fn default() -> Self {
+        Mappings::new()
+    }
+}
+
+/// Source Map revision 3, as described in
+/// https://sourcemaps.info/spec.html
+#[derive(Debug, PartialEq, Clone)]
+pub struct SourceMap {
+    pub file: Option<String>,
+    pub sources: Vec<String>,
+    pub sources_content: Vec<String>,
+    pub names: Vec<String>,
+    pub mappings: String,
+}
+
+impl SourceMap {
+    pub fn to_json(&self) -> String {
+        let mut map = BTreeMap::new();
+
+        map.insert("version".to_string(), Json::U64(3));
+
+        if let Some(ref file) = self.file {
+            map.insert("file".to_string(), file.to_json());
+        }
+
+        map.insert("sources".to_string(), self.sources.to_json());
+        map.insert("sourcesContent".to_string(), self.sources_content.to_json());
+        map.insert("names".to_string(), self.names.to_json());
+        map.insert("mappings".to_string(), self.mappings.to_json());
+
+        Json::Object(map).to_string()
+    }
+}
+
+/// Returns the number of UTF-16 code units, in which source map columns
+/// are counted, that a byte of UTF-8 encoded text adds to a column.
+/// Continuation bytes add nothing, and characters taking four bytes are
+/// encoded as a surrogate pair.
+#[inline]
+pub fn utf16_width(byte: u8) -> usize {
+    match byte {
+        0x80..=0xBF => 0,
+        0xF0..=0xFF => 2,
+        _           => 1,
+    }
+}
+
+/// Writes the difference between `value` and `previous` as a Base64 VLQ.
+#[inline]
+fn write_vlq(out: &mut String, value: usize, previous: usize) {
+    let mut vlq = if value >= previous {
+        ((value - previous) as u64) << 1
+    } else {
+        (((previous - value) as u64) << 1) | 1
+    };
+
+    loop {
+        let mut digit = vlq & 0b11111;
+
+        vlq >>= 5;
+
+        if vlq != 0 {
+            digit |= 0b100000;
+        }
+
+        out.push(BASE64[digit as usize] as char);
+
+        if vlq == 0 {
+            break;
+        }
+    }
+}
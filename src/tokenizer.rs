@@ -2,12 +2,13 @@ use std::str;
 use lexicon::Token;
 use lexicon::Token::*;
 use lexicon::ReservedKind::*;
-use grammar::OwnedSlice;
+use grammar::{ OwnedSlice, Span };
 use grammar::OperatorType::*;
 use grammar::VariableDeclarationKind::*;
 use grammar::LiteralValue;
 use grammar::LiteralValue::*;
-use error::{ Error, Result };
+use error::{ Error, Result, Expected, ParseError, ParseResult };
+use sourcemap::utf16_width;
 
 /// Helper macro for declaring byte-handler functions with correlating constants.
 /// This becomes handy due to a lookup table present below.
@@ -30,7 +31,7 @@ static BYTE_HANDLERS: [fn(&mut Tokenizer, u8) -> Result<Token>; 256] = [
     ZER, DIG, DIG, DIG, DIG, DIG, DIG, DIG, DIG, DIG, CTL, CTL, LSS, EQL, MOR, QST, // 3
     ___, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, // 4
     IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, IDT, CTL, ___, CTL, CRT, IDT, // 5
-    ___, IDT, L_B, L_C, L_D, L_E, L_F, IDT, IDT, L_I, IDT, IDT, L_L, IDT, L_N, IDT, // 6
+    TPL, IDT, L_B, L_C, L_D, L_E, L_F, IDT, IDT, L_I, IDT, IDT, L_L, IDT, L_N, IDT, // 6
     L_P, IDT, L_R, L_S, L_T, L_U, L_V, L_W, IDT, L_Y, IDT, CTL, PIP, CTL, TLD, ___, // 7
     UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, // 8
     UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, UNI, // 9
@@ -129,7 +130,7 @@ define_handlers! {
                 LesserEquals
             },
 
-            _ => LogicalNot
+            _ => Lesser
         };
 
         Ok(Operator(op))
@@ -158,7 +159,13 @@ define_handlers! {
                         }
                     },
 
-                    _ => BSRAssign
+                    b'=' => {
+                        tok.bump();
+
+                        BSRAssign
+                    },
+
+                    _ => BitShiftRight
                 }
             },
 
@@ -342,11 +349,13 @@ define_handlers! {
 
             b'*' => {
                 tok.bump();
-                tok.read_block_comment();
+                tok.read_block_comment()?;
 
                 return tok.get_token();
             },
 
+            _ if tok.regex_allowed => return tok.read_regex(),
+
             b'=' => {
                 tok.bump();
 
@@ -554,7 +563,7 @@ define_handlers! {
         tok.consume_label_characters();
 
         Ok(Identifier(unsafe {
-            let slice = tok.source.slice_unchecked(start, tok.index);
+            let slice = tok.source.get_unchecked(start..tok.index);
             OwnedSlice::from_str(slice)
         }))
     }
@@ -589,7 +598,7 @@ define_handlers! {
 
         while !tok.is_eof() {
             match tok.read_byte() {
-                b'0'...b'9' => {
+                b'0'..=b'9' => {
                     tok.bump();
                 },
                 b'.' => {
@@ -602,7 +611,7 @@ define_handlers! {
         }
 
         let value = unsafe {
-            let slice = tok.source.slice_unchecked(start, tok.index);
+            let slice = tok.source.get_unchecked(start..tok.index);
             OwnedSlice::from_str(slice)
         };
 
@@ -617,7 +626,7 @@ define_handlers! {
 
         while !tok.is_eof() {
             match tok.read_byte() {
-                b'0'...b'9' => {
+                b'0'..=b'9' => {
                     tok.bump();
                 },
                 b'.' => {
@@ -630,7 +639,7 @@ define_handlers! {
         }
 
         let value = unsafe {
-            let slice = tok.source.slice_unchecked(start, tok.index);
+            let slice = tok.source.get_unchecked(start..tok.index);
             OwnedSlice::from_str(slice)
         };
 
@@ -644,7 +653,7 @@ define_handlers! {
         tok.bump();
 
         match tok.peek_byte() {
-            b'0'...b'9' => {
+            b'0'..=b'9' => {
                 tok.bump();
 
                 Ok(Literal(tok.read_float(start)))
@@ -675,25 +684,32 @@ define_handlers! {
         tok.bump();
 
         loop {
-            let ch = tok.expect_byte();
+            let ch = tok.expect_byte()?;
 
             if ch == byte {
                 break;
             }
 
             if ch == b'\\' {
-                tok.expect_byte();
+                tok.expect_byte()?;
             }
         }
 
         let value = unsafe {
-            let slice = tok.source.slice_unchecked(start, tok.index);
+            let slice = tok.source.get_unchecked(start..tok.index);
             OwnedSlice::from_str(slice)
         };
 
         Ok(Literal(LiteralString(value)))
     }
 
+    // `
+    const TPL: template |tok, _| {
+        tok.bump();
+
+        tok.read_template()
+    }
+
     // One of: ( ) [ ] { } : ; ,
     const CTL: control_sign |tok, byte| {
         tok.bump();
@@ -775,6 +791,25 @@ pub struct Tokenizer<'a> {
 
     // Index of current token in source
     pub token_start: usize,
+
+    // Line and column of current token in source
+    token_line: usize,
+    token_column: usize,
+
+    // Index up to which lines have been counted, the line number at that
+    // index and the column of that index in UTF-16 code units
+    line_cursor: usize,
+    line: usize,
+    line_column: usize,
+
+    // Index at which the last consumed token ends
+    last_end: usize,
+
+    // Was there a line termination between current and previous token
+    line_terminated: bool,
+
+    // Can a `/` start a regular expression literal, given the previous token
+    regex_allowed: bool,
 }
 
 impl<'a> Tokenizer<'a> {
@@ -786,6 +821,14 @@ impl<'a> Tokenizer<'a> {
             length: source.len(),
             token: None,
             token_start: 0,
+            token_line: 1,
+            token_column: 0,
+            line_cursor: 0,
+            line: 1,
+            line_column: 0,
+            last_end: 0,
+            line_terminated: false,
+            regex_allowed: true,
         }
     }
 
@@ -803,7 +846,7 @@ impl<'a> Tokenizer<'a> {
     // is virtually irrelevant.
     #[inline]
     fn read_byte(&self) -> u8 {
-        unsafe { *self.byte_ptr.offset(self.index as isize) }
+        unsafe { *self.byte_ptr.add(self.index) }
     }
 
     #[inline]
@@ -847,14 +890,14 @@ impl<'a> Tokenizer<'a> {
     // }
 
     #[inline]
-    fn expect_byte(&mut self) -> u8 {
+    fn expect_byte(&mut self) -> Result<u8> {
         if self.is_eof() {
-            panic!("Unexpected end of source");
+            return Err(Error::UnexpectedEndOfProgram);
         }
 
         let ch = self.read_byte();
         self.bump();
-        ch
+        Ok(ch)
     }
 
     #[inline]
@@ -886,7 +929,7 @@ impl<'a> Tokenizer<'a> {
         while !self.is_eof() {
             let peek = self.read_byte();
             let digit = match peek {
-                b'0'...b'7' => peek - b'0',
+                b'0'..=b'7' => peek - b'0',
                 _           => break
             };
 
@@ -904,9 +947,9 @@ impl<'a> Tokenizer<'a> {
         while !self.is_eof() {
             let peek = self.read_byte();
             let digit = match peek {
-                b'0'...b'9' => peek - b'0',
-                b'a'...b'f' => peek - b'a' + 10,
-                b'A'...b'F' => peek - b'A' + 10,
+                b'0'..=b'9' => peek - b'0',
+                b'a'..=b'f' => peek - b'a' + 10,
+                b'A'..=b'F' => peek - b'A' + 10,
                 _           => break
             };
 
@@ -914,7 +957,7 @@ impl<'a> Tokenizer<'a> {
             self.bump();
         }
 
-        return LiteralInteger(value);
+        LiteralInteger(value)
     }
 
     #[inline]
@@ -928,7 +971,7 @@ impl<'a> Tokenizer<'a> {
         }
 
         unsafe {
-            self.source.slice_unchecked(start, self.index)
+            self.source.get_unchecked(start..self.index)
         }
     }
 
@@ -937,16 +980,107 @@ impl<'a> Tokenizer<'a> {
         while !self.is_eof() {
             let ch = self.read_byte();
             match ch {
-                b'0'...b'9' => self.bump(),
+                b'0'..=b'9' => self.bump(),
                 _           => break
             }
         }
 
         LiteralValue::LiteralFloat(unsafe {
-            OwnedSlice::from_str(self.source.slice_unchecked(start, self.index))
+            OwnedSlice::from_str(self.source.get_unchecked(start..self.index))
         })
     }
 
+    // Reads a regular expression literal, the opening `/` has already been
+    // consumed at this point.
+    #[inline]
+    fn read_regex(&mut self) -> Result<Token> {
+        let start = self.index;
+        let mut in_class = false;
+
+        loop {
+            match self.expect_byte()? {
+                b'\\'             => {
+                    self.expect_byte()?;
+                },
+                b'['              => in_class = true,
+                b']'              => in_class = false,
+                b'/' if !in_class => break,
+                b'\n'             => return Err(Error::UnexpectedToken {
+                    start: self.index - 1,
+                    end: self.index,
+                }),
+                _                 => {}
+            }
+        }
+
+        let pattern = unsafe {
+            OwnedSlice::from_str(self.source.get_unchecked(start..self.index - 1))
+        };
+
+        let flags_start = self.index;
+        let mut seen = 0u8;
+
+        while !self.is_eof() && ident_lookup::TABLE[self.read_byte() as usize] {
+            let flag = match self.read_byte() {
+                b'g' => 1,
+                b'i' => 2,
+                b'm' => 4,
+                b's' => 8,
+                b'u' => 16,
+                b'y' => 32,
+                _    => 0,
+            };
+
+            self.bump();
+
+            if flag == 0 || seen & flag != 0 {
+                return Err(Error::InvalidRegExFlags {
+                    start: flags_start,
+                    end: self.index,
+                });
+            }
+
+            seen |= flag;
+        }
+
+        let flags = unsafe {
+            OwnedSlice::from_str(self.source.get_unchecked(flags_start..self.index))
+        };
+
+        Ok(Literal(LiteralRegEx {
+            pattern: pattern,
+            flags: flags,
+        }))
+    }
+
+    // Reads a part of a template string up to either the closing backtick
+    // or the `${` opening an embedded expression.
+    #[inline]
+    fn read_template(&mut self) -> Result<Token> {
+        let start = self.index;
+
+        loop {
+            match self.expect_byte()? {
+                b'`'                             => break,
+                b'$' if self.peek_byte() == b'{' => {
+                    self.bump();
+
+                    return Ok(TemplateOpen(unsafe {
+                        OwnedSlice::from_str(self.source.get_unchecked(start..self.index - 2))
+                    }));
+                },
+                b'\\'                            => {
+                    self.expect_byte()?;
+                },
+                _                                => {}
+            }
+        }
+
+        Ok(TemplateClosed(unsafe {
+            OwnedSlice::from_str(self.source.get_unchecked(start..self.index - 1))
+        }))
+    }
+
     #[inline]
     fn read_comment(&mut self) {
         while !self.is_eof() {
@@ -958,44 +1092,136 @@ impl<'a> Tokenizer<'a> {
     }
 
     #[inline]
-    fn read_block_comment(&mut self) {
+    fn read_block_comment(&mut self) -> Result<()> {
         loop {
-            if self.expect_byte() == b'*' && self.expect_byte() == b'/' {
-                return;
+            match self.expect_byte()? {
+                b'*' if self.peek_byte() == b'/' => {
+                    self.bump();
+                    return Ok(());
+                },
+                b'\n' => self.line_terminated = true,
+                _      => {}
             }
         }
     }
 
     #[inline]
-    pub fn peek(&mut self) -> Token {
+    pub fn peek(&mut self) -> Result<Token> {
         match self.token {
-            Some(token) => token,
+            Some(token) => Ok(token),
 
             None => {
-                let token = self.get_token().unwrap();
+                let token = self.read_token()?;
 
                 self.token = Some(token);
 
-                token
+                Ok(token)
             }
         }
     }
 
     #[inline]
-    pub fn next(&mut self) -> Token {
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Token> {
         match self.token {
             Some(token) => {
                 self.consume();
 
-                token
+                Ok(token)
             },
-            None => self.get_token().unwrap()
+            None => {
+                let token = self.read_token()?;
+
+                self.last_end = self.index;
+
+                Ok(token)
+            }
         }
     }
 
     #[inline]
     pub fn consume(&mut self) {
         self.token = None;
+        self.last_end = self.index;
+    }
+
+    /// Reads the `/` or `/=` token that was just read as a division again,
+    /// as the start of a regular expression literal. The parser calls this
+    /// when the token is where an expression starts.
+    pub fn reread_as_regex(&mut self) -> Result<Token> {
+        self.index = self.token_start + 1;
+
+        let token = self.read_regex()?;
+
+        self.token = None;
+        self.last_end = self.index;
+        self.regex_allowed = false;
+
+        Ok(token)
+    }
+
+    /// Continues reading a template string once the `}` closing one of its
+    /// embedded expressions has been consumed.
+    pub fn template_continuation(&mut self) -> Result<Token> {
+        self.token_start = self.index;
+        self.locate_token();
+
+        let token = self.read_template()?;
+
+        self.last_end = self.index;
+        self.regex_allowed = !matches!(token, TemplateClosed(_));
+
+        Ok(token)
+    }
+
+    /// Returns the `Span` of the current (last read or peeked) token.
+    #[inline]
+    pub fn token_span(&self) -> Span {
+        Span {
+            start: self.token_start,
+            end: self.index,
+            line: self.token_line,
+            column: self.token_column,
+        }
+    }
+
+    /// Returns the index at which the last consumed token ends.
+    #[inline]
+    pub fn last_end(&self) -> usize {
+        self.last_end
+    }
+
+    /// Returns true if a line termination occured between the current
+    /// (last read or peeked) token and the one before it.
+    #[inline]
+    pub fn line_terminated(&self) -> bool {
+        self.line_terminated
+    }
+
+    #[inline]
+    fn read_token(&mut self) -> Result<Token> {
+        self.line_terminated = false;
+
+        let token = self.get_token()?;
+
+        // A `/` following anything that ends an expression is a division,
+        // otherwise it starts a regular expression. This isn't always right
+        // for `)` and `}`, as in `if (x) /re/.test(y)`, where the parser
+        // has to ask for the regular expression with `reread_as_regex`.
+        self.regex_allowed = !matches!(token,
+            Identifier(_)           |
+            Literal(_)              |
+            TemplateClosed(_)       |
+            This                    |
+            Super                   |
+            Reserved(_)             |
+            Control(b')')           |
+            Control(b']')           |
+            Control(b'}')           |
+            Operator(Increment)     |
+            Operator(Decrement));
+
+        Ok(token)
     }
 
     #[inline]
@@ -1003,6 +1229,7 @@ impl<'a> Tokenizer<'a> {
         self.consume_whitespace();
 
         self.token_start = self.index;
+        self.locate_token();
 
         if self.is_eof() {
             return Ok(EndOfProgram);
@@ -1013,13 +1240,37 @@ impl<'a> Tokenizer<'a> {
         BYTE_HANDLERS[ch as usize](self, ch)
     }
 
+    // Counts lines between the previous and current token, so that the
+    // source is only scanned once no matter how many tokens are read.
+    #[inline]
+    fn locate_token(&mut self) {
+        let bytes = self.source.as_bytes();
+
+        while self.line_cursor < self.token_start {
+            if bytes[self.line_cursor] == b'\n' {
+                self.line += 1;
+                self.line_column = 0;
+            } else {
+                self.line_column += utf16_width(bytes[self.line_cursor]);
+            }
+
+            self.line_cursor += 1;
+        }
+
+        self.token_line = self.line;
+        self.token_column = self.line_column;
+    }
+
     #[inline]
     fn consume_whitespace(&mut self) {
         while !self.is_eof() {
             let ch = self.read_byte();
 
-            // if ch <= 0x20 {
             if whitespace::TABLE[ch as usize] {
+                if ch == b'\n' {
+                    self.line_terminated = true;
+                }
+
                 self.bump();
                 continue;
             }
@@ -1028,39 +1279,84 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    /// Creates an error for a token that was just read from the source.
+    pub fn unexpected(&self, token: Token, expected: &[Expected]) -> ParseError {
+        match token {
+            EndOfProgram => ParseError::UnexpectedEndOfProgram {
+                expected: expected.to_vec(),
+            },
+            _ => ParseError::UnexpectedToken {
+                token: self.source[self.token_start..self.index].to_string(),
+                start: self.token_start,
+                end: self.index,
+                expected: expected.to_vec(),
+            }
+        }
+    }
+
     #[inline]
-    pub fn expect_identifier(&mut self) -> OwnedSlice {
-        match self.next() {
-            Identifier(ident) => ident,
-            token             => panic!("Unexpected token `{:?}` {}", token, self.index)
+    pub fn expect_identifier(&mut self) -> ParseResult<OwnedSlice> {
+        match self.next()? {
+            Identifier(ident) => Ok(ident),
+            token             => Err(self.unexpected(token, &[Expected::Identifier]))
+        }
+    }
+
+    /// Like `expect_identifier`, but also accepts keywords and reserved
+    /// words, which are valid property names after `.` (`promise.catch`).
+    #[inline]
+    pub fn expect_property_name(&mut self) -> ParseResult<OwnedSlice> {
+        let token = self.next()?;
+        let slice = &self.source[self.token_start..self.index];
+
+        match token {
+            Identifier(ident) => Ok(ident),
+            token             => {
+                let is_word = slice.bytes().next().is_some_and(|byte| byte.is_ascii_alphabetic()) &&
+                              slice.bytes().all(|byte| byte.is_ascii_alphanumeric());
+
+                if is_word {
+                    Ok(unsafe { OwnedSlice::from_str(slice) })
+                } else {
+                    Err(self.unexpected(token, &[Expected::Identifier]))
+                }
+            }
         }
     }
 
     #[inline]
-    pub fn expect_semicolon(&mut self) {
-        match self.peek() {
+    pub fn expect_semicolon(&mut self) -> ParseResult<()> {
+        match self.peek()? {
             Control(b';') => self.consume(),
             Control(b')') |
             Control(b'}') |
-            EndOfProgram  => return,
-            token         => panic!("Unexpected token `{:?}` {}", token, self.index)
+            EndOfProgram  => {},
+            token         => {
+                if !self.line_terminated {
+                    return Err(self.unexpected(token, &[Expected::Token(Control(b';'))]));
+                }
+            }
         }
+
+        Ok(())
     }
 
     #[inline]
-    pub fn expect_control(&mut self, expected: u8) {
-        let token = self.next();
+    pub fn expect_control(&mut self, expected: u8) -> ParseResult<()> {
+        let token = self.next()?;
 
         if token != Control(expected) {
-            panic!("Unexpected token `{:?}` {}", token, self.index);
+            return Err(self.unexpected(token, &[Expected::Token(Control(expected))]));
         }
+
+        Ok(())
     }
 
     #[inline]
-    pub fn allow_control(&mut self) -> u8 {
-        match self.peek() {
+    pub fn allow_control(&mut self) -> Result<u8> {
+        Ok(match self.peek()? {
             Control(byte) => byte,
             _             => 0
-        }
+        })
     }
 }
@@ -1,8 +1,13 @@
 use std::mem;
+use std::collections::{ HashMap, HashSet };
 
+use error::{ TransformError, TransformResult };
 use grammar::*;
 use grammar::ClassMember::*;
 use grammar::OperatorType::*;
+use mangler;
+use scope::{ self, BindingKind, ScopeId, ScopeTree };
+use visitor::{ Visitor, Visitable };
 
 pub struct Settings {
     pub transform_block_scope: bool,
@@ -10,488 +15,3921 @@ pub struct Settings {
     pub transform_object: bool,
     pub transform_exponentation: bool,
     pub transform_class_properties: bool,
+    // `catch` without a binding gets an unused one
+    pub transform_catch_binding: bool,
     pub transform_class: bool,
+    pub transform_template_strings: bool,
+    pub transform_destructuring: bool,
+    pub transform_parameters: bool,
+    pub transform_spread: bool,
+    pub transform_commonjs: bool,
+    // Dynamic `import()` loads chunks with the bundle runtime
+    pub transform_chunk_imports: bool,
+    // Rename the variables of functions to short names
+    pub mangle: bool,
 }
 
-trait Take {
-    fn take(&mut self) -> Self;
-}
+/// State of a single transformation pass over a `Program`.
+struct Transformer<'a> {
+    settings: &'a Settings,
 
-impl<T> Take for Vec<T> {
-    #[inline]
-    fn take(&mut self) -> Self {
-        mem::replace(self, Vec::new())
-    }
-}
+    // Storage for strings created during the transformation
+    strings: &'a mut Strings,
 
-impl Take for Expression {
-    #[inline]
-    fn take(&mut self) -> Self {
-        mem::replace(self, Expression::This)
-    }
-}
+    // Declarations to be put at the top of the program
+    hoisted: Vec<VariableDeclarator>,
 
-#[inline]
-fn bind_this(function: Expression) -> Expression {
-    Expression::call(Expression::member(function, "bind"), vec![Expression::This])
+    // Temporary variables created in the function being transformed
+    temporaries: Vec<OwnedSlice>,
+
+    // Counter used to generate unique names
+    uid: usize,
+
+    // Names of the helper functions used by the program
+    helpers: Vec<&'static str>,
+
+    // Parent class `super` refers to in the class member being lowered,
+    // and whether that member is static
+    superclass: Option<(OwnedSlice, bool)>,
+
+    // Class `this` refers to in the static property initializer being
+    // transformed, outside of nested functions
+    static_this: Option<OwnedSlice>,
+
+    // New names of the block scoped bindings that would clash with other
+    // variables once lowered to `var`, and of the mangled bindings, by the
+    // address of each identifier referring to them
+    renames: HashMap<usize, OwnedSlice>,
+
+    // Block scoped bindings, by the address of their declared name
+    block_bindings: HashMap<usize, BlockBinding>,
+
+    // Variable of the required module and the name of the property read
+    // from it for each imported binding, by the address of each identifier
+    // referring to them
+    imports: HashMap<usize, (OwnedSlice, OwnedSlice)>,
+
+    // Number of loops the statement being transformed is nested in
+    loops: usize,
+
+    // First error found while transforming the program, returned once
+    // the transformation is done
+    error: Option<TransformError>,
 }
 
-impl Settings {
-    pub fn target_es5() -> Settings {
-        let mut settings = Settings::target_es2015();
+/// What the scope analysis found out about a `let` or `const` binding.
+struct BlockBinding {
+    // Binding is referenced from a closure
+    captured: bool,
 
-        settings.transform_block_scope = true;
-        settings.transform_arrow = true;
-        settings.transform_object = true;
-        settings.transform_class = true;
+    // Source offsets of the assignments to the binding
+    writes: Vec<usize>,
+}
 
-        settings
-    }
+/// Bindings of a loop body that gets moved into a closure.
+struct LoopClosure {
+    // Loop head declares block scoped bindings, passed to the closure
+    head: bool,
 
-    pub fn target_es2015() -> Settings {
-        let mut settings = Settings::no_transform();
+    // Names of the head bindings assigned to in the body
+    written: Vec<OwnedSlice>,
 
-        settings.transform_exponentation = true;
-        settings.transform_class_properties = true;
+    // Variables declared with `var` in the body
+    vars: Vec<OwnedSlice>,
 
-        settings
-    }
+    // Variable holding the `arguments` of the enclosing function, read
+    // in the body
+    arguments: Option<OwnedSlice>,
+}
 
-    pub fn no_transform() -> Settings {
-        Settings {
-            transform_block_scope: false,
-            transform_arrow: false,
-            transform_object: false,
-            transform_exponentation: false,
-            transform_class_properties: false,
-            transform_class: false,
+impl<'a> Transformer<'a> {
+    fn new(settings: &'a Settings, strings: &'a mut Strings) -> Self {
+        Transformer {
+            settings: settings,
+            strings: strings,
+            hoisted: Vec::new(),
+            temporaries: Vec::new(),
+            uid: 0,
+            helpers: Vec::new(),
+            superclass: None,
+            static_this: None,
+            renames: HashMap::new(),
+            block_bindings: HashMap::new(),
+            imports: HashMap::new(),
+            loops: 0,
+            error: None,
         }
     }
-}
 
-/// The `Transformable` trait provides an interface for instances of grammar
-/// to alter the AST.
-trait Transformable {
+    /// Generates a name that's unique within the program.
     #[inline]
-    fn transform(&mut self, _: &Settings) {}
+    fn unique_name(&mut self, prefix: &str) -> OwnedSlice {
+        self.uid += 1;
+        self.strings.alloc(format!("{}{}", prefix, self.uid))
+    }
 
+    /// Declares a variable at the top of the program.
     #[inline]
-    fn contains_this(&self) -> bool {
-        false
+    fn hoist(&mut self, name: OwnedSlice, value: Option<Expression>) {
+        self.hoisted.push(VariableDeclarator {
+            name: Pattern::Identifier(name),
+            value: value.map(Loc::from),
+        });
     }
-}
 
-impl<T: Transformable> Transformable for Option<T> {
+    /// Declares a temporary variable at the top of the function being
+    /// transformed, or of the program outside of functions.
     #[inline]
-    fn transform(&mut self, settings: &Settings) {
-        if let Some(ref mut value) = *self {
-            value.transform(settings);
-        }
+    fn declare_temporary(&mut self, name: OwnedSlice) {
+        self.temporaries.push(name);
     }
 
+    /// Transforms a function, returning the temporary variables created
+    /// in it, which have to be declared at the top of its body.
     #[inline]
-    fn contains_this(&self) -> bool {
-        match *self {
-            Some(ref value) => value.contains_this(),
-            _               => false,
-        }
+    fn own_temporaries<F: FnOnce(&mut Self)>(&mut self, transform: F) -> Vec<OwnedSlice> {
+        let outer = self.temporaries.take();
+
+        transform(self);
+        mem::replace(&mut self.temporaries, outer)
     }
-}
 
-impl<T: Transformable> Transformable for Box<T> {
+    /// Stores the `value` in a new temporary variable. The variable is
+    /// declared by one of the `bindings` if `declare` is set, otherwise
+    /// it's declared as a temporary and the binding becomes an assignment.
     #[inline]
-    fn transform(&mut self, settings: &Settings) {
-        self.as_mut().transform(settings)
+    fn temporary(&mut self, value: Expression, declare: bool, bindings: &mut Vec<(Pattern, Expression)>) -> Expression {
+        let name = self.unique_name("_ref");
+
+        if !declare {
+            self.declare_temporary(name);
+        }
+
+        bindings.push((Pattern::Identifier(name), value));
+
+        Expression::Identifier(name)
     }
 
+    /// Returns an expression that can be evaluated multiple times to get
+    /// the `value`, using a temporary variable unless it's an identifier.
     #[inline]
-    fn contains_this(&self) -> bool {
-        self.as_ref().contains_this()
+    fn reference(&mut self, value: Expression, declare: bool, bindings: &mut Vec<(Pattern, Expression)>) -> Expression {
+        match value {
+            Expression::Identifier(_) => value,
+            _                         => self.temporary(value, declare, bindings),
+        }
     }
-}
 
-impl Transformable for Parameter {}
+    /// Breaks the `pattern` down to a list of `bindings`, each assigning
+    /// a part of the `value` to an identifier or a member expression.
+    fn flatten_pattern(
+        &mut self,
+        pattern: Pattern,
+        value: Expression,
+        declare: bool,
+        bindings: &mut Vec<(Pattern, Expression)>
+    ) {
+        match pattern {
+            Pattern::Identifier(_) |
+            Pattern::Expression(_) => bindings.push((pattern, value)),
 
-impl Transformable for Expression {
-    fn transform(&mut self, settings: &Settings) {
-        *self = match *self {
-            Expression::ArrowFunction {
-                ref mut params,
-                ref mut body,
+            Pattern::Rest(pattern) => self.flatten_pattern(*pattern, value, declare, bindings),
+
+            Pattern::Default {
+                pattern,
+                value: default,
             } => {
-                params.transform(settings);
-                body.transform(settings);
+                let reference = self.reference(value, declare, bindings);
+                let value = Expression::Conditional {
+                    test: Box::new(Expression::binary(
+                        reference.clone(),
+                        StrictEquality,
+                        void_zero()
+                    ).into()),
+                    consequent: default,
+                    alternate: Box::new(reference.into()),
+                };
 
-                // transformation flag check
-                if !settings.transform_arrow {
-                    return;
-                }
+                self.flatten_pattern(*pattern, value, declare, bindings);
+            },
 
-                let body = match **body {
-                    Statement::Block { ref mut body }   => body.split_off(0),
-                    Statement::Expression { ref mut value } => vec![
-                        Statement::Return {
-                            value: Some(value.take())
-                        }
-                    ],
-                    ref statement => {
-                        panic!("Invalid arrow function body {:#?}", statement);
-                    }
+            Pattern::Object(properties) => {
+                // Single property can be read without storing the object
+                let object = if properties.len() == 1 && value.binding_power() >= 17 {
+                    value
+                } else {
+                    self.reference(value, declare, bindings)
                 };
 
-                let bind = body.contains_this();
+                for property in properties {
+                    let value = property_access(object.clone(), property.key);
 
-                let function = Expression::Function {
-                    name: None,
-                    params: params.split_off(0),
-                    body: body,
-                };
+                    self.flatten_pattern(property.value, value, declare, bindings);
+                }
+            },
 
-                if bind {
-                    bind_this(function)
+            Pattern::Array(elements) => {
+                let array = if elements.len() == 1 && value.binding_power() >= 17 {
+                    value
                 } else {
-                    function
+                    self.reference(value, declare, bindings)
+                };
+
+                for (index, element) in elements.into_iter().enumerate() {
+                    let element = match element {
+                        Some(element) => element,
+                        None          => continue,
+                    };
+
+                    let index = Expression::Literal(LiteralInteger(index as u64));
+                    let value = match element {
+                        Pattern::Rest(_) => Expression::call(
+                            Expression::member(array.clone(), "slice"),
+                            vec![index.into()]
+                        ),
+                        _ => Expression::ComputedMember {
+                            object: Box::new(array.clone().into()),
+                            property: Box::new(index.into()),
+                        },
+                    };
+
+                    self.flatten_pattern(element, value, declare, bindings);
                 }
             },
+        }
+    }
 
-            Expression::Array(ref mut items) => {
-                items.transform(settings);
-                return;
-            },
+    /// Lowers an assignment to a destructuring `pattern` into a sequence
+    /// of simple assignments, which evaluates to the assigned `value`.
+    fn destructuring_assignment(&mut self, pattern: Pattern, value: Expression) -> Expression {
+        let mut bindings = Vec::new();
+        let mut names = Vec::new();
 
-            Expression::Object(ref mut members) => {
-                members.transform(settings);
+        pattern.names(&mut names);
 
-                // transformation flag check
-                if !settings.transform_object {
-                    return;
-                }
+        // Value has to be copied if the pattern overwrites it midway
+        let reference = match value {
+            Expression::Identifier(name) if !names.contains(&name) => value,
+            _ => self.temporary(value, false, &mut bindings),
+        };
 
-                let mut computed = partition_vec(members, |member| {
-                    match member {
-                        &ObjectMember::Computed { .. } => false,
-                        _                              => true,
-                    }
-                });
+        self.flatten_pattern(pattern, reference.clone(), false, &mut bindings);
 
-                if computed.is_empty() {
-                    return;
-                }
+        let mut sequence: Vec<Loc<Expression>> = bindings.into_iter().map(|(target, value)| {
+            Expression::binary(pattern_target(target), Assign, value).into()
+        }).collect();
 
-                let literal = members.take();
+        sequence.push(reference.into());
 
-                let mut body = Vec::with_capacity(computed.len() + 2);
+        Expression::Sequence(sequence)
+    }
 
-                body.push(Statement::VariableDeclaration {
-                    kind: VariableDeclarationKind::Var,
-                    declarators: vec![
-                        VariableDeclarator {
-                            name: "___".into(),
-                            value: Some(Expression::Object(literal)),
-                        }
-                    ]
-                });
+    /// Replaces declarators with destructuring patterns by declarators
+    /// of each of the bound variables.
+    fn lower_declarators(&mut self, declarators: &mut Vec<VariableDeclarator>) {
+        if declarators.iter().all(|declarator| declarator.name.is_identifier()) {
+            return;
+        }
 
-                for member in computed.drain(..) {
-                    if let ObjectMember::Computed { key, value } = member {
-                        body.push(
-                            Expression::binary(
-                                Expression::ComputedMember {
-                                    object: Box::new("___".into()),
-                                    property: Box::new(key),
-                                },
-                                Assign,
-                                value
-                            ).into()
-                        );
-                    }
+        let mut lowered = Vec::with_capacity(declarators.len());
+
+        for declarator in declarators.drain(..) {
+            let (pattern, value) = match declarator {
+                VariableDeclarator {
+                    name,
+                    value: Some(value),
+                } if !name.is_identifier() => (name, value),
+                _ => {
+                    lowered.push(declarator);
+                    continue;
                 }
+            };
 
-                body.push(Statement::Return {
-                    value: Some("___".into())
+            let mut bindings = Vec::new();
+
+            self.flatten_pattern(pattern, value.item, true, &mut bindings);
+
+            for (name, value) in bindings {
+                lowered.push(VariableDeclarator {
+                    name: name,
+                    value: Some(value.into()),
                 });
+            }
+        }
 
-                Expression::call(Expression::Function {
-                    name: None,
-                    params: Vec::new(),
-                    body: body,
-                }, Vec::new())
-            },
+        *declarators = lowered;
+    }
 
-            Expression::Call {
-                ref mut callee,
-                ref mut arguments,
+    /// Creates a call to the `apply` method of the `callee`, keeping the
+    /// object a method was read from as `this`.
+    fn apply_call(&mut self, callee: Loc<Expression>, arguments: Expression) -> Expression {
+        let span = callee.span;
+
+        let (callee, this) = match callee.item {
+            Expression::Member {
+                object,
+                property,
             } => {
-                callee.transform(settings);
-                arguments.transform(settings);
-                return;
+                let (object, this) = self.receiver(*object);
+
+                (Expression::Member {
+                    object: Box::new(object),
+                    property: property,
+                }, this)
             },
 
-            Expression::Binary {
-                ref mut left,
-                ref mut operator,
-                ref mut right,
+            Expression::ComputedMember {
+                object,
+                property,
             } => {
-                left.transform(settings);
-                right.transform(settings);
+                let (object, this) = self.receiver(*object);
 
-                if !settings.transform_exponentation {
-                    return;
+                (Expression::ComputedMember {
+                    object: Box::new(object),
+                    property: property,
+                }, this)
+            },
+
+            callee => (callee, void_zero()),
+        };
+
+        Expression::call(
+            Expression::member(Loc::new(span, callee), "apply"),
+            vec![this.into(), arguments.into()]
+        )
+    }
+
+    /// Returns the `object` of a method call, along with an expression
+    /// that can be passed as `this` without evaluating the object again.
+    #[inline]
+    fn receiver(&mut self, object: Loc<Expression>) -> (Loc<Expression>, Expression) {
+        match object.item {
+            Expression::This             => (object, Expression::This),
+            Expression::Identifier(name) => (object, Expression::Identifier(name)),
+            _ => {
+                let name = self.unique_name("_ref");
+
+                self.declare_temporary(name);
+
+                (Expression::binary(name, Assign, object).into(), Expression::Identifier(name))
+            }
+        }
+    }
+
+    /// Checks if any of the `params` is going to be moved to the body
+    /// of its function by `lower_parameters`.
+    #[inline]
+    fn lowers_parameters(&self, params: &[Parameter]) -> bool {
+        params.iter().any(|param| {
+            (self.settings.transform_destructuring && !param.name.is_identifier()) ||
+            (self.settings.transform_parameters && (param.default.is_some() || param.rest))
+        })
+    }
+
+    /// Moves destructuring, default values and the rest parameter into
+    /// statements at the beginning of the function `body`. Destructured
+    /// parameters are replaced with temporary names.
+    fn lower_parameters(&mut self, params: &mut Vec<Parameter>, body: &mut Vec<Loc<Statement>>) {
+        if !self.lowers_parameters(params) {
+            return;
+        }
+
+        let mut prelude = Vec::new();
+
+        let rest = match params.last() {
+            Some(param) if param.rest && self.settings.transform_parameters => params.pop(),
+            _ => None,
+        };
+
+        for param in params.iter_mut() {
+            let default = match param.default {
+                Some(_) if self.settings.transform_parameters => param.default.take(),
+                _                                             => None,
+            };
+
+            // Patterns with a default value are destructured only after
+            // the default is assigned to the temporary name.
+            let pattern = if !param.name.is_identifier()
+                          && (self.settings.transform_destructuring || default.is_some()) {
+                let name = self.unique_name("_ref");
+
+                Some(mem::replace(&mut param.name, Pattern::Identifier(name)))
+            } else {
+                None
+            };
+
+            let name = match param.name {
+                Pattern::Identifier(name) => name,
+                _                         => continue,
+            };
+
+            if let Some(default) = default {
+                prelude.push(Statement::If {
+                    test: Expression::binary(name, StrictEquality, void_zero()).into(),
+                    consequent: Box::new(Expression::binary(name, Assign, default).into()),
+                    alternate: None,
+                });
+            }
+
+            if let Some(pattern) = pattern {
+                declare(&mut prelude, pattern, Expression::Identifier(name));
+            }
+        }
+
+        if let Some(rest) = rest {
+            let index = Expression::Literal(LiteralInteger(params.len() as u64));
+
+            declare(&mut prelude, rest.name, Expression::call(
+                Expression::member(Expression::member(Expression::member("Array", "prototype"), "slice"), "call"),
+                vec![Expression::from("arguments").into(), index.into()]
+            ));
+        }
+
+        if self.settings.transform_destructuring {
+            for statement in prelude.iter_mut() {
+                if let Statement::VariableDeclaration { ref mut declarators, .. } = *statement {
+                    self.lower_declarators(declarators);
                 }
+            }
+        }
 
-                match *operator {
-                    Exponent => Expression::call(
-                        Expression::member("Math", "pow"),
-                        vec![left.take(), right.take()]
-                    ),
+        let mut prelude: Vec<Loc<Statement>> = prelude.into_iter().map(Loc::from).collect();
 
-                    ExponentAssign => {
-                        *operator = Assign;
-                        *right = Box::new(Expression::call(
-                            Expression::member("Math", "pow"),
-                            vec![left.take(), right.take()]
-                        ));
-                        return;
-                    },
+        prelude.append(body);
+
+        *body = prelude;
+    }
 
+    /// Replaces a destructuring pattern in the head of a `for-in` or
+    /// `for-of` loop with a temporary variable, which is destructured
+    /// at the beginning of the loop `body`.
+    fn lower_loop_head(&mut self, left: &mut Loc<Statement>, body: &mut Loc<Statement>) {
+        // transformation flag check
+        if !self.settings.transform_destructuring {
+            return;
+        }
+
+        let statement = match left.item {
+            Statement::VariableDeclaration {
+                kind,
+                ref mut declarators,
+            } => {
+                let declarator = match declarators.first_mut() {
+                    Some(declarator) if !declarator.name.is_identifier() => declarator,
                     _ => return,
+                };
+
+                let name = self.unique_name("_ref");
+                let pattern = mem::replace(&mut declarator.name, Pattern::Identifier(name));
+
+                Statement::VariableDeclaration {
+                    kind: kind,
+                    declarators: vec![VariableDeclarator {
+                        name: pattern,
+                        value: Some(Expression::Identifier(name).into()),
+                    }],
+                }
+            },
+
+            Statement::Expression {
+                ref mut value,
+            } => {
+                if let Expression::Pattern(_) = value.item {} else {
+                    return;
                 }
-            }
+
+                let name = self.unique_name("_ref");
+                let pattern = mem::replace(&mut value.item, Expression::Identifier(name));
+
+                self.declare_temporary(name);
+
+                Expression::binary(pattern, Assign, Expression::Identifier(name)).into()
+            },
 
             _ => return,
+        };
+
+        let mut statement = Loc::new(left.span, statement);
+
+        statement.transform(self);
+
+        if let Statement::Block { ref mut body } = body.item {
+            body.insert(0, statement);
+            return;
         }
+
+        let span = body.span;
+        let inner = mem::replace(&mut body.item, Statement::Transparent { body: Vec::new() });
+
+        body.item = Statement::Block {
+            body: vec![statement, Loc::new(span, inner)],
+        };
     }
 
-    fn contains_this(&self) -> bool {
-        match *self {
-            Expression::This => true,
+    /// Records what the scope analysis found out about `let` and `const`
+    /// bindings. Block scoped bindings that would clash with a variable of
+    /// the function they end up in once lowered to `var` are renamed, and
+    /// reassigned constants are reported as an error.
+    fn analyze_block_scope(&mut self, tree: &ScopeTree) -> TransformResult<()> {
+        for reference in tree.references.iter().filter(|reference| reference.write) {
+            let kind = reference.binding.map(|binding| tree.bindings[binding].kind);
 
-            Expression::Array(ref items) => items.contains_this(),
+            if kind == Some(BindingKind::Variable(VariableDeclarationKind::Const)) {
+                return Err(TransformError::ConstAssignment {
+                    name: reference.name.to_string(),
+                    start: reference.span.start,
+                    end: reference.span.end,
+                });
+            }
+        }
 
-            Expression::Object(ref members) => members.contains_this(),
+        let mut used: HashSet<String> = HashSet::new();
+        let mut references: HashMap<OwnedSlice, Vec<usize>> = HashMap::new();
 
-            Expression::Member {
-                ref object,
-                ..
-            } => object.contains_this(),
+        for (index, reference) in tree.references.iter().enumerate() {
+            used.insert(reference.name.to_string());
+            references.entry(reference.name).or_default().push(index);
+        }
 
-            Expression::Call {
-                ref callee,
-                ref arguments,
-            } => callee.contains_this() || arguments.contains_this(),
+        used.extend(tree.bindings.iter().map(|binding| binding.name.to_string()));
 
-            Expression::Binary {
-                ref left,
-                ref right,
-                ..
-            } => left.contains_this() || right.contains_this(),
+        // Names of the block scoped bindings moved to each function scope
+        let mut hoisted: HashSet<(ScopeId, OwnedSlice)> = HashSet::new();
 
-            _ => false,
-        }
-    }
-}
+        for binding in &tree.bindings {
+            match binding.kind {
+                BindingKind::Variable(VariableDeclarationKind::Let)   |
+                BindingKind::Variable(VariableDeclarationKind::Const) |
+                BindingKind::Class                                    => {},
+                _                                                     => continue,
+            }
+
+            let function = tree.function_scope(binding.scope);
+            let uses = binding.references.iter().map(|&index| &tree.references[index]);
+
+            self.block_bindings.insert(address(&binding.name), BlockBinding {
+                captured: uses.clone().any(|reference| tree.function_scope(reference.scope) != function),
+                writes: uses.filter(|reference| reference.write).map(|reference| reference.span.start).collect(),
+            });
+
+            // Moved to the function scope, the binding must neither hide
+            // another one, nor capture references resolved outside of it
+            let clashes = binding.scope != function && (
+                tree.is_shadowed(binding.scope, &binding.name)
+                || hoisted.contains(&(function, binding.name))
+                || references.get(&binding.name).is_some_and(|indexes| indexes.iter().any(|&index| {
+                    let reference = &tree.references[index];
+
+                    tree.contains(function, reference.scope) && match reference.binding {
+                        Some(other) => !tree.contains(function, tree.bindings[other].scope),
+                        None        => true,
+                    }
+                }))
+            );
+
+            if !clashes {
+                hoisted.insert((function, binding.name));
+                continue;
+            }
+
+            let mut name = format!("_{}", binding.name);
+            let mut count = 1;
+
+            while used.contains(&name) {
+                count += 1;
+                name = format!("_{}{}", binding.name, count);
+            }
+
+            used.insert(name.clone());
+
+            let name = self.strings.alloc(name);
+
+            hoisted.insert((function, name));
+            self.renames.insert(address(&binding.name), name);
+
+            for &index in &binding.references {
+                self.renames.insert(address(&tree.references[index].name), name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renames the bindings declared in functions to the short names
+    /// picked by the mangler, on top of the block scoped renames.
+    fn mangle(&mut self, tree: &ScopeTree) {
+        let names: Vec<OwnedSlice> = tree.bindings.iter().map(|binding| self.renamed(binding.name)).collect();
+        let mangled = mangler::mangle(tree, &names);
+
+        for (binding, name) in tree.bindings.iter().zip(mangled) {
+            let name = match name {
+                Some(name) => self.strings.alloc(name),
+                None       => continue,
+            };
+
+            for declaration in &binding.declarations {
+                self.renames.insert(address(declaration), name);
+            }
+
+            for &index in &binding.references {
+                self.renames.insert(address(&tree.references[index].name), name);
+            }
+        }
+    }
+
+    /// Returns the new name of an identifier referring to a renamed
+    /// binding.
+    #[inline]
+    fn renamed(&self, name: OwnedSlice) -> OwnedSlice {
+        self.renames.get(&address(&name)).cloned().unwrap_or(name)
+    }
+
+    /// Returns the property of the required module read by an identifier
+    /// referring to an imported binding, so that the binding stays live.
+    #[inline]
+    fn imported(&self, name: OwnedSlice) -> Option<Expression> {
+        self.imports.get(&address(&name)).map(|&(module, property)| Expression::member(module, property))
+    }
+
+    /// Checks if a loop declares a `let` or `const` binding captured by a
+    /// closure. ES5 can't create a new copy of the binding for each
+    /// iteration, so the loop body is going to be moved into a function,
+    /// with its `var` declarations turned into assignments, and `arguments`
+    /// renamed to a variable declared outside of it.
+    fn loop_closure(&mut self, head: Option<&Statement>, body: &mut Loc<Statement>) -> Option<LoopClosure> {
+        // transformation flag check
+        if !self.settings.transform_block_scope {
+            return None;
+        }
+
+        let mut names = Vec::new();
+
+        if let Some(&Statement::VariableDeclaration { kind, ref declarators }) = head {
+            if kind != VariableDeclarationKind::Var {
+                for declarator in declarators {
+                    declarator.name.names(&mut names);
+                }
+            }
+        }
+
+        let head_names = names.len();
+
+        block_declarations(&body.item, &mut names);
+
+        let captured = names.iter().any(|name| {
+            self.block_bindings.get(&address(name)).is_some_and(|binding| binding.captured)
+        });
+
+        if !captured {
+            return None;
+        }
+
+        let span = body.span;
+        let written = names[.. head_names].iter().filter(|name| {
+            self.block_bindings.get(&address(name)).is_some_and(|binding| {
+                binding.writes.iter().any(|&offset| offset >= span.start && offset < span.end)
+            })
+        }).map(|&name| self.renamed(name)).collect();
+
+        // References in an enclosing loop closure are already renamed
+        let references: Vec<usize> = arguments_references(&body.item).into_iter().filter(|address| {
+            !self.renames.contains_key(address)
+        }).collect();
+
+        let arguments = if references.is_empty() {
+            None
+        } else {
+            let name = self.unique_name("_arguments");
+
+            for address in references {
+                self.renames.insert(address, name);
+            }
+
+            Some(name)
+        };
+
+        let mut vars = Vec::new();
+
+        hoist_vars(&mut body.item, &mut vars);
+
+        Some(LoopClosure {
+            head: head_names != 0,
+            written: written,
+            vars: vars,
+            arguments: arguments,
+        })
+    }
+
+    /// Transforms a function, in which `this` is left as is inside of
+    /// a static property initializer, since it's the function's own. The
+    /// temporary variables it needs are declared at the top of its `body`.
+    #[inline]
+    fn own_this<F>(&mut self, body: &mut Vec<Loc<Statement>>, transform: F)
+        where F: FnOnce(&mut Self, &mut Vec<Loc<Statement>>)
+    {
+        let outer = self.static_this.take();
+        let temporaries = self.own_temporaries(|ctx| transform(ctx, body));
+
+        self.static_this = outer;
+        declare_temporaries(body, temporaries);
+    }
+
+    /// Transforms the body of a loop.
+    #[inline]
+    fn loop_body(&mut self, body: &mut Loc<Statement>) {
+        self.loops += 1;
+        body.transform(self);
+        self.loops -= 1;
+    }
+
+    /// Moves the body of a transformed loop into a closure, called on each
+    /// iteration with the bindings declared in the loop head. Jumps out of
+    /// the body are returned from the closure and taken after the call.
+    fn lower_loop(&mut self, mut statement: Statement, closure: LoopClosure) -> Statement {
+        let mut before = Vec::new();
+
+        if let Some(name) = closure.arguments {
+            before.push(var(name, Expression::from("arguments")).into());
+        }
+
+        if !closure.vars.is_empty() {
+            before.push(Statement::VariableDeclaration {
+                kind: VariableDeclarationKind::Var,
+                declarators: closure.vars.into_iter().map(|name| VariableDeclarator {
+                    name: Pattern::Identifier(self.renamed(name)),
+                    value: None,
+                }).collect(),
+            }.into());
+        }
+
+        {
+            let (head, body) = match statement {
+                Statement::For {
+                    ref init,
+                    ref mut body,
+                    ..
+                } => (init.as_ref().map(|init| &init.item), body),
+
+                Statement::ForIn {
+                    ref left,
+                    ref mut body,
+                    ..
+                } |
+                Statement::ForOf {
+                    ref left,
+                    ref mut body,
+                    ..
+                } => (Some(&left.item), body),
+
+                Statement::While {
+                    ref mut body,
+                    ..
+                } |
+                Statement::DoWhile {
+                    ref mut body,
+                    ..
+                } => (None, body),
+
+                _ => return statement,
+            };
+
+            let mut params = Vec::new();
+
+            if let (true, Some(head)) = (closure.head, head) {
+                declaration_names(head, &mut params);
+            }
+
+            // Head bindings assigned in the body are copied back to the loop
+            // through temporary variables
+            let mut copy_out = Vec::new();
+            let mut copy_in = Vec::new();
+
+            for binding in closure.written {
+                let copy = self.unique_name(&format!("_{}", binding));
+
+                self.declare_temporary(copy);
+                copy_out.push(Expression::binary(copy, Assign, binding).into());
+                copy_in.push(Expression::binary(binding, Assign, copy).into());
+            }
+
+            let mut jumps = Jumps {
+                copies: copy_out.clone(),
+                labels: Vec::new(),
+                taken: Vec::new(),
+                returns: false,
+            };
+
+            let mut statements = match body.item {
+                Statement::Block {
+                    ref mut body,
+                } => body.take(),
+                _ => vec![mem::replace(&mut **body, Statement::Transparent { body: Vec::new() }.into())],
+            };
+
+            for statement in &mut statements {
+                jumps.lower(statement, self, 0, 0);
+            }
+
+            statements.extend(copy_out);
+
+            let bind = statements.contains_this();
+            let function = Expression::Function {
+                name: None,
+                params: params.iter().map(|&name| Parameter {
+                    name: Pattern::Identifier(name),
+                    default: None,
+                    rest: false,
+                }).collect(),
+                body: statements,
+            };
+
+            let function = if bind {
+                bind_this(function)
+            } else {
+                function
+            };
+
+            let name = self.unique_name("_loop");
+            let call = Expression::call(name, params.iter().map(Loc::from).collect());
+
+            before.push(var(name, function).into());
+
+            let mut body_statements = Vec::new();
+
+            if jumps.taken.is_empty() && !jumps.returns {
+                body_statements.push(call.into());
+                body_statements.extend(copy_in);
+            } else {
+                let result = self.unique_name("_ret");
+
+                body_statements.push(var(result, call).into());
+                body_statements.extend(copy_in);
+
+                for (code, jump) in jumps.taken {
+                    body_statements.push(Statement::If {
+                        test: Expression::binary(result, StrictEquality, code).into(),
+                        consequent: Box::new(jump.into()),
+                        alternate: None,
+                    }.into());
+                }
+
+                if jumps.returns {
+                    let test = Expression::binary(Expression::Prefix {
+                        operator: Typeof,
+                        operand: Box::new(result.into()),
+                    }, StrictEquality, Expression::Literal(LiteralString("\"object\"".into())));
+
+                    body_statements.push(Statement::If {
+                        test: test.into(),
+                        consequent: Box::new(Statement::Return {
+                            value: Some(Expression::member(result, "v").into()),
+                        }.into()),
+                        alternate: None,
+                    }.into());
+                }
+            }
+
+            body.item = Statement::Block {
+                body: body_statements,
+            };
+        }
+
+        before.push(statement.into());
+
+        Statement::Transparent {
+            body: before,
+        }
+    }
+
+    /// Rewrites `import` and `export` declarations at the top level of
+    /// the program into CommonJS `require` calls and `exports` properties.
+    /// Exported bindings are defined as getters on `exports`, so that they
+    /// stay live, including the ones re-exported from other modules, while
+    /// imported bindings are read from the required module when used, which
+    /// is why writing to one is reported as an error.
+    fn lower_modules(&mut self, body: &mut Vec<Loc<Statement>>, tree: &ScopeTree) -> TransformResult<()> {
+        // transformation flag check
+        if !self.settings.transform_commonjs {
+            return Ok(());
+        }
+
+        for reference in tree.references.iter().filter(|reference| reference.write) {
+            let kind = reference.binding.map(|binding| tree.bindings[binding].kind);
+
+            if kind == Some(BindingKind::Import) {
+                return Err(TransformError::ImportAssignment {
+                    name: reference.name.to_string(),
+                    start: reference.span.start,
+                    end: reference.span.end,
+                });
+            }
+        }
+
+        let mut exports = false;
+        let mut getters = Vec::new();
+        let mut lowered = Vec::with_capacity(body.len());
+
+        for statement in body.drain(..) {
+            let span = statement.span;
+
+            let kind = match statement.item {
+                Statement::ImportDeclaration {
+                    specifiers,
+                    source,
+                } => {
+                    lowered.push(Loc::new(span, self.import_require(&specifiers, source, tree)));
+                    continue;
+                },
+
+                Statement::ExportDeclaration {
+                    kind,
+                } => kind,
+
+                item => {
+                    lowered.push(Loc::new(span, item));
+                    continue;
+                }
+            };
+
+            exports = true;
+
+            match kind {
+                ExportKind::Declaration(declaration) => {
+                    let mut names = Vec::new();
+
+                    declaration_names(&declaration, &mut names);
+
+                    for name in names {
+                        getters.push(self.export_getter(name, Expression::Identifier(name)));
+                    }
+
+                    lowered.push(*declaration);
+                },
+
+                ExportKind::Default(mut declaration) => {
+                    // Anonymous class needs a name for the getter
+                    if let Statement::Class { ref mut name, .. } = declaration.item {
+                        if name.as_str().is_empty() {
+                            *name = self.unique_name("_default");
+                        }
+                    }
+
+                    let name = match declaration.item {
+                        Statement::Function {
+                            name,
+                            ..
+                        } |
+                        Statement::Class {
+                            name,
+                            ..
+                        } => name,
+
+                        Statement::Expression {
+                            value,
+                        } => {
+                            let target = Expression::member("exports", "default");
+
+                            lowered.push(Loc::new(span, Expression::binary(target, Assign, value).into()));
+                            continue;
+                        },
+
+                        _ => unreachable!(),
+                    };
+
+                    getters.push(self.export_getter("default".into(), Expression::Identifier(name)));
+                    lowered.push(*declaration);
+                },
+
+                ExportKind::Named {
+                    specifiers,
+                    source: None,
+                } => {
+                    for specifier in specifiers {
+                        let value = self.imported(specifier.local).unwrap_or(Expression::Identifier(specifier.local));
+
+                        getters.push(self.export_getter(specifier.exported, value));
+                    }
+                },
+
+                ExportKind::Named {
+                    specifiers,
+                    source: Some(source),
+                } => {
+                    let default = specifiers.iter().any(|specifier| specifier.local.as_str() == "default");
+                    let module = self.module_name(source);
+                    let value = self.require(source, if default { Some(INTEROP_DEFAULT) } else { None });
+
+                    lowered.push(Loc::new(span, var(module, value)));
+
+                    for specifier in specifiers {
+                        let value = Expression::member(module, specifier.local);
+
+                        getters.push(self.export_getter(specifier.exported, value));
+                    }
+                },
+
+                ExportKind::All {
+                    source,
+                } => {
+                    let module = self.module_name(source);
+                    let value = self.require(source, None);
+
+                    lowered.push(Loc::new(span, var(module, value)));
+                    lowered.push(export_all(module).into());
+                },
+            }
+        }
+
+        let mut prelude = Vec::new();
+
+        if exports {
+            prelude.push(Expression::call(Expression::member("Object", "defineProperty"), vec![
+                "exports".into(),
+                Expression::Literal(LiteralString("\"__esModule\"".into())).into(),
+                Expression::Object(vec![ObjectMember::Literal {
+                    key: "value".into(),
+                    value: Expression::Literal(LiteralTrue).into(),
+                }.into()]).into(),
+            ]).into());
+        }
+
+        prelude.append(&mut getters);
+        prelude.append(&mut lowered);
+
+        *body = prelude;
+
+        Ok(())
+    }
+
+    /// Creates a `var` declaration of the module required for the import
+    /// `specifiers`, and records the properties of the module the imported
+    /// names refer to.
+    fn import_require(&mut self, specifiers: &[ImportSpecifier], source: OwnedSlice, tree: &ScopeTree) -> Statement {
+        if specifiers.is_empty() {
+            return self.require(source, None).into();
+        }
+
+        let namespace = specifiers.iter().filter_map(|specifier| match *specifier {
+            ImportSpecifier::Namespace { local } => Some(local),
+            _                                    => None,
+        }).next();
+
+        let default = specifiers.iter().any(|specifier| match *specifier {
+            ImportSpecifier::Default { .. }         => true,
+            ImportSpecifier::Named { imported, .. } => imported.as_str() == "default",
+            ImportSpecifier::Namespace { .. }       => false,
+        });
+
+        let (module, value) = match namespace {
+            Some(local) => (local, self.require(source, Some(INTEROP_WILDCARD))),
+            None        => (self.module_name(source), self.require(source, if default { Some(INTEROP_DEFAULT) } else { None })),
+        };
+
+        for specifier in specifiers {
+            let (imported, local) = match *specifier {
+                ImportSpecifier::Default { local }         => ("default".into(), local),
+                ImportSpecifier::Named { imported, local } => (imported, local),
+                ImportSpecifier::Namespace { .. }          => continue,
+            };
+
+            let binding = match tree.binding(0, &local) {
+                Some(binding) => &tree.bindings[binding],
+                None          => continue,
+            };
+
+            for &index in &binding.references {
+                self.imports.insert(address(&tree.references[index].name), (module, imported));
+            }
+        }
+
+        var(module, value)
+    }
+
+    /// Creates a `require` call for the module `source`, optionally wrapped
+    /// in one of the interop helpers.
+    #[inline]
+    fn require(&mut self, source: OwnedSlice, interop: Option<&'static str>) -> Expression {
+        let require = Expression::call("require", vec![Expression::Literal(LiteralString(source)).into()]);
+
+        match interop {
+            Some(helper) => Expression::call(self.helper(helper), vec![require.into()]),
+            None         => require,
+        }
+    }
+
+    /// Returns a reference to one of the helper functions, which is going
+    /// to be declared at the top of the program.
+    #[inline]
+    fn helper(&mut self, name: &'static str) -> Expression {
+        if !self.helpers.contains(&name) {
+            self.helpers.push(name);
+        }
+
+        Expression::Identifier(name.into())
+    }
+
+    /// Lowers a class to a constructor function, with methods and accessors
+    /// defined on its prototype, followed by assignments of static properties.
+    /// Instance properties are expected to be moved to the constructor.
+    fn lower_class(
+        &mut self,
+        name: OwnedSlice,
+        extends: Option<OwnedSlice>,
+        members: Vec<Loc<ClassMember>>,
+        statics: Vec<Loc<Statement>>
+    ) -> Statement {
+        let mut lowered = Vec::with_capacity(members.len() + statics.len() + 1);
+
+        if let Some(parent) = extends {
+            let inherits = self.helper(INHERITS);
+
+            lowered.push(Expression::call(inherits, vec![name.into(), parent.into()]).into());
+        }
+
+        let mut constructor = None;
+        let mut accessors = Vec::new();
+
+        let target = |is_static| if is_static {
+            Expression::Identifier(name)
+        } else {
+            Expression::member(name, "prototype")
+        };
+
+        for member in members {
+            match member.item {
+                ClassMember::Constructor {
+                    params,
+                    body,
+                } => constructor = Some(Statement::Function {
+                    name: name,
+                    params: params,
+                    body: body,
+                }),
+
+                ClassMember::Method {
+                    is_static,
+                    name: method,
+                    params,
+                    body,
+                } => {
+                    // Naming the function would shadow a variable
+                    // of the same name used in the method
+                    let function = Expression::Function {
+                        name: if references(&body, method) { None } else { Some(method) },
+                        params: params,
+                        body: body,
+                    };
+
+                    let key = self.strings.alloc(format!("\"{}\"", method.as_str()));
+
+                    lowered.push(Loc::new(member.span, define_method(
+                        target(is_static),
+                        Expression::Literal(LiteralString(key)),
+                        function
+                    ).into()));
+                },
+
+                ClassMember::Getter {
+                    is_static,
+                    name,
+                    body,
+                } => add_accessor(&mut accessors, is_static, name, "get", Vec::new(), body),
+
+                ClassMember::Setter {
+                    is_static,
+                    name,
+                    params,
+                    body,
+                } => add_accessor(&mut accessors, is_static, name, "set", params, body),
+
+                ClassMember::Property { .. } => unreachable!(),
+            }
+        }
+
+        for (is_static, property, descriptor) in accessors {
+            let key = self.strings.alloc(format!("\"{}\"", property.as_str()));
+
+            lowered.push(Expression::call(Expression::member("Object", "defineProperty"), vec![
+                target(is_static).into(),
+                Expression::Literal(LiteralString(key)).into(),
+                accessor_descriptor(descriptor, false).into(),
+            ]).into());
+        }
+
+        let constructor = constructor.expect("Class has a constructor");
+
+        lowered.extend(statics);
+
+        if lowered.is_empty() {
+            constructor
+        } else {
+            lowered.insert(0, constructor.into());
+
+            Statement::Transparent {
+                body: lowered
+            }
+        }
+    }
+
+    /// Reports `super` used where it can't be lowered, since there's no
+    /// parent class it would refer to: in a class without `extends`, or
+    /// outside of classes.
+    fn check_super(&mut self, expression: &Loc<Expression>) {
+        // transformation flag check
+        let lowered = self.settings.transform_class || self.static_this.is_some();
+
+        if expression.item != Expression::Super || self.superclass.is_some() || !lowered || self.error.is_some() {
+            return;
+        }
+
+        self.error = Some(TransformError::SuperWithoutParent {
+            start: expression.span.start,
+            end: expression.span.end,
+        });
+    }
+
+    /// Returns what `this` is in the code being transformed, which is the
+    /// class in static property initializers moved out of the class.
+    #[inline]
+    fn this(&self) -> Expression {
+        match self.static_this {
+            Some(class) => Expression::Identifier(class),
+            None        => Expression::This,
+        }
+    }
+
+    /// Returns the object properties of `super` are read from in the class
+    /// member being lowered: the prototype of the parent class, or the
+    /// parent class itself in static methods.
+    #[inline]
+    fn super_object(&self) -> Option<Expression> {
+        self.superclass.map(|(parent, is_static)| {
+            if is_static {
+                Expression::Identifier(parent)
+            } else {
+                Expression::member(parent, "prototype")
+            }
+        })
+    }
+
+    /// Generates a unique variable name for the module required from
+    /// the `source`, based on the name of the file.
+    #[inline]
+    fn module_name(&mut self, source: OwnedSlice) -> OwnedSlice {
+        let path = source.as_str().trim_matches(|ch| ch == '"' || ch == '\'');
+        let file = path.rsplit('/').next().unwrap_or(path);
+        let file = file.split('.').next().unwrap_or(file);
+
+        let mut prefix = String::with_capacity(file.len() + 1);
+
+        prefix.push('_');
+        prefix.extend(file.chars().filter(|&ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '$'));
+
+        if prefix.len() == 1 {
+            prefix.push_str("module");
+        }
+
+        self.unique_name(&prefix)
+    }
+
+    /// Defines the `exported` name on `exports` as a getter of the `value`.
+    #[inline]
+    fn export_getter(&mut self, exported: OwnedSlice, value: Expression) -> Loc<Statement> {
+        let key = self.strings.alloc(format!("\"{}\"", exported.as_str()));
+
+        define_getter(Expression::Literal(LiteralString(key)), value).into()
+    }
+
+    /// Defines the `members` on the `object` with `Object.defineProperty`,
+    /// one at a time in order. A getter directly followed by a setter of
+    /// the same name, or the other way around, share a descriptor.
+    fn define_members(&mut self, object: Expression, members: Vec<Loc<ObjectMember>>) -> Expression {
+        // Keys with their descriptors, and whether they describe an accessor
+        let mut properties: Vec<(Expression, Vec<Loc<ObjectMember>>, bool)> = Vec::new();
+        // Name and kind of the last accessor, while it can take its counterpart
+        let mut last: Option<(OwnedSlice, &'static str)> = None;
+
+        for member in members {
+            let (name, kind, params, body) = match member.item {
+                ObjectMember::Literal {
+                    key,
+                    value,
+                } => {
+                    last = None;
+                    properties.push((self.property_key(key), data_descriptor(value), false));
+                    continue;
+                },
+
+                ObjectMember::Computed {
+                    key,
+                    value,
+                } => {
+                    last = None;
+                    properties.push((key.item, data_descriptor(value), false));
+                    continue;
+                },
+
+                ObjectMember::Getter {
+                    name,
+                    body,
+                } => (name, "get", Vec::new(), body),
+
+                ObjectMember::Setter {
+                    name,
+                    params,
+                    body,
+                } => (name, "set", params, body),
+
+                _ => unreachable!(),
+            };
+
+            let function = ObjectMember::Literal {
+                key: kind.into(),
+                value: Expression::Function {
+                    name: None,
+                    params: params,
+                    body: body,
+                }.into(),
+            }.into();
+
+            match last {
+                Some((previous, other)) if previous == name && other != kind => {
+                    last = None;
+                    properties.last_mut().expect("Accessor was defined").1.push(function);
+                },
+                _ => {
+                    last = Some((name, kind));
+                    properties.push((self.property_key(name), vec![function], true));
+                },
+            }
+        }
+
+        properties.into_iter().fold(object, |object, (key, descriptor, accessor)| {
+            let descriptor = if accessor {
+                accessor_descriptor(descriptor, true)
+            } else {
+                Expression::Object(descriptor)
+            };
+
+            Expression::call(Expression::member("Object", "defineProperty"), vec![
+                object.into(),
+                key.into(),
+                descriptor.into(),
+            ])
+        })
+    }
+
+    /// Creates a string literal of an object literal `key`, which is either
+    /// an identifier or already a string literal.
+    #[inline]
+    fn property_key(&mut self, key: OwnedSlice) -> Expression {
+        let key = match key.as_str().as_bytes()[0] {
+            b'"' | b'\'' => key,
+            _            => self.strings.alloc(format!("\"{}\"", key.as_str())),
+        };
+
+        Expression::Literal(LiteralString(key))
+    }
+
+    /// Creates a string literal with the value of a template string part,
+    /// or `void 0` if the part has an invalid escape sequence, which is
+    /// only allowed in tagged templates.
+    #[inline]
+    fn cooked_string(&mut self, quasi: &OwnedSlice) -> Expression {
+        match template_value(quasi.as_str(), false) {
+            Some(value) => {
+                let literal = self.strings.alloc(quote_utf16(&value));
+
+                Expression::Literal(LiteralString(literal))
+            },
+            None        => void_zero(),
+        }
+    }
+
+    /// Creates a string literal with the raw source of a template string part.
+    #[inline]
+    fn raw_string(&mut self, quasi: &OwnedSlice) -> Expression {
+        let value = template_value(quasi.as_str(), true).expect("Raw strings have no escapes");
+        let literal = self.strings.alloc(quote_utf16(&value));
+
+        Expression::Literal(LiteralString(literal))
+    }
+}
+
+/// Returns the value of a part of a template string as UTF-16 code units,
+/// either with the escape sequences decoded (cooked), or as `raw` source.
+/// Line terminators are normalized to `\n` in both. Invalid escapes leave
+/// the cooked value undefined.
+fn template_value(quasi: &str, raw: bool) -> Option<Vec<u16>> {
+    let mut value = Vec::with_capacity(quasi.len());
+    let mut chars = quasi.chars().peekable();
+    let mut buffer = [0u16; 2];
+
+    while let Some(ch) = chars.next() {
+        let ch = match ch {
+            '\r'         => {
+                chars.next_if_eq(&'\n');
+                '\n'
+            },
+            '\\' if !raw => match chars.next()? {
+                'n'  => '\n',
+                't'  => '\t',
+                'r'  => '\r',
+                'b'  => '\u{8}',
+                'f'  => '\u{c}',
+                'v'  => '\u{b}',
+                '0' if !chars.peek().is_some_and(char::is_ascii_digit) => '\0',
+                '0' ..= '9' => return None,
+                'x'  => {
+                    value.push(hex_digits(&mut chars, 2)? as u16);
+                    continue;
+                },
+                'u'  => {
+                    let code = if chars.next_if_eq(&'{').is_some() {
+                        let mut code = 0u32;
+                        let mut digits = 0;
+
+                        while let Some(digit) = chars.next_if(|&ch| ch != '}') {
+                            code = code * 16 + digit.to_digit(16)?;
+                            digits += 1;
+
+                            if code > 0x10FFFF {
+                                return None;
+                            }
+                        }
+
+                        chars.next_if_eq(&'}')?;
+
+                        if digits == 0 {
+                            return None;
+                        }
+
+                        code
+                    } else {
+                        hex_digits(&mut chars, 4)?
+                    };
+
+                    // Surrogates can't be a `char`, and are kept as they are
+                    match char::from_u32(code) {
+                        Some(ch) => value.extend_from_slice(ch.encode_utf16(&mut buffer)),
+                        None     => value.push(code as u16),
+                    }
+                    continue;
+                },
+                // Line continuation doesn't add anything to the value
+                '\r'         => {
+                    chars.next_if_eq(&'\n');
+                    continue;
+                },
+                '\n'         |
+                '\u{2028}'   |
+                '\u{2029}'   => continue,
+                ch           => ch,
+            },
+            ch           => ch,
+        };
+
+        value.extend_from_slice(ch.encode_utf16(&mut buffer));
+    }
+
+    Some(value)
+}
+
+/// Reads a code point of exactly `count` hexadecimal digits.
+#[inline]
+fn hex_digits<I: Iterator<Item = char>>(chars: &mut I, count: usize) -> Option<u32> {
+    let mut code = 0;
+
+    for _ in 0 .. count {
+        code = code * 16 + chars.next()?.to_digit(16)?;
+    }
+
+    Some(code)
+}
+
+/// Writes a string of UTF-16 code units as the source of a double quoted
+/// string literal valid in ES5. Characters that can't appear in the literal
+/// as they are, including unpaired surrogates, are escaped.
+fn quote_utf16(value: &[u16]) -> String {
+    let mut string = String::with_capacity(value.len() + 2);
+
+    string.push('"');
+
+    for unit in char::decode_utf16(value.iter().cloned()) {
+        match unit {
+            Ok('"')        => string.push_str("\\\""),
+            Ok('\\')       => string.push_str("\\\\"),
+            Ok('\n')       => string.push_str("\\n"),
+            Ok('\r')       => string.push_str("\\r"),
+            Ok('\t')       => string.push_str("\\t"),
+            Ok('\u{2028}') => string.push_str("\\u2028"),
+            Ok('\u{2029}') => string.push_str("\\u2029"),
+            Ok(ch) if ch < ' ' || ch == '\u{7f}' => string.push_str(&format!("\\x{:02x}", ch as u32)),
+            Ok(ch)         => string.push(ch),
+            Err(error)     => string.push_str(&format!("\\u{:04x}", error.unpaired_surrogate())),
+        }
+    }
+
+    string.push('"');
+
+    string
+}
+
+trait Take {
+    fn take(&mut self) -> Self;
+}
+
+impl<T> Take for Vec<T> {
+    #[inline]
+    fn take(&mut self) -> Self {
+        mem::take(self)
+    }
+}
+
+impl Take for Expression {
+    #[inline]
+    fn take(&mut self) -> Self {
+        mem::replace(self, Expression::This)
+    }
+}
+
+impl<T: Take> Take for Loc<T> {
+    #[inline]
+    fn take(&mut self) -> Self {
+        Loc::new(self.span, self.item.take())
+    }
+}
+
+/// Adds a `var` declaration to the list of `statements`, merging it with
+/// the preceding declaration if there is one.
+#[inline]
+fn declare(statements: &mut Vec<Statement>, name: Pattern, value: Expression) {
+    let declarator = VariableDeclarator {
+        name: name,
+        value: Some(value.into()),
+    };
+
+    if let Some(&mut Statement::VariableDeclaration {
+        ref mut declarators,
+        ..
+    }) = statements.last_mut() {
+        declarators.push(declarator);
+        return;
+    }
+
+    statements.push(Statement::VariableDeclaration {
+        kind: VariableDeclarationKind::Var,
+        declarators: vec![declarator],
+    });
+}
+
+/// Declares the `temporaries` of a function at the top of its `body`.
+fn declare_temporaries(body: &mut Vec<Loc<Statement>>, temporaries: Vec<OwnedSlice>) {
+    if temporaries.is_empty() {
+        return;
+    }
+
+    body.insert(0, Statement::VariableDeclaration {
+        kind: VariableDeclarationKind::Var,
+        declarators: temporaries.into_iter().map(|name| VariableDeclarator {
+            name: Pattern::Identifier(name),
+            value: None,
+        }).collect(),
+    }.into());
+}
+
+#[inline]
+fn is_spread(expression: &Loc<Expression>) -> bool {
+    matches!(expression.item, Expression::Prefix {
+        operator: Spread,
+        ..
+    })
+}
+
+/// Converts call `arguments` containing spread elements to a single
+/// array that can be passed to `apply`.
+fn spread_arguments(mut arguments: Vec<Loc<Expression>>) -> Expression {
+    // A lone spread argument can be passed to `apply` as is
+    if arguments.len() == 1 {
+        match arguments.pop().map(|argument| argument.item) {
+            Some(Expression::Prefix {
+                operand,
+                ..
+            }) => operand.item,
+            _  => unreachable!(),
+        }
+    } else {
+        concat_spread(arguments)
+    }
+}
+
+/// Creates a `new` expression for the `constructor` binding the spread
+/// `arguments` up front, as `apply` can't be used with `new`.
+fn construct_call(constructor: Loc<Expression>, arguments: Expression) -> Expression {
+    let bind = Expression::member(Expression::member(Expression::member("Function", "prototype"), "bind"), "apply");
+    let arguments = Expression::call(
+        Expression::member(Expression::Array(vec![Expression::Literal(LiteralNull).into()]), "concat"),
+        vec![arguments.into()]
+    );
+
+    Expression::call(
+        Expression::Prefix {
+            operator: New,
+            operand: Box::new(Expression::call(bind, vec![constructor, arguments.into()]).into()),
+        },
+        Vec::new()
+    )
+}
+
+/// Transforms the `callee` of a call or a tagged template. Imported
+/// functions are read from the module object, which mustn't become
+/// the `this` of the call: `f()` -> `(0, _module.f)()`.
+fn transform_callee(callee: &mut Loc<Expression>, ctx: &mut Transformer) {
+    let imported = match callee.item {
+        Expression::Identifier(name) => ctx.imports.contains_key(&address(&name)),
+        _                            => false,
+    };
+
+    callee.transform(ctx);
+
+    if imported {
+        let function = callee.take();
+
+        callee.item = Expression::Sequence(vec![Expression::Literal(LiteralInteger(0)).into(), function]);
+    }
+}
+
+/// Checks if a `callee` is `super` or a method of `super`.
+#[inline]
+fn is_super_callee(callee: &Expression) -> bool {
+    match *callee {
+        Expression::Super => true,
+
+        Expression::Member {
+            ref object,
+            ..
+        } |
+        Expression::ComputedMember {
+            ref object,
+            ..
+        } => object.item == Expression::Super,
+
+        _ => false,
+    }
+}
+
+/// Concatenates the `items` of an array literal containing spread
+/// elements, grouping the regular elements into arrays in between.
+fn concat_spread(items: Vec<Loc<Expression>>) -> Expression {
+    let mut parts = Vec::new();
+    let mut group = Vec::new();
+
+    for item in items {
+        match item.item {
+            Expression::Prefix {
+                operator: Spread,
+                operand,
+            } => {
+                if !group.is_empty() {
+                    parts.push(Expression::Array(group.take()).into());
+                }
+
+                parts.push(*operand);
+            },
+            _ => group.push(item),
+        }
+    }
+
+    if !group.is_empty() {
+        parts.push(Expression::Array(group).into());
+    }
+
+    Expression::call(Expression::member(Expression::Array(Vec::new()), "concat"), parts)
+}
+
+const INTEROP_DEFAULT: &str = "_interopRequireDefault";
+const INTEROP_WILDCARD: &str = "_interopRequireWildcard";
+const INHERITS: &str = "_inherits";
+const GET: &str = "_get";
+
+/// Collects the names bound by an exported declaration.
+#[inline]
+fn declaration_names(statement: &Statement, names: &mut Vec<OwnedSlice>) {
+    match *statement {
+        Statement::VariableDeclaration {
+            ref declarators,
+            ..
+        } => {
+            for declarator in declarators {
+                declarator.name.names(names);
+            }
+        },
+
+        Statement::Function {
+            name,
+            ..
+        } |
+        Statement::Class {
+            name,
+            ..
+        } => names.push(name),
+
+        _ => {},
+    }
+}
+
+#[inline]
+fn var(name: OwnedSlice, value: Expression) -> Statement {
+    Statement::VariableDeclaration {
+        kind: VariableDeclarationKind::Var,
+        declarators: vec![VariableDeclarator {
+            name: Pattern::Identifier(name),
+            value: Some(value.into()),
+        }],
+    }
+}
+
+/// Creates an `Object.defineProperty` call adding an enumerable getter
+/// of the `value` as the `key` property of `exports`.
+fn define_getter(key: Expression, value: Expression) -> Expression {
+    let getter = Expression::Function {
+        name: None,
+        params: Vec::new(),
+        body: vec![Statement::Return {
+            value: Some(value.into()),
+        }.into()],
+    };
+
+    Expression::call(Expression::member("Object", "defineProperty"), vec![
+        "exports".into(),
+        key.into(),
+        Expression::Object(vec![
+            ObjectMember::Literal {
+                key: "enumerable".into(),
+                value: Expression::Literal(LiteralTrue).into(),
+            }.into(),
+            ObjectMember::Literal {
+                key: "get".into(),
+                value: getter.into(),
+            }.into(),
+        ]).into(),
+    ])
+}
+
+/// Re-exports all names of the `module`, except for the default export.
+fn export_all(module: OwnedSlice) -> Statement {
+    let key = || Expression::from("key");
+    let skip = Expression::binary(
+        Expression::binary(key(), StrictEquality, Expression::Literal(LiteralString("\"default\"".into()))),
+        LogicalOr,
+        Expression::binary(key(), StrictEquality, Expression::Literal(LiteralString("\"__esModule\"".into())))
+    );
+    let value = Expression::ComputedMember {
+        object: Box::new(module.into()),
+        property: Box::new(key().into()),
+    };
+
+    let callback = Expression::Function {
+        name: None,
+        params: vec!["key".into()],
+        body: vec![
+            Statement::If {
+                test: skip.into(),
+                consequent: Box::new(Statement::Return { value: None }.into()),
+                alternate: None,
+            }.into(),
+            define_getter(key(), value).into(),
+        ],
+    };
+
+    let keys = Expression::call(Expression::member("Object", "keys"), vec![module.into()]);
+
+    Expression::call(Expression::member(keys, "forEach"), vec![callback.into()]).into()
+}
+
+/// Creates the declaration of one of the helper functions: the interop
+/// helpers used to import modules that aren't ES modules compiled to
+/// CommonJS, or `_inherits` and `_get` used by derived classes.
+fn helper_function(name: &'static str) -> Statement {
+    if name == INHERITS {
+        return inherits_function();
+    }
+
+    if name == GET {
+        return get_function();
+    }
+
+    let is_es_module = Expression::binary("obj", LogicalAnd, Expression::member("obj", "__esModule"));
+
+    let body = if name == INTEROP_DEFAULT {
+        // return obj && obj.__esModule ? obj : { default: obj };
+        vec![Statement::Return {
+            value: Some(Expression::Conditional {
+                test: Box::new(is_es_module.into()),
+                consequent: Box::new("obj".into()),
+                alternate: Box::new(Expression::Object(vec![ObjectMember::Literal {
+                    key: "default".into(),
+                    value: "obj".into(),
+                }.into()]).into()),
+            }.into()),
+        }.into()]
+    } else {
+        // Copies properties of the module to a namespace object
+        vec![
+            Statement::If {
+                test: is_es_module.into(),
+                consequent: Box::new(Statement::Return {
+                    value: Some("obj".into()),
+                }.into()),
+                alternate: None,
+            }.into(),
+            var("ns".into(), Expression::Object(Vec::new())).into(),
+            Statement::ForIn {
+                left: Box::new(Statement::VariableDeclaration {
+                    kind: VariableDeclarationKind::Var,
+                    declarators: vec![VariableDeclarator {
+                        name: "key".into(),
+                        value: None,
+                    }],
+                }.into()),
+                right: "obj".into(),
+                body: Box::new(Expression::binary(
+                    Expression::ComputedMember {
+                        object: Box::new("ns".into()),
+                        property: Box::new("key".into()),
+                    },
+                    Assign,
+                    Expression::ComputedMember {
+                        object: Box::new("obj".into()),
+                        property: Box::new("key".into()),
+                    }
+                ).into()),
+            }.into(),
+            Expression::binary(Expression::member("ns", "default"), Assign, "obj").into(),
+            Statement::Return {
+                value: Some("ns".into()),
+            }.into(),
+        ]
+    };
+
+    Statement::Function {
+        name: name.into(),
+        params: vec!["obj".into()],
+        body: body,
+    }
+}
+
+/// Creates the `_inherits` helper, which chains the prototype of a class
+/// to its parent, and the class itself to the parent for static methods.
+fn inherits_function() -> Statement {
+    // { constructor: { value: subClass, writable: true, configurable: true } }
+    let properties = Expression::Object(vec![ObjectMember::Literal {
+        key: "constructor".into(),
+        value: Expression::Object(vec![
+            ObjectMember::Literal {
+                key: "value".into(),
+                value: "subClass".into(),
+            }.into(),
+            ObjectMember::Literal {
+                key: "writable".into(),
+                value: Expression::Literal(LiteralTrue).into(),
+            }.into(),
+            ObjectMember::Literal {
+                key: "configurable".into(),
+                value: Expression::Literal(LiteralTrue).into(),
+            }.into(),
+        ]).into(),
+    }.into()]);
+
+    let prototype = Expression::call(Expression::member("Object", "create"), vec![
+        Expression::member("superClass", "prototype").into(),
+        properties.into(),
+    ]);
+
+    let set_prototype = || Expression::member("Object", "setPrototypeOf");
+
+    Statement::Function {
+        name: INHERITS.into(),
+        params: vec!["subClass".into(), "superClass".into()],
+        body: vec![
+            Expression::binary(Expression::member("subClass", "prototype"), Assign, prototype).into(),
+            Statement::If {
+                test: set_prototype().into(),
+                consequent: Box::new(Expression::call(set_prototype(), vec![
+                    "subClass".into(),
+                    "superClass".into(),
+                ]).into()),
+                alternate: Some(Box::new(Expression::binary(
+                    Expression::member("subClass", "__proto__"),
+                    Assign,
+                    "superClass"
+                ).into())),
+            }.into(),
+        ],
+    }
+}
+
+/// Creates the `_get` helper, which reads a property of `super` the way
+/// ES2015 does, calling getters of the parent class with the `receiver`.
+fn get_function() -> Statement {
+    let call = |callee: Expression, arguments: Vec<&'static str>| {
+        Expression::call(callee, arguments.into_iter().map(|argument| argument.into()).collect())
+    };
+
+    // var descriptor = Object.getOwnPropertyDescriptor(object, property);
+    let descriptor = var("descriptor".into(), call(
+        Expression::member("Object", "getOwnPropertyDescriptor"),
+        vec!["object", "property"]
+    ));
+
+    // Missing properties are looked up further in the prototype chain
+    let lookup = Statement::If {
+        test: Expression::binary("descriptor", StrictEquality, void_zero()).into(),
+        consequent: Box::new(Statement::Block {
+            body: vec![
+                var("parent".into(), call(Expression::member("Object", "getPrototypeOf"), vec!["object"])).into(),
+                Statement::Return {
+                    value: Some(Expression::Conditional {
+                        test: Box::new(Expression::binary("parent", StrictEquality, Expression::Literal(LiteralNull)).into()),
+                        consequent: Box::new(void_zero().into()),
+                        alternate: Box::new(call(GET.into(), vec!["parent", "property", "receiver"]).into()),
+                    }.into()),
+                }.into(),
+            ],
+        }.into()),
+        alternate: None,
+    };
+
+    // return descriptor.get ? descriptor.get.call(receiver) : descriptor.value;
+    let value = Statement::Return {
+        value: Some(Expression::Conditional {
+            test: Box::new(Expression::member("descriptor", "get").into()),
+            consequent: Box::new(call(
+                Expression::member(Expression::member("descriptor", "get"), "call"),
+                vec!["receiver"]
+            ).into()),
+            alternate: Box::new(Expression::member("descriptor", "value").into()),
+        }.into()),
+    };
+
+    Statement::Function {
+        name: GET.into(),
+        params: vec!["object".into(), "property".into(), "receiver".into()],
+        body: vec![descriptor.into(), lookup.into(), value.into()],
+    }
+}
+
+/// Converts a target produced by `flatten_pattern` to an expression.
+#[inline]
+fn pattern_target(pattern: Pattern) -> Loc<Expression> {
+    match pattern {
+        Pattern::Identifier(name)       => Expression::Identifier(name).into(),
+        Pattern::Expression(expression) => *expression,
+        _                               => panic!("Pattern wasn't flattened!"),
+    }
+}
+
+/// Reads the `key` of an `object`, using a computed member for quoted keys.
+#[inline]
+fn property_access(object: Expression, key: OwnedSlice) -> Expression {
+    match key.as_bytes().first() {
+        Some(&b'"') | Some(&b'\'') => Expression::ComputedMember {
+            object: Box::new(object.into()),
+            property: Box::new(Expression::Literal(LiteralString(key)).into()),
+        },
+        _ => Expression::member(object, key),
+    }
+}
+
+#[inline]
+fn void_zero() -> Expression {
+    Expression::Prefix {
+        operator: Void,
+        operand: Box::new(Expression::Literal(LiteralInteger(0)).into()),
+    }
+}
+
+#[inline]
+fn bind_this(function: Expression) -> Expression {
+    Expression::call(Expression::member(function, "bind"), vec![Expression::This.into()])
+}
+
+impl Settings {
+    pub fn target_es5() -> Settings {
+        let mut settings = Settings::target_es2015();
+
+        settings.transform_block_scope = true;
+        settings.transform_arrow = true;
+        settings.transform_object = true;
+        settings.transform_class = true;
+        settings.transform_template_strings = true;
+        settings.transform_destructuring = true;
+        settings.transform_parameters = true;
+        settings.transform_spread = true;
+
+        settings
+    }
+
+    pub fn target_es2015() -> Settings {
+        let mut settings = Settings::no_transform();
+
+        settings.transform_exponentation = true;
+        settings.transform_class_properties = true;
+        settings.transform_catch_binding = true;
+
+        settings
+    }
+
+    pub fn no_transform() -> Settings {
+        Settings {
+            transform_block_scope: false,
+            transform_arrow: false,
+            transform_object: false,
+            transform_exponentation: false,
+            transform_class_properties: false,
+            transform_catch_binding: false,
+            transform_class: false,
+            transform_template_strings: false,
+            transform_destructuring: false,
+            transform_parameters: false,
+            transform_spread: false,
+            transform_commonjs: false,
+            transform_chunk_imports: false,
+            mangle: false,
+        }
+    }
+}
+
+/// The `Transformable` trait provides an interface for instances of grammar
+/// to alter the AST.
+trait Transformable {
+    #[inline]
+    fn transform(&mut self, _: &mut Transformer) {}
+
+    #[inline]
+    fn contains_this(&self) -> bool {
+        false
+    }
+}
+
+impl<T: Transformable> Transformable for Option<T> {
+    #[inline]
+    fn transform(&mut self, ctx: &mut Transformer) {
+        if let Some(ref mut value) = *self {
+            value.transform(ctx);
+        }
+    }
+
+    #[inline]
+    fn contains_this(&self) -> bool {
+        match *self {
+            Some(ref value) => value.contains_this(),
+            _               => false,
+        }
+    }
+}
+
+impl<T: Transformable> Transformable for Loc<T> {
+    #[inline]
+    fn transform(&mut self, ctx: &mut Transformer) {
+        self.item.transform(ctx)
+    }
+
+    #[inline]
+    fn contains_this(&self) -> bool {
+        self.item.contains_this()
+    }
+}
+
+impl<T: Transformable> Transformable for Box<T> {
+    #[inline]
+    fn transform(&mut self, ctx: &mut Transformer) {
+        self.as_mut().transform(ctx)
+    }
+
+    #[inline]
+    fn contains_this(&self) -> bool {
+        self.as_ref().contains_this()
+    }
+}
+
+impl Transformable for Parameter {
+    #[inline]
+    fn transform(&mut self, ctx: &mut Transformer) {
+        self.name.transform(ctx);
+        self.default.transform(ctx);
+    }
+
+    #[inline]
+    fn contains_this(&self) -> bool {
+        self.name.contains_this() || self.default.contains_this()
+    }
+}
+
+impl Transformable for PropertyPattern {
+    #[inline]
+    fn transform(&mut self, ctx: &mut Transformer) {
+        self.value.transform(ctx);
+    }
+
+    #[inline]
+    fn contains_this(&self) -> bool {
+        self.value.contains_this()
+    }
+}
+
+impl Transformable for Pattern {
+    fn transform(&mut self, ctx: &mut Transformer) {
+        match *self {
+            Pattern::Identifier(ref mut name) => *name = ctx.renamed(*name),
+
+            Pattern::Object(ref mut properties) => properties.transform(ctx),
+
+            Pattern::Array(ref mut elements) => elements.transform(ctx),
+
+            Pattern::Rest(ref mut pattern) => pattern.transform(ctx),
+
+            Pattern::Default {
+                ref mut pattern,
+                ref mut value,
+            } => {
+                pattern.transform(ctx);
+                value.transform(ctx);
+            },
+
+            Pattern::Expression(ref mut expression) => expression.transform(ctx),
+        }
+    }
+
+    fn contains_this(&self) -> bool {
+        match *self {
+            Pattern::Identifier(_) => false,
+
+            Pattern::Object(ref properties) => properties.contains_this(),
+
+            Pattern::Array(ref elements) => elements.contains_this(),
+
+            Pattern::Rest(ref pattern) => pattern.contains_this(),
+
+            Pattern::Default {
+                ref pattern,
+                ref value,
+            } => pattern.contains_this() || value.contains_this(),
+
+            Pattern::Expression(ref expression) => expression.contains_this(),
+        }
+    }
+}
+
+impl Transformable for Expression {
+    fn transform(&mut self, ctx: &mut Transformer) {
+        *self = match *self {
+            Expression::ArrowFunction {
+                ref mut params,
+                ref mut body,
+            } => {
+                let temporaries = ctx.own_temporaries(|ctx| {
+                    params.transform(ctx);
+                    body.transform(ctx);
+                });
+                let lowers_parameters = ctx.lowers_parameters(params);
+
+                if lowers_parameters || !temporaries.is_empty() {
+                    // Parameters are lowered to statements in the body, along
+                    // with the declaration of the temporary variables
+                    let block = match body.item {
+                        Statement::Expression {
+                            ref mut value,
+                        } => Some(Statement::Block {
+                            body: vec![Loc::new(value.span, Statement::Return {
+                                value: Some(value.take())
+                            })]
+                        }),
+                        _ => None,
+                    };
+
+                    if let Some(block) = block {
+                        body.item = block;
+                    }
+
+                    if let Statement::Block { ref mut body } = body.item {
+                        if lowers_parameters {
+                            ctx.lower_parameters(params, body);
+                        }
+
+                        declare_temporaries(body, temporaries);
+                    }
+                }
+
+                // transformation flag check
+                if !ctx.settings.transform_arrow {
+                    return;
+                }
+
+                let body = match body.item {
+                    Statement::Block { ref mut body }   => body.split_off(0),
+                    Statement::Expression { ref mut value } => vec![
+                        Loc::new(value.span, Statement::Return {
+                            value: Some(value.take())
+                        })
+                    ],
+                    ref statement => {
+                        panic!("Invalid arrow function body {:#?}", statement);
+                    }
+                };
+
+                let bind = body.contains_this();
+
+                let function = Expression::Function {
+                    name: None,
+                    params: params.split_off(0),
+                    body: body,
+                };
+
+                if bind {
+                    bind_this(function)
+                } else {
+                    function
+                }
+            },
+
+            Expression::Array(ref mut items) => {
+                items.transform(ctx);
+
+                // transformation flag check
+                if !ctx.settings.transform_spread || !items.iter().any(is_spread) {
+                    return;
+                }
+
+                concat_spread(items.take())
+            },
+
+            Expression::Sequence(ref mut items) => {
+                items.transform(ctx);
+                return;
+            },
+
+            Expression::Identifier(ref mut name) => {
+                match ctx.imported(*name) {
+                    Some(value) => value,
+                    None        => {
+                        *name = ctx.renamed(*name);
+                        return;
+                    },
+                }
+            },
+
+            Expression::Pattern(ref mut pattern) => {
+                pattern.transform(ctx);
+                return;
+            },
+
+            Expression::Member {
+                ref mut object,
+                property,
+            } => {
+                let reads_super = object.item == Expression::Super && ctx.superclass.is_some();
+
+                ctx.check_super(object);
+                object.transform(ctx);
+
+                if !reads_super {
+                    return;
+                }
+
+                // Getters of the parent class are called with `this`
+                let key = ctx.strings.alloc(format!("\"{}\"", property.as_str()));
+                let get = ctx.helper(GET);
+
+                Expression::call(get, vec![
+                    object.take(),
+                    Expression::Literal(LiteralString(key)).into(),
+                    ctx.this().into(),
+                ])
+            },
+
+            Expression::ComputedMember {
+                ref mut object,
+                ref mut property,
+            } => {
+                let reads_super = object.item == Expression::Super && ctx.superclass.is_some();
+
+                ctx.check_super(object);
+                object.transform(ctx);
+                property.transform(ctx);
+
+                if !reads_super {
+                    return;
+                }
+
+                let get = ctx.helper(GET);
+
+                Expression::call(get, vec![object.take(), property.take(), ctx.this().into()])
+            },
+
+            Expression::Prefix {
+                ref mut operand,
+                ..
+            } |
+            Expression::Postfix {
+                ref mut operand,
+                ..
+            } => {
+                operand.transform(ctx);
+                return;
+            },
+
+            Expression::Conditional {
+                ref mut test,
+                ref mut consequent,
+                ref mut alternate,
+            } => {
+                test.transform(ctx);
+                consequent.transform(ctx);
+                alternate.transform(ctx);
+                return;
+            },
+
+            Expression::Function {
+                ref mut name,
+                ref mut params,
+                ref mut body,
+            } => {
+                if let Some(ref mut name) = *name {
+                    *name = ctx.renamed(*name);
+                }
+
+                ctx.own_this(body, |ctx, body| {
+                    params.transform(ctx);
+                    body.transform(ctx);
+                    ctx.lower_parameters(params, body);
+                });
+                return;
+            },
+
+            Expression::Object(ref mut members) => {
+                members.transform(ctx);
+
+                // transformation flag check
+                if !ctx.settings.transform_object {
+                    return;
+                }
+
+                // Accessors are defined one by one in source order, so that
+                // later members still override earlier ones of the same name
+                let has_accessors = members.iter().any(|member| is_accessor_member(&member.item));
+
+                if has_accessors {
+                    let first = members.iter().position(|member| {
+                        !matches!(member.item, ObjectMember::Literal { .. })
+                    }).expect("Object has an accessor");
+
+                    let rest = members.split_off(first);
+                    let literal = Expression::Object(members.take());
+
+                    ctx.define_members(literal, rest)
+                } else {
+                    let mut computed = partition_vec(members, |member| {
+                        !matches!(member.item, ObjectMember::Computed { .. })
+                    });
+
+                    if computed.is_empty() {
+                        return;
+                    }
+
+                    let literal = Expression::Object(members.take());
+                    let mut body: Vec<Loc<Statement>> = Vec::with_capacity(computed.len() + 2);
+
+                    body.push(Statement::VariableDeclaration {
+                        kind: VariableDeclarationKind::Var,
+                        declarators: vec![
+                            VariableDeclarator {
+                                name: "___".into(),
+                                value: Some(literal.into()),
+                            }
+                        ]
+                    }.into());
+
+                    for member in computed.drain(..) {
+                        if let ObjectMember::Computed { key, value } = member.item {
+                            body.push(
+                                Expression::binary(
+                                    Expression::ComputedMember {
+                                        object: Box::new("___".into()),
+                                        property: Box::new(key),
+                                    },
+                                    Assign,
+                                    value
+                                ).into()
+                            );
+                        }
+                    }
+
+                    body.push(Statement::Return {
+                        value: Some("___".into())
+                    }.into());
+
+                    Expression::call(Expression::Function {
+                        name: None,
+                        params: Vec::new(),
+                        body: body,
+                    }, Vec::new())
+                }
+            },
+
+            Expression::Call {
+                ref mut callee,
+                ref mut arguments,
+            } => {
+                let super_call = ctx.superclass.is_some() && is_super_callee(&callee.item);
+
+                ctx.check_super(callee);
+
+                if super_call {
+                    // Methods are read from the parent class directly
+                    match callee.item {
+                        Expression::Member {
+                            ref mut object,
+                            ..
+                        } => object.transform(ctx),
+
+                        Expression::ComputedMember {
+                            ref mut object,
+                            ref mut property,
+                        } => {
+                            object.transform(ctx);
+                            property.transform(ctx);
+                        },
+
+                        // super(a) -> Parent.call(this, a)
+                        _ => if let Some((parent, _)) = ctx.superclass {
+                            callee.item = Expression::Identifier(parent);
+                        },
+                    }
+                } else {
+                    transform_callee(callee, ctx);
+                }
+
+                arguments.transform(ctx);
+
+                // transformation flag check
+                let spread = ctx.settings.transform_spread && arguments.iter().any(is_spread);
+
+                if super_call && spread {
+                    // super.method(...a) -> Parent.prototype.method.apply(this, a)
+                    let arguments = spread_arguments(arguments.take());
+
+                    Expression::call(
+                        Expression::member(callee.take(), "apply"),
+                        vec![ctx.this().into(), arguments.into()]
+                    )
+                } else if super_call {
+                    // super.method(a) -> Parent.prototype.method.call(this, a)
+                    arguments.insert(0, ctx.this().into());
+
+                    Expression::call(Expression::member(callee.take(), "call"), arguments.take())
+                } else if spread {
+                    let arguments = spread_arguments(arguments.take());
+
+                    match callee.item {
+                        // new F(...a) -> new (Function.prototype.bind.apply(F, [null].concat(a)))()
+                        Expression::Prefix {
+                            operator: New,
+                            ref mut operand,
+                        } => construct_call(operand.take(), arguments),
+
+                        _ => ctx.apply_call(callee.take(), arguments),
+                    }
+                } else {
+                    return;
+                }
+            },
+
+            // Static property initializers are moved out of the class
+            Expression::This => {
+                match ctx.static_this {
+                    Some(class) => Expression::Identifier(class),
+                    None        => return,
+                }
+            },
+
+            Expression::Super => {
+                match ctx.super_object() {
+                    Some(object) => object,
+                    None         => return,
+                }
+            },
+
+            Expression::Import {
+                ref mut source,
+            } => {
+                source.transform(ctx);
+
+                // transformation flag check
+                if !ctx.settings.transform_commonjs {
+                    return;
+                }
+
+                let interop = ctx.helper(INTEROP_WILDCARD);
+
+                if ctx.settings.transform_chunk_imports {
+                    // require.import(source).then(_interopRequireWildcard)
+                    let import = Expression::call(Expression::member("require", "import"), vec![source.take()]);
+
+                    Expression::call(Expression::member(import, "then"), vec![interop.into()])
+                } else {
+                    // Promise.resolve().then(function () { return _interopRequireWildcard(require(source)); })
+                    let require = Expression::call("require", vec![source.take()]);
+                    let resolved = Expression::call(Expression::member("Promise", "resolve"), Vec::new());
+
+                    Expression::call(Expression::member(resolved, "then"), vec![Expression::Function {
+                        name: None,
+                        params: Vec::new(),
+                        body: vec![Statement::Return {
+                            value: Some(Expression::call(interop, vec![require.into()]).into()),
+                        }.into()],
+                    }.into()])
+                }
+            },
+
+            Expression::TemplateLiteral {
+                ref mut quasis,
+                ref mut expressions,
+            } => {
+                expressions.transform(ctx);
+
+                // transformation flag check
+                if !ctx.settings.transform_template_strings {
+                    return;
+                }
+
+                let mut quasis = quasis.iter();
+
+                // Template always starts with a string, even if empty, which
+                // makes sure the concatenation produces a string.
+                let mut result = match quasis.next() {
+                    Some(quasi) => ctx.cooked_string(quasi),
+                    None        => return,
+                };
+
+                for (expression, quasi) in expressions.drain(..).zip(quasis) {
+                    result = Expression::binary(result, Addition, expression);
+
+                    if !quasi.as_str().is_empty() {
+                        let string = ctx.cooked_string(quasi);
+
+                        result = Expression::binary(result, Addition, string);
+                    }
+                }
+
+                result
+            },
+
+            Expression::TaggedTemplate {
+                ref mut tag,
+                ref mut quasis,
+                ref mut expressions,
+            } => {
+                transform_callee(tag, ctx);
+                expressions.transform(ctx);
+
+                // transformation flag check
+                if !ctx.settings.transform_template_strings {
+                    return;
+                }
+
+                let cooked = quasis.iter().map(|quasi| ctx.cooked_string(quasi).into()).collect();
+                let raw = quasis.iter().map(|quasi| ctx.raw_string(quasi).into()).collect();
+
+                // Tag functions expect to receive the same strings array each
+                // time the template is evaluated, so it's created just once.
+                let name = ctx.unique_name("_templateObject");
+
+                ctx.hoist(name, Some(Expression::call(
+                    Expression::member("Object", "freeze"),
+                    vec![Expression::call(
+                        Expression::member("Object", "defineProperty"),
+                        vec![
+                            Expression::Array(cooked).into(),
+                            Expression::Literal(LiteralString("\"raw\"".into())).into(),
+                            Expression::Object(vec![
+                                ObjectMember::Literal {
+                                    key: "value".into(),
+                                    value: Expression::call(
+                                        Expression::member("Object", "freeze"),
+                                        vec![Expression::Array(raw).into()]
+                                    ).into(),
+                                }.into()
+                            ]).into(),
+                        ]
+                    ).into()]
+                )));
+
+                let mut arguments = Vec::with_capacity(expressions.len() + 1);
+
+                arguments.push(Expression::Identifier(name).into());
+                arguments.append(expressions);
+
+                Expression::Call {
+                    callee: Box::new(tag.take()),
+                    arguments: arguments,
+                }
+            },
+
+            Expression::Binary {
+                ref mut left,
+                ref mut operator,
+                ref mut right,
+            } => {
+                // Assigning to a property of `super` sets it on `this`
+                if operator.assignment() && ctx.superclass.is_some() {
+                    match left.item {
+                        Expression::Member {
+                            ref mut object,
+                            ..
+                        } |
+                        Expression::ComputedMember {
+                            ref mut object,
+                            ..
+                        } if object.item == Expression::Super => object.item = Expression::This,
+
+                        _ => {},
+                    }
+                }
+
+                left.transform(ctx);
+                right.transform(ctx);
+
+                if let Expression::Pattern(ref mut pattern) = left.item {
+                    // transformation flag check
+                    if !ctx.settings.transform_destructuring {
+                        return;
+                    }
+
+                    let pattern = mem::replace(pattern, Pattern::Array(Vec::new()));
+
+                    ctx.destructuring_assignment(pattern, right.take().item)
+                } else {
+                    if !ctx.settings.transform_exponentation {
+                        return;
+                    }
+
+                    match *operator {
+                        Exponent => Expression::call(
+                            Expression::member("Math", "pow"),
+                            vec![left.take(), right.take()]
+                        ),
+
+                        ExponentAssign => {
+                            *operator = Assign;
+                            **right = Expression::call(
+                                Expression::member("Math", "pow"),
+                                vec![left.take(), right.take()]
+                            ).into();
+                            return;
+                        },
+
+                        _ => return,
+                    }
+                }
+            }
+
+            _ => return,
+        }
+    }
+
+    fn contains_this(&self) -> bool {
+        match *self {
+            Expression::This => true,
+
+            Expression::Array(ref items) => items.contains_this(),
+
+            Expression::Object(ref members) => members.contains_this(),
+
+            Expression::Sequence(ref items) => items.contains_this(),
+
+            Expression::Pattern(ref pattern) => pattern.contains_this(),
+
+            Expression::Member {
+                ref object,
+                ..
+            } => object.contains_this(),
+
+            Expression::ComputedMember {
+                ref object,
+                ref property,
+            } => object.contains_this() || property.contains_this(),
+
+            Expression::Prefix {
+                ref operand,
+                ..
+            } |
+            Expression::Postfix {
+                ref operand,
+                ..
+            } => operand.contains_this(),
+
+            Expression::Conditional {
+                ref test,
+                ref consequent,
+                ref alternate,
+            } => test.contains_this() || consequent.contains_this() || alternate.contains_this(),
+
+            Expression::Call {
+                ref callee,
+                ref arguments,
+            } => callee.contains_this() || arguments.contains_this(),
+
+            Expression::Import {
+                ref source,
+            } => source.contains_this(),
+
+            Expression::TemplateLiteral {
+                ref expressions,
+                ..
+            } => expressions.contains_this(),
+
+            Expression::TaggedTemplate {
+                ref tag,
+                ref expressions,
+                ..
+            } => tag.contains_this() || expressions.contains_this(),
+
+            Expression::Binary {
+                ref left,
+                ref right,
+                ..
+            } => left.contains_this() || right.contains_this(),
+
+            _ => false,
+        }
+    }
+}
+
+impl Transformable for ObjectMember {
+    fn transform(&mut self, ctx: &mut Transformer) {
+        *self = match *self {
+
+            ObjectMember::Shorthand {
+                key,
+            } => {
+                let value = match ctx.imported(key) {
+                    Some(value) => value,
+                    None        => {
+                        let value = ctx.renamed(key);
+
+                        // transformation flag check
+                        if !ctx.settings.transform_object && value == key {
+                            return;
+                        }
+
+                        Expression::Identifier(value)
+                    },
+                };
+
+                ObjectMember::Literal {
+                    key: key,
+                    value: value.into(),
+                }
+            },
+
+            ObjectMember::Literal {
+                ref mut value,
+                ..
+            } => {
+                value.transform(ctx);
+                return;
+            },
+
+            ObjectMember::Computed {
+                ref mut key,
+                ref mut value,
+            } => {
+                key.transform(ctx);
+                value.transform(ctx);
+                return;
+            },
+
+            ObjectMember::Method {
+                ref name,
+                ref mut params,
+                ref mut body,
+            } => {
+                ctx.own_this(body, |ctx, body| {
+                    body.transform(ctx);
+                    params.transform(ctx);
+                    ctx.lower_parameters(params, body);
+                });
+
+                // transformation flag check
+                if !ctx.settings.transform_object {
+                    return;
+                }
+
+                // Naming the function would shadow a variable
+                // of the same name used in the method
+                ObjectMember::Literal {
+                    key: *name,
+                    value: Expression::Function {
+                        name: if references(body, *name) { None } else { Some(*name) },
+                        params: params.take(),
+                        body: body.take(),
+                    }.into()
+                }
+            },
+
+            ObjectMember::ComputedMethod {
+                ref mut name,
+                ref mut params,
+                ref mut body,
+            } => {
+                name.transform(ctx);
+                ctx.own_this(body, |ctx, body| {
+                    body.transform(ctx);
+                    params.transform(ctx);
+                    ctx.lower_parameters(params, body);
+                });
+
+                // transformation flag check
+                if !ctx.settings.transform_object {
+                    return;
+                }
+
+                ObjectMember::Computed {
+                    key: name.take(),
+                    value: Expression::Function {
+                        name: None,
+                        params: params.take(),
+                        body: body.take(),
+                    }.into()
+                }
+            },
+
+            // Accessors are lowered by the object expression
+            ObjectMember::Setter {
+                ref mut params,
+                ref mut body,
+                ..
+            } => {
+                ctx.own_this(body, |ctx, body| {
+                    body.transform(ctx);
+                    params.transform(ctx);
+                    ctx.lower_parameters(params, body);
+                });
+                return;
+            },
+
+            ObjectMember::Getter {
+                ref mut body,
+                ..
+            } => {
+                ctx.own_this(body, |ctx, body| body.transform(ctx));
+                return;
+            },
+        }
+    }
+
+    fn contains_this(&self) -> bool {
+        match *self {
+            ObjectMember::Literal {
+                ref value,
+                ..
+            } => value.contains_this(),
+
+            ObjectMember::Computed {
+                ref key,
+                ref value,
+            } => key.contains_this() || value.contains_this(),
+
+            _ => false,
+        }
+    }
+}
+
+impl Transformable for ClassMember {
+    fn transform(&mut self, ctx: &mut Transformer) {
+        let outer = ctx.static_this.take();
+
+        match *self {
+            Constructor {
+                ref mut params,
+                ref mut body,
+            } |
+            Method {
+                ref mut params,
+                ref mut body,
+                ..
+            } |
+            Setter {
+                ref mut params,
+                ref mut body,
+                ..
+            } => {
+                ctx.own_this(body, |ctx, body| {
+                    params.transform(ctx);
+                    body.transform(ctx);
+                    ctx.lower_parameters(params, body);
+                });
+            },
+
+            Getter {
+                ref mut body,
+                ..
+            } => {
+                ctx.own_this(body, |ctx, body| body.transform(ctx));
+            },
+
+            Property {
+                ref mut value,
+                ..
+            } => {
+                value.transform(ctx);
+            }
+        }
+
+        ctx.static_this = outer;
+    }
+}
+
+impl Transformable for VariableDeclarator {
+    #[inline]
+    fn transform(&mut self, ctx: &mut Transformer) {
+        self.name.transform(ctx);
+        self.value.transform(ctx);
+    }
+
+    #[inline]
+    fn contains_this(&self) -> bool {
+        self.value.contains_this()
+    }
+}
+
+impl Transformable for SwitchCase {
+    #[inline]
+    fn transform(&mut self, ctx: &mut Transformer) {
+        self.test.transform(ctx);
+        self.consequent.transform(ctx);
+    }
+
+    #[inline]
+    fn contains_this(&self) -> bool {
+        self.test.contains_this() || self.consequent.contains_this()
+    }
+}
+
+impl Transformable for CatchClause {
+    fn transform(&mut self, ctx: &mut Transformer) {
+        self.param.transform(ctx);
+        self.body.transform(ctx);
+
+        // transformation flag check
+        if self.param.is_none() && ctx.settings.transform_catch_binding {
+            self.param = Some(Pattern::Identifier(ctx.unique_name("_e")));
+            return;
+        }
+
+        // transformation flag check
+        if !ctx.settings.transform_destructuring {
+            return;
+        }
+
+        let param = match self.param {
+            Some(ref mut param) if !param.is_identifier() => param,
+            _ => return,
+        };
+
+        let name = ctx.unique_name("_ref");
+        let mut declarators = vec![VariableDeclarator {
+            name: mem::replace(param, Pattern::Identifier(name)),
+            value: Some(Expression::Identifier(name).into()),
+        }];
+
+        ctx.lower_declarators(&mut declarators);
+
+        self.body.insert(0, Statement::VariableDeclaration {
+            kind: VariableDeclarationKind::Var,
+            declarators: declarators,
+        }.into());
+    }
+
+    #[inline]
+    fn contains_this(&self) -> bool {
+        self.param.contains_this() || self.body.contains_this()
+    }
+}
+
+/// Removes static properties from the class `members`, returning their
+/// assignments to properties of the class.
+fn static_props(members: &mut Vec<Loc<ClassMember>>, class: OwnedSlice) -> Vec<Loc<Statement>> {
+    let statics = partition_vec(members, |member| match member.item {
+        ClassMember::Property { is_static, .. } => !is_static,
+        _                                       => true,
+    });
+
+    // Partitioned items come out in reverse order
+    statics.into_iter().rev().filter_map(|member| match member.item {
+        ClassMember::Property {
+            name,
+            value,
+            ..
+        } => Some(Loc::new(member.span, Expression::binary(
+            Expression::member(class, name),
+            Assign,
+            value,
+        ).into())),
+        _ => None,
+    }).collect()
+}
+
+/// Moves class properties to assignments in the constructor, which is
+/// created if the class doesn't have one. In derived classes properties
+/// are assigned right after the `super(...)` call.
+fn add_props_to_constructor(members: &mut Vec<Loc<ClassMember>>, extends: Option<OwnedSlice>) {
+    let mut props = Vec::new();
+
+    let moved = partition_vec(members, |member| {
+        !matches!(member.item, ClassMember::Property { .. })
+    });
+
+    // Partitioned items come out in reverse order
+    for member in moved.into_iter().rev() {
+        if let ClassMember::Property {
+            name,
+            value,
+            ..
+        } = member.item {
+            props.push(Expression::binary(
+                Expression::member(Expression::This, name),
+                Assign,
+                value,
+            ).into());
+        }
+    }
+
+    let has_constructor = members.iter().any(|member| {
+        matches!(member.item, ClassMember::Constructor { .. })
+    });
+
+    if !has_constructor {
+        // constructor() { super(...arguments); }
+        let body = match extends {
+            Some(_) => vec![Expression::call(Expression::Super, vec![
+                Expression::Prefix {
+                    operator: Spread,
+                    operand: Box::new("arguments".into()),
+                }.into()
+            ]).into()],
+            None    => Vec::new(),
+        };
+
+        members.insert(0, ClassMember::Constructor {
+            params: Vec::new(),
+            body: body,
+        }.into());
+    }
+
+    for member in members.iter_mut() {
+        if let ClassMember::Constructor {
+            ref mut body,
+            ..
+        } = member.item {
+            let position = body.iter()
+                               .position(is_super_call)
+                               .map_or(0, |index| index + 1);
+
+            body.splice(position .. position, props.drain(..));
+        }
+    }
+}
+
+/// Checks if a `statement` calls the constructor of the parent class.
+#[inline]
+fn is_super_call(statement: &Loc<Statement>) -> bool {
+    match statement.item {
+        Statement::Expression {
+            value: Loc {
+                item: Expression::Call {
+                    ref callee,
+                    ..
+                },
+                ..
+            },
+        } => callee.item == Expression::Super,
+        _ => false,
+    }
+}
+
+/// Creates an `Object.defineProperty` call adding a non-enumerable
+/// method to the `target`, the way class methods are defined.
+fn define_method(target: Expression, key: Expression, function: Expression) -> Expression {
+    Expression::call(Expression::member("Object", "defineProperty"), vec![
+        target.into(),
+        key.into(),
+        Expression::Object(vec![
+            ObjectMember::Literal {
+                key: "value".into(),
+                value: function.into(),
+            }.into(),
+            ObjectMember::Literal {
+                key: "writable".into(),
+                value: Expression::Literal(LiteralTrue).into(),
+            }.into(),
+            ObjectMember::Literal {
+                key: "configurable".into(),
+                value: Expression::Literal(LiteralTrue).into(),
+            }.into(),
+        ]).into(),
+    ])
+}
+
+/// Checks if an object literal member is a getter or a setter.
+#[inline]
+fn is_accessor_member(member: &ObjectMember) -> bool {
+    matches!(*member,
+        ObjectMember::Getter { .. } |
+        ObjectMember::Setter { .. })
+}
+
+/// Creates the members of a descriptor of an enumerable, writable and
+/// configurable property holding the `value`, as in an object literal.
+fn data_descriptor(value: Loc<Expression>) -> Vec<Loc<ObjectMember>> {
+    vec![
+        ObjectMember::Literal {
+            key: "value".into(),
+            value: value,
+        }.into(),
+        ObjectMember::Literal {
+            key: "writable".into(),
+            value: Expression::Literal(LiteralTrue).into(),
+        }.into(),
+        ObjectMember::Literal {
+            key: "enumerable".into(),
+            value: Expression::Literal(LiteralTrue).into(),
+        }.into(),
+        ObjectMember::Literal {
+            key: "configurable".into(),
+            value: Expression::Literal(LiteralTrue).into(),
+        }.into(),
+    ]
+}
+
+/// Property descriptors built from getters and setters, along with the
+/// name of the property and whether it's a static class member.
+type Accessors = Vec<(bool, OwnedSlice, Vec<Loc<ObjectMember>>)>;
+
+/// Adds a getter or setter function as the `kind` (`get` or `set`) of
+/// the descriptor for `name`, which is created on first use.
+fn add_accessor(
+    descriptors: &mut Accessors,
+    is_static: bool,
+    name: OwnedSlice,
+    kind: &'static str,
+    params: Vec<Parameter>,
+    body: Vec<Loc<Statement>>
+) {
+    let function = ObjectMember::Literal {
+        key: kind.into(),
+        value: Expression::Function {
+            name: None,
+            params: params,
+            body: body,
+        }.into(),
+    }.into();
+
+    match descriptors.iter_mut().find(|descriptor| descriptor.0 == is_static && descriptor.1 == name) {
+        Some(descriptor) => descriptor.2.push(function),
+        None             => descriptors.push((is_static, name, vec![function])),
+    }
+}
+
+/// Completes a descriptor of an accessor property. Accessors of classes
+/// aren't `enumerable`, unlike accessors of object literals.
+fn accessor_descriptor(mut members: Vec<Loc<ObjectMember>>, enumerable: bool) -> Expression {
+    if enumerable {
+        members.push(ObjectMember::Literal {
+            key: "enumerable".into(),
+            value: Expression::Literal(LiteralTrue).into(),
+        }.into());
+    }
+
+    members.push(ObjectMember::Literal {
+        key: "configurable".into(),
+        value: Expression::Literal(LiteralTrue).into(),
+    }.into());
+
+    Expression::Object(members)
+}
+
+/// Checks if the `body` of a function reads the variable `name`.
+fn references(body: &[Loc<Statement>], name: OwnedSlice) -> bool {
+    struct References {
+        name: OwnedSlice,
+        found: bool,
+    }
+
+    impl Visitor for References {
+        #[inline]
+        fn reference(&mut self, name: OwnedSlice) {
+            self.found |= name == self.name;
+        }
+    }
+
+    let mut references = References {
+        name: name,
+        found: false,
+    };
+
+    for statement in body {
+        statement.visit(&mut references);
+    }
+
+    references.found
+}
+
+/// Finds the identifiers in a loop `body` reading the `arguments` of the
+/// function the loop is in. Nested functions and methods are left out, as
+/// they have their own `arguments`, while arrow functions are not.
+fn arguments_references(body: &Statement) -> Vec<usize> {
+    #[derive(Default)]
+    struct Arguments {
+        found: Vec<usize>,
+        nested: HashSet<usize>,
+    }
+
+    impl Arguments {
+        #[inline]
+        fn skip<T: Visitable>(&mut self, params: &[Parameter], body: &T) {
+            let mut inner = Arguments::default();
+
+            params.iter().for_each(|param| param.visit(&mut inner));
+            body.visit(&mut inner);
+            self.nested.extend(inner.found);
+        }
+    }
+
+    impl Visitor for Arguments {
+        fn statement(&mut self, statement: &Statement) {
+            match *statement {
+                Statement::Function {
+                    ref params,
+                    ref body,
+                    ..
+                } => self.skip(params, body),
+
+                Statement::Class {
+                    ref body,
+                    ..
+                } => self.skip(&[], body),
+
+                _ => {},
+            }
+        }
+
+        fn expression(&mut self, expression: &Expression) {
+            match *expression {
+                Expression::Function {
+                    ref params,
+                    ref body,
+                    ..
+                } => self.skip(params, body),
+
+                Expression::Object(ref members) => {
+                    for member in members {
+                        match member.item {
+                            ObjectMember::Method { ref params, ref body, .. } |
+                            ObjectMember::Setter { ref params, ref body, .. } |
+                            ObjectMember::ComputedMethod { ref params, ref body, .. } => self.skip(params, body),
+                            ObjectMember::Getter { ref body, .. }                     => self.skip(&[], body),
+                            _                                                         => {},
+                        }
+                    }
+                },
+
+                _ => {},
+            }
+        }
+
+        #[inline]
+        fn reference(&mut self, name: OwnedSlice) {
+            if name.as_str() == "arguments" {
+                self.found.push(address(&name));
+            }
+        }
+    }
+
+    let mut arguments = Arguments::default();
+
+    body.visit(&mut arguments);
+
+    let nested = arguments.nested;
+
+    arguments.found.into_iter().filter(|address| !nested.contains(address)).collect()
+}
+
+/// Identifies an occurrence of a name in the source. Copies of the name
+/// made while transforming share its address.
+#[inline]
+fn address(name: &OwnedSlice) -> usize {
+    name.as_ptr() as usize
+}
+
+/// Collects the names declared by `let` and `const` in a loop body,
+/// except for the ones in nested loops and functions.
+fn block_declarations(statement: &Statement, names: &mut Vec<OwnedSlice>) {
+    match *statement {
+        Statement::VariableDeclaration {
+            kind,
+            ref declarators,
+        } if kind != VariableDeclarationKind::Var => {
+            for declarator in declarators {
+                declarator.name.names(names);
+            }
+        },
+
+        Statement::Block {
+            ref body,
+        } |
+        Statement::Transparent {
+            ref body,
+        } => {
+            for statement in body {
+                block_declarations(statement, names);
+            }
+        },
+
+        Statement::Labeled {
+            ref body,
+            ..
+        } => block_declarations(body, names),
+
+        Statement::If {
+            ref consequent,
+            ref alternate,
+            ..
+        } => {
+            block_declarations(consequent, names);
+
+            if let Some(ref alternate) = *alternate {
+                block_declarations(alternate, names);
+            }
+        },
+
+        Statement::Switch {
+            ref cases,
+            ..
+        } => {
+            for statement in cases.iter().flat_map(|case| &case.consequent) {
+                block_declarations(statement, names);
+            }
+        },
+
+        Statement::Try {
+            ref block,
+            ref handler,
+            ref finalizer,
+        } => {
+            let handler = handler.iter().flat_map(|handler| &handler.body);
+            let finalizer = finalizer.iter().flatten();
+
+            for statement in block.iter().chain(handler).chain(finalizer) {
+                block_declarations(statement, names);
+            }
+        },
+
+        _ => {},
+    }
+}
+
+/// Turns the `var` declarations of a loop body into assignments and
+/// collects the declared names, so that the variables stay in the scope
+/// of the function once the body is moved into a closure. Returns true
+/// if the `statement` was a declaration without any values, leaving an
+/// empty block behind.
+fn hoist_vars(statement: &mut Statement, names: &mut Vec<OwnedSlice>) -> bool {
+    match *statement {
+        Statement::VariableDeclaration {
+            kind: VariableDeclarationKind::Var,
+            ref mut declarators,
+        } => {
+            let assignments = var_assignments(declarators.take(), names);
+            let empty = assignments.is_none();
+
+            *statement = match assignments {
+                Some(value) => Statement::Expression {
+                    value: value,
+                },
+                None => Statement::Block {
+                    body: Vec::new(),
+                },
+            };
+
+            return empty;
+        },
+
+        Statement::Block {
+            ref mut body,
+        } |
+        Statement::Transparent {
+            ref mut body,
+        } => body.retain_mut(|statement| !hoist_vars(statement, names)),
+
+        Statement::Labeled {
+            ref mut body,
+            ..
+        } |
+        Statement::While {
+            ref mut body,
+            ..
+        } |
+        Statement::DoWhile {
+            ref mut body,
+            ..
+        } => {
+            hoist_vars(body, names);
+        },
+
+        Statement::If {
+            ref mut consequent,
+            ref mut alternate,
+            ..
+        } => {
+            hoist_vars(consequent, names);
+
+            if let Some(ref mut alternate) = *alternate {
+                hoist_vars(alternate, names);
+            }
+        },
+
+        Statement::Switch {
+            ref mut cases,
+            ..
+        } => {
+            for case in cases {
+                case.consequent.retain_mut(|statement| !hoist_vars(statement, names));
+            }
+        },
+
+        Statement::Try {
+            ref mut block,
+            ref mut handler,
+            ref mut finalizer,
+        } => {
+            block.retain_mut(|statement| !hoist_vars(statement, names));
+
+            if let Some(ref mut handler) = *handler {
+                handler.body.retain_mut(|statement| !hoist_vars(statement, names));
+            }
+
+            if let Some(ref mut finalizer) = *finalizer {
+                finalizer.retain_mut(|statement| !hoist_vars(statement, names));
+            }
+        },
+
+        Statement::For {
+            ref mut init,
+            ref mut body,
+            ..
+        } => {
+            let assignments = match *init {
+                Some(ref mut init) => match init.item {
+                    Statement::VariableDeclaration {
+                        kind: VariableDeclarationKind::Var,
+                        ref mut declarators,
+                    } => Some(var_assignments(declarators.take(), names)),
+                    _ => None,
+                },
+                None => None,
+            };
+
+            if let Some(assignments) = assignments {
+                *init = assignments.map(|value| Box::new(value.into()));
+            }
+
+            hoist_vars(body, names);
+        },
+
+        Statement::ForIn {
+            ref mut left,
+            ref mut body,
+            ..
+        } |
+        Statement::ForOf {
+            ref mut left,
+            ref mut body,
+            ..
+        } => {
+            let declarator = match left.item {
+                Statement::VariableDeclaration {
+                    kind: VariableDeclarationKind::Var,
+                    ref mut declarators,
+                } => declarators.pop(),
+                _ => None,
+            };
+
+            if let Some(declarator) = declarator {
+                declarator.name.names(names);
+                left.item = Statement::Expression {
+                    value: assignment_target(declarator.name).into(),
+                };
+            }
+
+            hoist_vars(body, names);
+        },
+
+        _ => {},
+    }
+
+    false
+}
+
+/// Converts `var` declarators to a single expression assigning their
+/// values, `None` if none of them has a value.
+fn var_assignments(declarators: Vec<VariableDeclarator>, names: &mut Vec<OwnedSlice>) -> Option<Loc<Expression>> {
+    let mut assignments: Vec<Loc<Expression>> = declarators.into_iter().filter_map(|declarator| {
+        declarator.name.names(names);
+
+        let value = declarator.value?;
+
+        Some(Expression::binary(assignment_target(declarator.name), Assign, value).into())
+    }).collect();
+
+    match assignments.len() {
+        0 | 1 => assignments.pop(),
+        _     => Some(Expression::Sequence(assignments).into()),
+    }
+}
+
+#[inline]
+fn assignment_target(pattern: Pattern) -> Expression {
+    match pattern {
+        Pattern::Identifier(name) => Expression::Identifier(name),
+        pattern                   => Expression::Pattern(pattern),
+    }
+}
+
+/// Jumps out of a loop body moved into a closure. They are turned into
+/// returns of codes, which are checked after calling the closure.
+struct Jumps {
+    // Statements copying the head bindings back to the loop, run before
+    // the next iteration
+    copies: Vec<Loc<Statement>>,
+
+    // Labels defined inside of the body
+    labels: Vec<OwnedSlice>,
+
+    // Codes returned from the closure, with the jumps they stand for
+    taken: Vec<(Expression, Statement)>,
+
+    // Body returns from the function containing the loop, returned value
+    // is wrapped in an object
+    returns: bool,
+}
+
+impl Jumps {
+    fn lower(&mut self, statement: &mut Loc<Statement>, ctx: &mut Transformer, loops: usize, breakables: usize) {
+        statement.item = match statement.item {
+            Statement::Continue {
+                label: None,
+            } if loops == 0 => {
+                let mut body = self.copies.clone();
+
+                body.push(Statement::Return { value: None }.into());
+
+                match body.len() {
+                    1 => Statement::Return { value: None },
+                    _ => Statement::Block { body: body },
+                }
+            },
+
+            Statement::Break {
+                label: None,
+            } if breakables == 0 => self.code(ctx, "break".to_string(), Statement::Break { label: None }),
+
+            Statement::Break {
+                label: Some(label),
+            } if !self.labels.contains(&label) => {
+                self.code(ctx, format!("break|{}", label), Statement::Break { label: Some(label) })
+            },
+
+            Statement::Continue {
+                label: Some(label),
+            } if !self.labels.contains(&label) => {
+                self.code(ctx, format!("continue|{}", label), Statement::Continue { label: Some(label) })
+            },
+
+            Statement::Return {
+                ref mut value,
+            } => {
+                let value = match value.take() {
+                    Some(value) => value,
+                    None        => void_zero().into(),
+                };
+
+                self.returns = true;
+
+                Statement::Return {
+                    value: Some(Expression::Object(vec![
+                        ObjectMember::Literal {
+                            key: "v".into(),
+                            value: value,
+                        }.into()
+                    ]).into()),
+                }
+            },
+
+            Statement::Block {
+                ref mut body,
+            } |
+            Statement::Transparent {
+                ref mut body,
+            } => {
+                for statement in body {
+                    self.lower(statement, ctx, loops, breakables);
+                }
+                return;
+            },
+
+            Statement::Labeled {
+                label,
+                ref mut body,
+            } => {
+                self.labels.push(label);
+                self.lower(body, ctx, loops, breakables);
+                self.labels.pop();
+                return;
+            },
+
+            Statement::If {
+                ref mut consequent,
+                ref mut alternate,
+                ..
+            } => {
+                self.lower(consequent, ctx, loops, breakables);
 
-impl Transformable for ObjectMember {
-    fn transform(&mut self, settings: &Settings) {
-        *self = match *self {
+                if let Some(ref mut alternate) = *alternate {
+                    self.lower(alternate, ctx, loops, breakables);
+                }
+                return;
+            },
 
-            ObjectMember::Shorthand {
-                ref mut key,
+            Statement::Try {
+                ref mut block,
+                ref mut handler,
+                ref mut finalizer,
             } => {
-                // transformation flag check
-                if !settings.transform_object {
-                    return;
+                let handler = handler.iter_mut().flat_map(|handler| &mut handler.body);
+                let finalizer = finalizer.iter_mut().flatten();
+
+                for statement in block.iter_mut().chain(handler).chain(finalizer) {
+                    self.lower(statement, ctx, loops, breakables);
                 }
+                return;
+            },
 
-                ObjectMember::Literal {
-                    key: *key,
-                    value: Expression::Identifier(*key),
+            Statement::Switch {
+                ref mut cases,
+                ..
+            } => {
+                for statement in cases.iter_mut().flat_map(|case| &mut case.consequent) {
+                    self.lower(statement, ctx, loops, breakables + 1);
                 }
+                return;
             },
 
-            ObjectMember::Literal {
-                ref mut value,
+            Statement::While {
+                ref mut body,
+                ..
+            } |
+            Statement::DoWhile {
+                ref mut body,
+                ..
+            } |
+            Statement::For {
+                ref mut body,
+                ..
+            } |
+            Statement::ForIn {
+                ref mut body,
+                ..
+            } |
+            Statement::ForOf {
+                ref mut body,
                 ..
             } => {
-                value.transform(settings);
+                self.lower(body, ctx, loops + 1, breakables + 1);
                 return;
             },
 
-            ObjectMember::Computed {
-                ref mut key,
-                ref mut value,
+            _ => return,
+        }
+    }
+
+    /// Returns the `code` of a `jump` from the closure.
+    fn code(&mut self, ctx: &mut Transformer, code: String, jump: Statement) -> Statement {
+        let code = Expression::Literal(LiteralString(ctx.strings.alloc(format!("\"{}\"", code))));
+
+        if !self.taken.iter().any(|taken| taken.1 == jump) {
+            self.taken.push((code.clone(), jump));
+        }
+
+        Statement::Return {
+            value: Some(code.into()),
+        }
+    }
+}
+
+impl Transformable for Statement {
+    fn transform(&mut self, ctx: &mut Transformer) {
+        *self = match *self {
+            Statement::Block {
+                ref mut body,
             } => {
-                key.transform(settings);
-                value.transform(settings);
+                body.transform(ctx);
                 return;
             },
 
-            ObjectMember::Method {
-                ref name,
-                ref mut params,
+            Statement::Labeled {
+                label,
                 ref mut body,
             } => {
-                body.transform(settings);
-                params.transform(settings);
+                body.transform(ctx);
 
-                // transformation flag check
-                if !settings.transform_object {
-                    return;
+                // Loop lowered with a closure is preceded by its declarations
+                let mut statements = match body.item {
+                    Statement::Transparent {
+                        ref mut body,
+                    } => body.take(),
+                    _ => return,
+                };
+
+                if let Some(last) = statements.pop() {
+                    statements.push(Loc::new(last.span, Statement::Labeled {
+                        label: label,
+                        body: Box::new(last),
+                    }));
                 }
 
-                ObjectMember::Literal {
-                    key: *name,
-                    value: Expression::Function {
-                        name: Some(*name),
-                        params: params.take(),
-                        body: body.take(),
-                    }
+                Statement::Transparent {
+                    body: statements,
                 }
             },
 
-            ObjectMember::ComputedMethod {
-                ref mut name,
-                ref mut params,
-                ref mut body,
+            Statement::VariableDeclaration {
+                ref mut kind,
+                ref mut declarators,
             } => {
-                body.transform(settings);
-                params.transform(settings);
+                declarators.transform(ctx);
+
+                if ctx.settings.transform_destructuring {
+                    ctx.lower_declarators(declarators);
+                }
 
                 // transformation flag check
-                if !settings.transform_object {
+                if !ctx.settings.transform_block_scope {
                     return;
                 }
 
-                ObjectMember::Computed {
-                    key: name.take(),
-                    value: Expression::Function {
-                        name: None,
-                        params: params.take(),
-                        body: body.take(),
+                // Each iteration of a loop gets a new binding
+                if *kind == VariableDeclarationKind::Let && ctx.loops > 0 {
+                    for declarator in declarators.iter_mut().filter(|declarator| declarator.value.is_none()) {
+                        declarator.value = Some(void_zero().into());
                     }
                 }
-            },
-        }
-    }
 
-    fn contains_this(&self) -> bool {
-        match *self {
-            ObjectMember::Literal {
-                ref value,
-                ..
-            } => value.contains_this(),
+                *kind = VariableDeclarationKind::Var;
+                return;
+            },
 
-            ObjectMember::Computed {
-                ref key,
-                ref value,
-            } => key.contains_this() || value.contains_this(),
+            Statement::Expression {
+                ref mut value,
+            } |
+            Statement::Throw {
+                ref mut value,
+            } => {
+                value.transform(ctx);
+                return;
+            },
 
-            _ => false,
-        }
-    }
-}
+            Statement::Return {
+                ref mut value,
+            } => {
+                value.transform(ctx);
+                return;
+            },
 
-impl Transformable for ClassMember {
-    fn transform(&mut self, settings: &Settings) {
-        match *self {
-            Constructor {
-                ref mut params,
+            Statement::Transparent {
                 ref mut body,
             } => {
-                params.transform(settings);
-                body.transform(settings);
+                body.transform(ctx);
+                return;
             },
 
-            Method {
+            Statement::Function {
+                ref mut name,
                 ref mut params,
                 ref mut body,
-                ..
             } => {
-                params.transform(settings);
-                body.transform(settings);
+                *name = ctx.renamed(*name);
+                ctx.own_this(body, |ctx, body| {
+                    params.transform(ctx);
+                    body.transform(ctx);
+                    ctx.lower_parameters(params, body);
+                });
+                return;
             },
 
-            Property {
-                ref mut value,
-                ..
+            Statement::While {
+                ref mut test,
+                ref mut body,
+            } |
+            Statement::DoWhile {
+                ref mut body,
+                ref mut test,
             } => {
-                value.transform(settings);
-            }
-        }
-    }
-}
+                let closure = ctx.loop_closure(None, body);
 
-impl Transformable for VariableDeclarator {
-    #[inline]
-    fn transform(&mut self, settings: &Settings) {
-        self.value.transform(settings);
-    }
-
-    #[inline]
-    fn contains_this(&self) -> bool {
-        self.value.contains_this()
-    }
-}
-
-fn add_props_to_body(body: &mut Vec<Statement>, mut props: Vec<ClassMember>) {
-    body.reserve(props.len());
+                test.transform(ctx);
+                ctx.loop_body(body);
 
-    for prop in props.iter_mut().rev() {
-        if let &mut ClassMember::Property {
-            // ref is_static,
-            ref name,
-            ref mut value,
-            ..
-        } = prop {
-            body.insert(
-                0,
-                Expression::binary(
-                    Expression::member(Expression::This, *name),
-                    Assign,
-                    value.take(),
-                ).into()
-            );
-        }
-    }
-}
+                match closure {
+                    Some(closure) => ctx.lower_loop(mem::replace(self, Statement::Transparent { body: Vec::new() }), closure),
+                    None          => return,
+                }
+            },
 
-impl Transformable for Statement {
-    fn transform(&mut self, settings: &Settings) {
-        *self = match *self {
-            Statement::Block {
-                ref mut body,
+            Statement::Switch {
+                ref mut discriminant,
+                ref mut cases,
             } => {
-                body.transform(settings);
+                discriminant.transform(ctx);
+                cases.transform(ctx);
                 return;
             },
 
-            Statement::Labeled {
+            Statement::For {
+                ref mut init,
+                ref mut test,
+                ref mut update,
                 ref mut body,
-                ..
             } => {
-                body.transform(settings);
-                return;
+                let closure = ctx.loop_closure(init.as_ref().map(|init| &init.item), body);
+
+                init.transform(ctx);
+                test.transform(ctx);
+                update.transform(ctx);
+                ctx.loop_body(body);
+
+                match closure {
+                    Some(closure) => ctx.lower_loop(mem::replace(self, Statement::Transparent { body: Vec::new() }), closure),
+                    None          => return,
+                }
             },
 
-            Statement::VariableDeclaration {
-                ref mut kind,
-                ref mut declarators,
+            Statement::ForIn {
+                ref mut left,
+                ref mut right,
+                ref mut body,
+            } |
+            Statement::ForOf {
+                ref mut left,
+                ref mut right,
+                ref mut body,
             } => {
-                declarators.transform(settings);
+                let closure = ctx.loop_closure(Some(&left.item), body);
 
-                // transformation flag check
-                if !settings.transform_block_scope {
-                    return;
+                // Declaration in the head gets a new binding without a value
+                let loops = mem::replace(&mut ctx.loops, 0);
+
+                left.transform(ctx);
+                ctx.loops = loops;
+                right.transform(ctx);
+                ctx.loop_body(body);
+                ctx.lower_loop_head(left, body);
+
+                match closure {
+                    Some(closure) => ctx.lower_loop(mem::replace(self, Statement::Transparent { body: Vec::new() }), closure),
+                    None          => return,
                 }
+            },
 
-                *kind = VariableDeclarationKind::Var;
+            Statement::ExportDeclaration {
+                kind: ExportKind::Declaration(ref mut statement),
+            } |
+            Statement::ExportDeclaration {
+                kind: ExportKind::Default(ref mut statement),
+            } => {
+                statement.transform(ctx);
                 return;
             },
 
-            Statement::Expression {
-                ref mut value,
+            Statement::Try {
+                ref mut block,
+                ref mut handler,
+                ref mut finalizer,
             } => {
-                value.transform(settings);
+                block.transform(ctx);
+                handler.transform(ctx);
+                finalizer.transform(ctx);
                 return;
             },
 
@@ -501,109 +3939,115 @@ impl Transformable for Statement {
                 ref mut alternate,
                 ..
             } => {
-                test.transform(settings);
-                consequent.transform(settings);
-                alternate.transform(settings);
+                test.transform(ctx);
+                consequent.transform(ctx);
+                alternate.transform(ctx);
                 return;
             },
 
             Statement::Class {
-                ref name,
+                ref mut name,
+                ref mut extends,
                 ref mut body,
-                ..
             } => {
-                body.transform(settings);
-
-                if !settings.transform_class_properties
-                && !settings.transform_class {
-                    return;
-                }
+                *name = ctx.renamed(*name);
 
-                let prop_count = body.iter().filter(|member| match **member {
-                    ClassMember::Property { .. } => true,
-                    _                            => false,
-                }).count();
+                // Imported parent classes are read from the module when the
+                // class is defined: `class A extends B` -> `var _B1 = _module.B`
+                let mut parent = None;
 
-                if prop_count == 0 {
-                    return;
-                }
+                *extends = extends.map(|extends| match ctx.imported(extends) {
+                    Some(value) => {
+                        let local = ctx.unique_name(&format!("_{}", extends.as_str()));
 
-                let mut constructor = None;
-                let mut methods = Vec::with_capacity(body.len());
-                let mut props = Vec::with_capacity(prop_count);
+                        parent = Some(var(local, value));
+                        local
+                    },
+                    None        => ctx.renamed(extends),
+                });
 
-                for member in body.drain(..) {
-                    match member {
-                        ClassMember::Property {
-                            ..
-                        } => props.push(member),
+                let lowers_props = ctx.settings.transform_class_properties || ctx.settings.transform_class;
 
-                        ClassMember::Constructor {
-                            params,
-                            body,
-                        } => constructor = Some((params, body)),
+                let has_statics = body.iter().any(|member| match member.item {
+                    ClassMember::Property { is_static, .. } => is_static,
+                    _                                       => false,
+                });
 
-                        _ => methods.push(member),
-                    }
+                // Anonymous class needs a name to be lowered, or to have
+                // static properties assigned to it
+                if name.as_str().is_empty() && (ctx.settings.transform_class || (lowers_props && has_statics)) {
+                    *name = ctx.unique_name("_default");
                 }
 
-                let (cnst_params, mut cnst_body) = constructor.unwrap_or_else(|| {
-                    (Vec::new(), Vec::new())
-                });
+                let (name, extends) = (*name, *extends);
 
-                add_props_to_body(&mut cnst_body, props);
+                // Static properties are assigned to the class after it's defined
+                let mut statics = if lowers_props {
+                    static_props(body, name)
+                } else {
+                    Vec::new()
+                };
 
-                if !settings.transform_class {
-                    methods.insert(0, ClassMember::Constructor {
-                        params: cnst_params,
-                        body: cnst_body,
-                    });
+                // `super` in static initializers refers to the parent class
+                let outer = ctx.static_this.replace(name);
+                let outer_super = mem::replace(&mut ctx.superclass, extends.map(|parent| (parent, true)));
 
-                    *body = methods;
+                statics.transform(ctx);
+                ctx.superclass = outer_super;
+                ctx.static_this = outer;
 
-                    return;
+                let has_props = body.iter().any(|member| {
+                    matches!(member.item, ClassMember::Property { .. })
+                });
+
+                // Properties are initialized in the constructor
+                if ctx.settings.transform_class
+                || (ctx.settings.transform_class_properties && has_props) {
+                    add_props_to_constructor(body, extends);
                 }
 
-                let constructor = Statement::Function {
-                    name: *name,
-                    params: cnst_params,
-                    body: cnst_body,
-                };
+                for member in body.iter_mut() {
+                    let is_static = match member.item {
+                        ClassMember::Method { is_static, .. } |
+                        ClassMember::Getter { is_static, .. } |
+                        ClassMember::Setter { is_static, .. } => is_static,
+                        _                                     => false,
+                    };
 
-                if methods.len() > 0 {
-                    let mut body = Vec::with_capacity(methods.len() + 1);
+                    let superclass = match extends {
+                        Some(parent) if ctx.settings.transform_class => Some((parent, is_static)),
+                        _                                            => None,
+                    };
 
-                    body.push(constructor);
+                    let outer = mem::replace(&mut ctx.superclass, superclass);
 
-                    for method in methods.iter_mut() {
-                        if let &mut ClassMember::Method {
-                            name: ref method_name,
-                            params: ref mut method_params,
-                            body: ref mut method_body,
-                            ..
-                        } = method {
-                            body.push(
-                                Expression::binary(
-                                    Expression::member(
-                                        Expression::member(name, "prototype"),
-                                        *method_name
-                                    ),
-                                    Assign,
-                                    Expression::Function {
-                                        name: Some(*method_name),
-                                        params: method_params.take(),
-                                        body: method_body.take(),
-                                    },
-                                ).into()
-                            );
-                        }
-                    }
+                    member.transform(ctx);
+
+                    ctx.superclass = outer;
+                }
+
+                // transformation flag check
+                let class = if ctx.settings.transform_class {
+                    ctx.lower_class(name, extends, body.take(), statics)
+                } else if statics.is_empty() && parent.is_none() {
+                    return;
+                } else {
+                    statics.insert(0, Statement::Class {
+                        name: name,
+                        extends: extends,
+                        body: body.take(),
+                    }.into());
 
                     Statement::Transparent {
-                        body: body
+                        body: statics
                     }
-                } else {
-                    constructor
+                };
+
+                match parent {
+                    Some(parent) => Statement::Transparent {
+                        body: vec![parent.into(), class.into()],
+                    },
+                    None         => class,
                 }
             }
 
@@ -626,6 +4070,69 @@ impl Transformable for Statement {
                 value: Some(ref expression)
             } => expression.contains_this(),
 
+            Statement::Throw {
+                ref value,
+            } => value.contains_this(),
+
+            Statement::Block {
+                ref body,
+            } |
+            Statement::Transparent {
+                ref body,
+            } => body.contains_this(),
+
+            Statement::Labeled {
+                ref body,
+                ..
+            } => body.contains_this(),
+
+            Statement::If {
+                ref test,
+                ref consequent,
+                ref alternate,
+            } => test.contains_this() || consequent.contains_this() || alternate.contains_this(),
+
+            Statement::While {
+                ref test,
+                ref body,
+            } |
+            Statement::DoWhile {
+                ref body,
+                ref test,
+            } => test.contains_this() || body.contains_this(),
+
+            Statement::Switch {
+                ref discriminant,
+                ref cases,
+            } => discriminant.contains_this() || cases.contains_this(),
+
+            Statement::For {
+                ref init,
+                ref test,
+                ref update,
+                ref body,
+            } => {
+                init.contains_this() || test.contains_this() ||
+                update.contains_this() || body.contains_this()
+            },
+
+            Statement::ForIn {
+                ref left,
+                ref right,
+                ref body,
+            } |
+            Statement::ForOf {
+                ref left,
+                ref right,
+                ref body,
+            } => left.contains_this() || right.contains_this() || body.contains_this(),
+
+            Statement::Try {
+                ref block,
+                ref handler,
+                ref finalizer,
+            } => block.contains_this() || handler.contains_this() || finalizer.contains_this(),
+
             _ => false,
         }
     }
@@ -633,20 +4140,15 @@ impl Transformable for Statement {
 
 impl<T: Transformable> Transformable for Vec<T> {
     #[inline]
-    fn transform(&mut self, settings: &Settings) {
+    fn transform(&mut self, ctx: &mut Transformer) {
         for item in self.iter_mut() {
-            item.transform(settings);
+            item.transform(ctx);
         }
     }
 
     #[inline]
     fn contains_this(&self) -> bool {
-        for item in self {
-            if item.contains_this() {
-                return true;
-            }
-        }
-        return false;
+        self.iter().any(|item| item.contains_this())
     }
 }
 
@@ -667,6 +4169,51 @@ fn partition_vec<T, F: Fn(&T) -> bool>(source: &mut Vec<T>, f: F) -> Vec<T> {
     other
 }
 
-pub fn transform(program: &mut Program, settings: Settings) {
-    program.body.transform(&settings);
+pub fn transform(program: &mut Program, settings: Settings) -> TransformResult<()> {
+    // transformation flag check
+    let scopes = if settings.transform_block_scope || settings.mangle || settings.transform_commonjs {
+        Some(scope::analyze(program))
+    } else {
+        None
+    };
+
+    let mut ctx = Transformer::new(&settings, &mut program.strings);
+
+    if let Some(ref scopes) = scopes {
+        if settings.transform_block_scope {
+            ctx.analyze_block_scope(scopes)?;
+        }
+
+        if settings.mangle {
+            ctx.mangle(scopes);
+        }
+
+        ctx.lower_modules(&mut program.body, scopes)?;
+    }
+
+    program.body.transform(&mut ctx);
+
+    if let Some(error) = ctx.error.take() {
+        return Err(error);
+    }
+
+    let temporaries = ctx.temporaries.take();
+
+    ctx.hoisted.extend(temporaries.into_iter().map(|name| VariableDeclarator {
+        name: Pattern::Identifier(name),
+        value: None,
+    }));
+
+    if !ctx.hoisted.is_empty() {
+        program.body.insert(0, Statement::VariableDeclaration {
+            kind: VariableDeclarationKind::Var,
+            declarators: ctx.hoisted.take(),
+        }.into());
+    }
+
+    let helpers = ctx.helpers.iter().map(|&name| helper_function(name).into());
+
+    program.body.splice(0 .. 0, helpers);
+
+    Ok(())
 }
@@ -0,0 +1,721 @@
+use std::collections::{ HashMap, HashSet };
+use std::fmt;
+use std::path::{ Path, PathBuf };
+
+use bundler::{ self, Module, ModuleGraph };
+use grammar::*;
+use visitor::{ Visitor, Visitable };
+
+/// Why a declaration or a whole module was removed from the bundle.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Reason {
+    /// Declaration is exported, but none of the modules import it.
+    UnusedExport,
+
+    /// Declaration isn't referenced by any of the code kept in its module.
+    Unreferenced,
+
+    /// None of the exports of the module are used, and its `package.json`
+    /// declares it doesn't have side effects.
+    NoSideEffects,
+}
+
+/// A single entry of the `Report`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Removal {
+    pub path: PathBuf,
+
+    /// Name of the removed declaration, `None` if the whole module was removed
+    pub name: Option<String>,
+
+    pub reason: Reason,
+}
+
+/// Lists everything the tree shaking removed from a `ModuleGraph`.
+#[derive(Debug, PartialEq, Default)]
+pub struct Report {
+    pub removed: Vec<Removal>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for removal in &self.removed {
+            let path = removal.path.display();
+
+            match (&removal.name, removal.reason) {
+                (Some(name), Reason::UnusedExport) => {
+                    writeln!(f, "{}: removed `{}`, export is never imported", path, name)?
+                },
+                (Some(name), _) => {
+                    writeln!(f, "{}: removed `{}`, it's never referenced", path, name)?
+                },
+                (None, _) => {
+                    writeln!(f, "{}: removed module, none of its exports are used and it has no side effects", path)?
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Top level declaration of a module, or any other statement.
+struct Item {
+    // Index of the statement in the module body, and of the declarator
+    // if the item is a part of a variable declaration
+    statement: usize,
+    declarator: Option<usize>,
+
+    names: Vec<OwnedSlice>,
+    references: Vec<OwnedSlice>,
+    requires: Vec<usize>,
+
+    // Can the item be dropped without changing the behavior of the module
+    pure: bool,
+    live: bool,
+}
+
+enum Export {
+    Local(OwnedSlice),
+    Item(usize),
+    Reexport(usize, OwnedSlice),
+}
+
+/// What the tree shaking knows about a single module of the graph.
+#[derive(Default)]
+struct ModuleInfo {
+    // Only ES modules can be shaken, CommonJS modules are kept whole
+    es_module: bool,
+    side_effects: bool,
+
+    // Is the module going to be evaluated at all
+    included: bool,
+    all_used: bool,
+
+    items: Vec<Item>,
+    declarations: HashMap<OwnedSlice, Vec<usize>>,
+
+    // Imported bindings, `None` standing for a namespace import
+    imports: HashMap<OwnedSlice, (usize, Option<OwnedSlice>)>,
+    exports: HashMap<OwnedSlice, Export>,
+    stars: Vec<usize>,
+
+    // Modules evaluated by the import and export declarations
+    dependencies: Vec<usize>,
+
+    used_exports: HashSet<OwnedSlice>,
+    used_imports: HashSet<OwnedSlice>,
+}
+
+impl ModuleInfo {
+    fn new(module: &Module) -> Self {
+        let mut info = ModuleInfo {
+            side_effects: has_side_effects(&module.path),
+            ..ModuleInfo::default()
+        };
+
+        for (index, statement) in module.program.body.iter().enumerate() {
+            let kind = match statement.item {
+                Statement::ImportDeclaration {
+                    ref specifiers,
+                    source,
+                } => {
+                    let target = dependency(module, source);
+
+                    info.es_module = true;
+                    info.dependencies.push(target);
+
+                    for specifier in specifiers {
+                        let (local, imported) = match *specifier {
+                            ImportSpecifier::Default { local }         => (local, Some("default".into())),
+                            ImportSpecifier::Named { imported, local } => (local, Some(imported)),
+                            ImportSpecifier::Namespace { local }       => (local, None),
+                        };
+
+                        info.imports.insert(local, (target, imported));
+                    }
+
+                    continue;
+                },
+
+                Statement::ExportDeclaration {
+                    ref kind,
+                } => kind,
+
+                ref statement => {
+                    info.add_items(module, index, statement);
+                    continue;
+                }
+            };
+
+            info.es_module = true;
+
+            match *kind {
+                ExportKind::Declaration(ref declaration) => {
+                    let first = info.items.len();
+
+                    info.add_items(module, index, declaration);
+
+                    for item in &info.items[first ..] {
+                        for &name in &item.names {
+                            info.exports.insert(name, Export::Local(name));
+                        }
+                    }
+                },
+
+                ExportKind::Default(ref declaration) => {
+                    let export = match declaration.item {
+                        Statement::Function {
+                            name,
+                            ..
+                        } |
+                        Statement::Class {
+                            name,
+                            ..
+                        } => {
+                            info.add_items(module, index, declaration);
+
+                            Export::Local(name)
+                        },
+
+                        Statement::Expression {
+                            ref value,
+                        } => {
+                            info.add_item(module, index, None, Vec::new(), value, value.is_pure());
+
+                            Export::Item(info.items.len() - 1)
+                        },
+
+                        _ => unreachable!(),
+                    };
+
+                    info.exports.insert("default".into(), export);
+                },
+
+                ExportKind::Named {
+                    ref specifiers,
+                    source: None,
+                } => {
+                    for specifier in specifiers {
+                        info.exports.insert(specifier.exported, Export::Local(specifier.local));
+                    }
+                },
+
+                ExportKind::Named {
+                    ref specifiers,
+                    source: Some(source),
+                } => {
+                    let target = dependency(module, source);
+
+                    info.dependencies.push(target);
+
+                    for specifier in specifiers {
+                        info.exports.insert(specifier.exported, Export::Reexport(target, specifier.local));
+                    }
+                },
+
+                ExportKind::All {
+                    source,
+                } => {
+                    let target = dependency(module, source);
+
+                    info.dependencies.push(target);
+                    info.stars.push(target);
+                },
+            }
+        }
+
+        info
+    }
+
+    /// Adds the items of a top level `statement`, a separate one for
+    /// each declarator of a variable declaration.
+    fn add_items(&mut self, module: &Module, index: usize, statement: &Statement) {
+        if let Statement::VariableDeclaration {
+            ref declarators,
+            ..
+        } = *statement {
+            for (position, declarator) in declarators.iter().enumerate() {
+                let mut names = Vec::new();
+
+                declarator.name.names(&mut names);
+
+                let pure = declarator.name.is_identifier() &&
+                           declarator.value.as_ref().is_none_or(|value| value.is_pure());
+
+                self.add_item(module, index, Some(position), names, declarator, pure);
+            }
+
+            return;
+        }
+
+        let (names, pure) = match *statement {
+            Statement::Function {
+                name,
+                ..
+            } => (vec![name], true),
+
+            Statement::Class {
+                name,
+                ref body,
+                ..
+            } => (vec![name], body.iter().all(|member| match member.item {
+                ClassMember::Property {
+                    is_static: true,
+                    ref value,
+                    ..
+                } => value.is_pure(),
+                _ => true,
+            })),
+
+            _ => (Vec::new(), false),
+        };
+
+        self.add_item(module, index, None, names, statement, pure);
+    }
+
+    fn add_item<T: Visitable>(
+        &mut self,
+        module: &Module,
+        index: usize,
+        declarator: Option<usize>,
+        names: Vec<OwnedSlice>,
+        node: &T,
+        pure: bool
+    ) {
+        let mut references = References {
+            names: Vec::new(),
+            requires: Vec::new(),
+        };
+
+        node.visit(&mut references);
+
+        for &name in &names {
+            self.declarations.entry(name).or_default().push(self.items.len());
+        }
+
+        self.items.push(Item {
+            statement: index,
+            declarator: declarator,
+            names: names,
+            references: references.names,
+            requires: references.requires.into_iter().map(|source| dependency(module, source)).collect(),
+            pure: pure,
+            live: false,
+        });
+    }
+
+    /// Explains why an item that isn't live is removed.
+    #[inline]
+    fn reason(&self, item: &Item) -> Reason {
+        let exported = self.exports.values().any(|export| match *export {
+            Export::Local(local) => item.names.contains(&local),
+            _                    => false,
+        });
+
+        if exported || item.names.is_empty() {
+            Reason::UnusedExport
+        } else {
+            Reason::Unreferenced
+        }
+    }
+}
+
+/// Collects names and `require`d modules referenced by a piece of code.
+struct References {
+    names: Vec<OwnedSlice>,
+    requires: Vec<OwnedSlice>,
+}
+
+impl Visitor for References {
+    #[inline]
+    fn expression(&mut self, expression: &Expression) {
+        if let Some(source) = bundler::required_source(expression).or_else(|| bundler::imported_source(expression)) {
+            self.requires.push(source);
+        }
+    }
+
+    #[inline]
+    fn reference(&mut self, name: OwnedSlice) {
+        self.names.push(name);
+    }
+}
+
+#[inline]
+fn dependency(module: &Module, source: OwnedSlice) -> usize {
+    module.dependency(bundler::unquote(&source)).expect("Module graph resolves all dependencies")
+}
+
+/// Unit of work of the marking phase.
+enum Work {
+    Include(usize),
+    Item(usize, usize),
+    Name(usize, OwnedSlice),
+    Export(usize, OwnedSlice),
+    All(usize),
+}
+
+/// Marks everything that's needed by the entry module, starting with all
+/// of its exports and side effects of all included modules.
+fn mark(modules: &mut [ModuleInfo]) {
+    let mut stack = vec![Work::All(0)];
+
+    while let Some(work) = stack.pop() {
+        match work {
+            Work::Include(index) => {
+                let module = &mut modules[index];
+
+                if module.included {
+                    continue;
+                }
+
+                // Modules without side effects are only evaluated when used
+                if !module.side_effects && index != 0 && !module.all_used && module.used_exports.is_empty() {
+                    continue;
+                }
+
+                module.included = true;
+
+                for (item, entry) in module.items.iter().enumerate() {
+                    if !module.es_module || !entry.pure {
+                        stack.push(Work::Item(index, item));
+                    }
+                }
+
+                stack.extend(module.dependencies.iter().map(|&dependency| Work::Include(dependency)));
+            },
+
+            Work::Item(index, item) => {
+                let item = &mut modules[index].items[item];
+
+                if item.live {
+                    continue;
+                }
+
+                item.live = true;
+
+                for &name in &item.references {
+                    stack.push(Work::Name(index, name));
+                }
+
+                for &dependency in &item.requires {
+                    stack.push(Work::All(dependency));
+                }
+            },
+
+            Work::Name(index, name) => {
+                let module = &mut modules[index];
+
+                if let Some(items) = module.declarations.get(&name) {
+                    for &item in items {
+                        stack.push(Work::Item(index, item));
+                    }
+                } else if let Some(&(dependency, imported)) = module.imports.get(&name) {
+                    module.used_imports.insert(name);
+
+                    stack.push(match imported {
+                        Some(imported) => Work::Export(dependency, imported),
+                        None           => Work::All(dependency),
+                    });
+                }
+            },
+
+            Work::Export(index, name) => {
+                let module = &mut modules[index];
+
+                if !module.es_module {
+                    stack.push(Work::All(index));
+                    continue;
+                }
+
+                if !module.used_exports.insert(name) {
+                    continue;
+                }
+
+                stack.push(Work::Include(index));
+
+                match module.exports.get(&name) {
+                    Some(&Export::Local(local))                => stack.push(Work::Name(index, local)),
+                    Some(&Export::Item(item))                  => stack.push(Work::Item(index, item)),
+                    Some(&Export::Reexport(dependency, local)) => stack.push(Work::Export(dependency, local)),
+                    None if name.as_str() != "default"         => {
+                        for &dependency in &module.stars {
+                            stack.push(Work::Export(dependency, name));
+                        }
+                    },
+                    None => {},
+                }
+            },
+
+            Work::All(index) => {
+                let module = &mut modules[index];
+
+                if module.all_used {
+                    continue;
+                }
+
+                module.all_used = true;
+
+                stack.push(Work::Include(index));
+
+                for &name in module.exports.keys() {
+                    stack.push(Work::Export(index, name));
+                }
+
+                for &dependency in &module.stars {
+                    stack.push(Work::All(dependency));
+                }
+            },
+        }
+    }
+}
+
+/// Removes exports that aren't imported by any other module, and top
+/// level declarations which aren't referenced and whose initializers
+/// have no side effects. Modules which don't have side effects, as
+/// declared by the `sideEffects` field of their `package.json`, are
+/// removed altogether if none of their exports are used.
+pub fn shake(graph: &mut ModuleGraph) -> Report {
+    let mut report = Report::default();
+
+    if graph.modules.is_empty() {
+        return report;
+    }
+
+    let mut infos: Vec<ModuleInfo> = graph.modules.iter().map(ModuleInfo::new).collect();
+
+    mark(&mut infos);
+
+    let included: Vec<bool> = infos.iter().map(|info| info.included).collect();
+
+    for (module, info) in graph.modules.iter_mut().zip(infos.iter()) {
+        if !info.included {
+            report.removed.push(Removal {
+                path: module.path.clone(),
+                name: None,
+                reason: Reason::NoSideEffects,
+            });
+            continue;
+        }
+
+        if info.es_module {
+            shake_module(module, info, &included, &mut report);
+        }
+    }
+
+    // Drop removed modules and update indexes of the remaining ones
+    let mut indexes = Vec::with_capacity(included.len());
+    let mut count = 0;
+
+    for &included in &included {
+        indexes.push(if included { Some(count) } else { None });
+
+        if included {
+            count += 1;
+        }
+    }
+
+    let mut index = 0;
+
+    graph.modules.retain(|_| {
+        index += 1;
+        included[index - 1]
+    });
+
+    for module in &mut graph.modules {
+        module.dependencies = module.dependencies.drain(..).filter_map(|(specifier, dependency)| {
+            indexes[dependency].map(|dependency| (specifier, dependency))
+        }).collect();
+    }
+
+    report
+}
+
+/// Rewrites the body of an included ES module, dropping all items
+/// that aren't live along with unused imports and exports.
+fn shake_module(module: &mut Module, info: &ModuleInfo, included: &[bool], report: &mut Report) {
+    let body = module.program.body.drain(..).collect::<Vec<_>>();
+    let mut kept = Vec::with_capacity(body.len());
+
+    for (index, mut statement) in body.into_iter().enumerate() {
+        let items: Vec<&Item> = info.items.iter().filter(|item| item.statement == index).collect();
+
+        let keep = match statement.item {
+            Statement::ImportDeclaration {
+                ref mut specifiers,
+                source,
+            } => {
+                specifiers.retain(|specifier| match *specifier {
+                    ImportSpecifier::Default { local }   |
+                    ImportSpecifier::Namespace { local } |
+                    ImportSpecifier::Named { local, .. } => info.used_imports.contains(&local),
+                });
+
+                !specifiers.is_empty() || included[dependency(module, source)]
+            },
+
+            Statement::ExportDeclaration {
+                ref mut kind,
+            } => match *kind {
+                ExportKind::Declaration(ref mut declaration) |
+                ExportKind::Default(ref mut declaration) => {
+                    shake_statement(declaration, &items, &module.path, info, report)
+                },
+
+                ExportKind::Named {
+                    ref mut specifiers,
+                    source,
+                } => {
+                    if !info.all_used {
+                        specifiers.retain(|specifier| info.used_exports.contains(&specifier.exported));
+                    }
+
+                    match source {
+                        _ if !specifiers.is_empty() => true,
+                        Some(source) if included[dependency(module, source)] => {
+                            // Keep the side effects of the re-exported module
+                            statement.item = Statement::ImportDeclaration {
+                                specifiers: Vec::new(),
+                                source: source,
+                            };
+                            true
+                        },
+                        _ => false,
+                    }
+                },
+
+                ExportKind::All {
+                    source,
+                } => included[dependency(module, source)],
+            },
+
+            _ => shake_statement(&mut statement, &items, &module.path, info, report),
+        };
+
+        if keep {
+            kept.push(statement);
+        }
+    }
+
+    module.program.body = kept;
+}
+
+/// Removes dead `items` of a top level `statement`, returns `false` if
+/// nothing is left of the statement.
+fn shake_statement(
+    statement: &mut Loc<Statement>,
+    items: &[&Item],
+    path: &Path,
+    info: &ModuleInfo,
+    report: &mut Report
+) -> bool {
+    if let Statement::VariableDeclaration {
+        ref mut declarators,
+        ..
+    } = statement.item {
+        let mut position = 0;
+
+        declarators.retain(|_| {
+            let item = items.iter().find(|item| item.declarator == Some(position));
+
+            position += 1;
+
+            match item {
+                Some(item) if !item.live => {
+                    report_removal(item, path, info, report);
+                    false
+                },
+                _ => true,
+            }
+        });
+
+        return !declarators.is_empty();
+    }
+
+    match items.first() {
+        Some(item) if !item.live => {
+            report_removal(item, path, info, report);
+            false
+        },
+        _ => true,
+    }
+}
+
+#[inline]
+fn report_removal(item: &Item, path: &Path, info: &ModuleInfo, report: &mut Report) {
+    if item.names.is_empty() {
+        report.removed.push(Removal {
+            path: path.to_path_buf(),
+            name: Some("default".to_string()),
+            reason: Reason::UnusedExport,
+        });
+    }
+
+    for name in &item.names {
+        report.removed.push(Removal {
+            path: path.to_path_buf(),
+            name: Some(name.as_str().to_string()),
+            reason: info.reason(item),
+        });
+    }
+}
+
+/// Checks the `sideEffects` field of the closest `package.json` to find
+/// out if the module at `path` can have side effects when evaluated.
+fn has_side_effects(path: &Path) -> bool {
+    for directory in path.ancestors().skip(1) {
+        let package = match bundler::read_package(directory) {
+            Some(package) => package,
+            None          => continue,
+        };
+
+        let relative = path.strip_prefix(directory).unwrap_or(path).to_string_lossy().replace('\\', "/");
+
+        return match package.find("sideEffects") {
+            Some(side_effects) if side_effects.is_boolean() => side_effects.as_boolean() == Some(true),
+            Some(side_effects) if side_effects.is_array()   => {
+                side_effects.as_array().unwrap().iter().filter_map(|pattern| pattern.as_string()).any(|pattern| {
+                    let pattern = pattern.trim_start_matches("./");
+
+                    // Patterns without a slash match the file name anywhere
+                    if pattern.contains('/') {
+                        glob_match(pattern.as_bytes(), relative.as_bytes())
+                    } else {
+                        let name = relative.rsplit('/').next().unwrap_or(&relative);
+
+                        glob_match(pattern.as_bytes(), name.as_bytes())
+                    }
+                })
+            },
+            _ => true,
+        };
+    }
+
+    true
+}
+
+/// Matches a `path` against a glob `pattern` with `*` matching anything
+/// but a slash, `**` matching anything and `?` matching a single byte.
+fn glob_match(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern.first() {
+        None => path.is_empty(),
+
+        Some(&b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = &pattern[2 ..];
+            let rest = if rest.first() == Some(&b'/') { &rest[1 ..] } else { rest };
+
+            (0 ..= path.len()).any(|index| glob_match(rest, &path[index ..]))
+        },
+
+        Some(&b'*') => {
+            let rest = &pattern[1 ..];
+
+            (0 ..= path.len()).take_while(|&index| index == 0 || path[index - 1] != b'/')
+                              .any(|index| glob_match(rest, &path[index ..]))
+        },
+
+        Some(&b'?') => !path.is_empty() && path[0] != b'/' && glob_match(&pattern[1 ..], &path[1 ..]),
+
+        Some(&byte) => path.first() == Some(&byte) && glob_match(&pattern[1 ..], &path[1 ..]),
+    }
+}
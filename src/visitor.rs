@@ -0,0 +1,455 @@
+use grammar::*;
+
+/// Callbacks invoked while walking the AST with `Visitable::visit`. Nodes
+/// are passed to the callbacks before any of their children are visited.
+pub trait Visitor {
+    #[inline]
+    fn statement(&mut self, _: &Statement) {}
+
+    #[inline]
+    fn expression(&mut self, _: &Expression) {}
+
+    /// Called for every identifier that reads a variable, including
+    /// shorthand object members and the superclass of a class.
+    #[inline]
+    fn reference(&mut self, _: OwnedSlice) {}
+}
+
+/// The `Visitable` trait provides read-only traversal of the AST, the
+/// counterpart of `Transformable` used by the transformer.
+pub trait Visitable {
+    fn visit<V: Visitor>(&self, visitor: &mut V);
+}
+
+impl<T: Visitable> Visitable for Vec<T> {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        for item in self {
+            item.visit(visitor);
+        }
+    }
+}
+
+impl<T: Visitable> Visitable for Option<T> {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        if let Some(ref item) = *self {
+            item.visit(visitor);
+        }
+    }
+}
+
+impl<T: Visitable> Visitable for Box<T> {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        (**self).visit(visitor);
+    }
+}
+
+impl<T: Visitable> Visitable for Loc<T> {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        self.item.visit(visitor);
+    }
+}
+
+impl Visitable for Parameter {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        self.name.visit(visitor);
+        self.default.visit(visitor);
+    }
+}
+
+impl Visitable for Pattern {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        match *self {
+            Pattern::Identifier(_) => {},
+
+            Pattern::Object(ref properties) => {
+                for property in properties {
+                    property.value.visit(visitor);
+                }
+            },
+
+            Pattern::Array(ref elements) => {
+                for element in elements {
+                    element.visit(visitor);
+                }
+            },
+
+            Pattern::Rest(ref pattern) => pattern.visit(visitor),
+
+            Pattern::Default {
+                ref pattern,
+                ref value,
+            } => {
+                pattern.visit(visitor);
+                value.visit(visitor);
+            },
+
+            Pattern::Expression(ref expression) => expression.visit(visitor),
+        }
+    }
+}
+
+impl Visitable for ObjectMember {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        match *self {
+            ObjectMember::Shorthand {
+                key,
+            } => visitor.reference(key),
+
+            ObjectMember::Literal {
+                ref value,
+                ..
+            } => value.visit(visitor),
+
+            ObjectMember::Computed {
+                ref key,
+                ref value,
+            } => {
+                key.visit(visitor);
+                value.visit(visitor);
+            },
+
+            ObjectMember::Method {
+                ref params,
+                ref body,
+                ..
+            } |
+            ObjectMember::Setter {
+                ref params,
+                ref body,
+                ..
+            } => {
+                params.visit(visitor);
+                body.visit(visitor);
+            },
+
+            ObjectMember::Getter {
+                ref body,
+                ..
+            } => body.visit(visitor),
+
+            ObjectMember::ComputedMethod {
+                ref name,
+                ref params,
+                ref body,
+            } => {
+                name.visit(visitor);
+                params.visit(visitor);
+                body.visit(visitor);
+            },
+        }
+    }
+}
+
+impl Visitable for ClassMember {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        match *self {
+            ClassMember::Constructor {
+                ref params,
+                ref body,
+            } |
+            ClassMember::Method {
+                ref params,
+                ref body,
+                ..
+            } |
+            ClassMember::Setter {
+                ref params,
+                ref body,
+                ..
+            } => {
+                params.visit(visitor);
+                body.visit(visitor);
+            },
+
+            ClassMember::Getter {
+                ref body,
+                ..
+            } => body.visit(visitor),
+
+            ClassMember::Property {
+                ref value,
+                ..
+            } => value.visit(visitor),
+        }
+    }
+}
+
+impl Visitable for Expression {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.expression(self);
+
+        match *self {
+            Expression::Identifier(name) => visitor.reference(name),
+
+            Expression::Array(ref items) |
+            Expression::Sequence(ref items) => items.visit(visitor),
+
+            Expression::Object(ref members) => members.visit(visitor),
+
+            Expression::Member {
+                ref object,
+                ..
+            } => object.visit(visitor),
+
+            Expression::ComputedMember {
+                ref object,
+                ref property,
+            } => {
+                object.visit(visitor);
+                property.visit(visitor);
+            },
+
+            Expression::Call {
+                ref callee,
+                ref arguments,
+            } => {
+                callee.visit(visitor);
+                arguments.visit(visitor);
+            },
+
+            Expression::Binary {
+                ref left,
+                ref right,
+                ..
+            } => {
+                left.visit(visitor);
+                right.visit(visitor);
+            },
+
+            Expression::Prefix {
+                ref operand,
+                ..
+            } |
+            Expression::Postfix {
+                ref operand,
+                ..
+            } => operand.visit(visitor),
+
+            Expression::Conditional {
+                ref test,
+                ref consequent,
+                ref alternate,
+            } => {
+                test.visit(visitor);
+                consequent.visit(visitor);
+                alternate.visit(visitor);
+            },
+
+            Expression::ArrowFunction {
+                ref params,
+                ref body,
+            } => {
+                params.visit(visitor);
+                body.visit(visitor);
+            },
+
+            Expression::Function {
+                ref params,
+                ref body,
+                ..
+            } => {
+                params.visit(visitor);
+                body.visit(visitor);
+            },
+
+            Expression::TemplateLiteral {
+                ref expressions,
+                ..
+            } => expressions.visit(visitor),
+
+            Expression::TaggedTemplate {
+                ref tag,
+                ref expressions,
+                ..
+            } => {
+                tag.visit(visitor);
+                expressions.visit(visitor);
+            },
+
+            Expression::Pattern(ref pattern) => pattern.visit(visitor),
+
+            Expression::Import {
+                ref source,
+            } => source.visit(visitor),
+
+            Expression::This |
+            Expression::Super |
+            Expression::Literal(_) => {},
+        }
+    }
+}
+
+impl Visitable for VariableDeclarator {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        self.name.visit(visitor);
+        self.value.visit(visitor);
+    }
+}
+
+impl Visitable for SwitchCase {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        self.test.visit(visitor);
+        self.consequent.visit(visitor);
+    }
+}
+
+impl Visitable for CatchClause {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        self.param.visit(visitor);
+        self.body.visit(visitor);
+    }
+}
+
+impl Visitable for Statement {
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.statement(self);
+
+        match *self {
+            Statement::Block {
+                ref body,
+            } |
+            Statement::Transparent {
+                ref body,
+            } => body.visit(visitor),
+
+            Statement::Labeled {
+                ref body,
+                ..
+            } => body.visit(visitor),
+
+            Statement::VariableDeclaration {
+                ref declarators,
+                ..
+            } => declarators.visit(visitor),
+
+            Statement::Expression {
+                ref value,
+            } |
+            Statement::Throw {
+                ref value,
+            } => value.visit(visitor),
+
+            Statement::Return {
+                ref value,
+            } => value.visit(visitor),
+
+            Statement::Function {
+                ref params,
+                ref body,
+                ..
+            } => {
+                params.visit(visitor);
+                body.visit(visitor);
+            },
+
+            Statement::If {
+                ref test,
+                ref consequent,
+                ref alternate,
+            } => {
+                test.visit(visitor);
+                consequent.visit(visitor);
+                alternate.visit(visitor);
+            },
+
+            Statement::While {
+                ref test,
+                ref body,
+            } |
+            Statement::DoWhile {
+                ref body,
+                ref test,
+            } => {
+                test.visit(visitor);
+                body.visit(visitor);
+            },
+
+            Statement::Switch {
+                ref discriminant,
+                ref cases,
+            } => {
+                discriminant.visit(visitor);
+                cases.visit(visitor);
+            },
+
+            Statement::For {
+                ref init,
+                ref test,
+                ref update,
+                ref body,
+            } => {
+                init.visit(visitor);
+                test.visit(visitor);
+                update.visit(visitor);
+                body.visit(visitor);
+            },
+
+            Statement::ForIn {
+                ref left,
+                ref right,
+                ref body,
+            } |
+            Statement::ForOf {
+                ref left,
+                ref right,
+                ref body,
+            } => {
+                left.visit(visitor);
+                right.visit(visitor);
+                body.visit(visitor);
+            },
+
+            Statement::Class {
+                extends,
+                ref body,
+                ..
+            } => {
+                if let Some(extends) = extends {
+                    visitor.reference(extends);
+                }
+
+                body.visit(visitor);
+            },
+
+            Statement::Try {
+                ref block,
+                ref handler,
+                ref finalizer,
+            } => {
+                block.visit(visitor);
+                handler.visit(visitor);
+                finalizer.visit(visitor);
+            },
+
+            Statement::ExportDeclaration {
+                ref kind,
+            } => match *kind {
+                ExportKind::Declaration(ref statement) |
+                ExportKind::Default(ref statement) => statement.visit(visitor),
+
+                ExportKind::Named {
+                    ref specifiers,
+                    source: None,
+                } => {
+                    for specifier in specifiers {
+                        visitor.reference(specifier.local);
+                    }
+                },
+
+                _ => {},
+            },
+
+            Statement::Break { .. } |
+            Statement::Continue { .. } |
+            Statement::ImportDeclaration { .. } => {},
+        }
+    }
+}
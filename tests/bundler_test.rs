@@ -0,0 +1,168 @@
+extern crate badger;
+
+use std::path::{ Path, PathBuf };
+
+use badger::bundler::{ self, ModuleGraph };
+use badger::error::BundleError;
+
+fn fixture(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bundle").join(path)
+}
+
+fn split_fixture(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/split").join(path)
+}
+
+fn resolve(specifier: &str) -> Option<PathBuf> {
+    bundler::resolve(specifier, &fixture("entry.js"))
+}
+
+#[test]
+fn resolve_relative_file() {
+    assert_eq!(resolve("./config.json"), Some(fixture("config.json")));
+    assert_eq!(resolve("./entry"), Some(fixture("entry.js")));
+}
+
+#[test]
+fn resolve_directory_index() {
+    assert_eq!(resolve("./lib"), Some(fixture("lib/index.js")));
+}
+
+#[test]
+fn resolve_node_modules() {
+    assert_eq!(resolve("left-pad"), Some(fixture("node_modules/left-pad/index.js")));
+    assert_eq!(resolve("counter"), Some(fixture("node_modules/counter/src/counter.js")));
+    assert_eq!(
+        bundler::resolve("left-pad", &fixture("lib/index.js")),
+        Some(fixture("node_modules/left-pad/index.js"))
+    );
+    assert_eq!(resolve("missing"), None);
+}
+
+#[test]
+fn build_module_graph() {
+    let graph = ModuleGraph::build(&fixture("entry.js")).unwrap();
+    let entry = &graph.modules[0];
+
+    assert_eq!(graph.modules.len(), 5);
+    assert!(entry.path.ends_with("entry.js"));
+    assert_eq!(entry.dependencies, vec![
+        ("./lib".to_string(), 1),
+        ("left-pad".to_string(), 2),
+        ("counter".to_string(), 3),
+        ("./config.json".to_string(), 4),
+    ]);
+    assert!(graph.modules[3].path.ends_with("counter/src/counter.js"));
+}
+
+#[test]
+fn bundle_wraps_modules_in_registry() {
+    let bundle = bundler::bundle(&fixture("lib/index.js"), true).unwrap();
+
+    assert!(bundle.chunks.is_empty());
+    assert!(bundle.code.starts_with("(function(modules,chunks){"));
+    assert!(bundle.code.ends_with(
+        "[function(require,module,exports){\
+         Object.defineProperty(exports,\"__esModule\",{value:!0}),\
+         Object.defineProperty(exports,\"name\",{enumerable:!0,get:function(){return name;}}),\
+         Object.defineProperty(exports,\"default\",{enumerable:!0,get:function(){return greet;}});\
+         var name='badger';function greet(who){return \"hello \"+who;}},{}]],{});"
+    ));
+}
+
+#[test]
+fn bundle_mangles_modules() {
+    let mut graph = ModuleGraph::build(&fixture("lib/index.js")).unwrap();
+
+    graph.mangle = true;
+
+    let bundle = graph.generate_code(true).unwrap();
+
+    assert!(bundle.code.contains("var name='badger';function greet(a){return \"hello \"+a;}"));
+}
+
+#[test]
+fn imported_bindings_stay_live_through_reexports() {
+    let bundle = bundler::bundle(&fixture("live/main.js"), true).unwrap();
+
+    // The entry reads `count` after `inc` updated it, instead of a copy
+    // taken when `./re` was required
+    assert!(bundle.code.contains(
+        "[function(require,module,exports){var _re1=require('./re');(0,_re1.inc)(),console.log(_re1.count);},{\"./re\":1}]"
+    ));
+    assert!(bundle.code.contains("get:function(){return _c1.count;}"));
+    assert!(bundle.code.contains("get:function(){return count;}"));
+}
+
+#[test]
+fn dynamic_imports_are_not_evaluated() {
+    let graph = ModuleGraph::build(&split_fixture("entry.js")).unwrap();
+    let entry = &graph.modules[0];
+
+    assert_eq!(entry.dynamic_imports, vec!["./about".to_string(), "./contact".to_string()]);
+    assert_eq!(entry.dependency("./about"), Some(2));
+    assert!(graph.modules[1].dynamic_imports.is_empty());
+}
+
+#[test]
+fn split_dynamic_imports_into_chunks() {
+    let bundle = bundler::bundle(&split_fixture("entry.js"), true).unwrap();
+    let chunk = |module: &str| {
+        bundle.chunks.iter().find(|chunk| chunk.code.contains(module)).unwrap()
+    };
+
+    // `about` and `contact` get a chunk each, `shared/form` used by both
+    // is moved into a common chunk, `shared/log` stays in the entry
+    assert_eq!(bundle.chunks.len(), 3);
+
+    let about = chunk("'about '");
+    let contact = chunk("'contact'");
+    let form = chunk("var fields=");
+
+    assert!(about.name.starts_with("chunk.") && about.name.ends_with(".js"));
+    assert!(about.code.starts_with("badgerChunk({2:[function(require,module,exports){"));
+    assert!(!about.code.contains("var fields="));
+    assert!(!bundle.code.contains("var fields="));
+    assert!(bundle.code.contains("function log(message)"));
+    assert!(bundle.code.contains("require.import('./about').then(_interopRequireWildcard)"));
+    assert!(bundle.code.ends_with(&format!(
+        "],{{2:[\"{}\",\"{}\"],3:[\"{}\",\"{}\"]}});",
+        about.name, form.name, contact.name, form.name
+    )));
+}
+
+#[test]
+fn chunk_names_are_deterministic() {
+    let first = bundler::bundle(&split_fixture("entry.js"), true).unwrap();
+    let second = bundler::bundle(&split_fixture("entry.js"), true).unwrap();
+    let names = |bundle: &bundler::Bundle| {
+        bundle.chunks.iter().map(|chunk| chunk.name.clone()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(names(&first), names(&second));
+    assert_eq!(first.code, second.code);
+}
+
+#[test]
+fn unresolved_module_error() {
+    match bundler::bundle(&fixture("missing.js"), true) {
+        Err(BundleError::Unresolved { specifier, path }) => {
+            assert_eq!(specifier, "./does-not-exist");
+            assert!(path.ends_with("missing.js"));
+        },
+        other => panic!("Expected unresolved module error, got {:?}", other),
+    }
+}
+
+#[test]
+fn dynamic_specifier_error() {
+    for &(file, start, end) in &[("dynamic.js", 66, 70), ("dynamic-import.js", 47, 62)] {
+        match bundler::bundle(&fixture(file), true) {
+            Err(BundleError::Dynamic { path, start: error_start, end: error_end }) => {
+                assert!(path.ends_with(file));
+                assert_eq!((error_start, error_end), (start, end));
+            },
+            other => panic!("Expected dynamic specifier error, got {:?}", other),
+        }
+    }
+}
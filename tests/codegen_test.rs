@@ -6,8 +6,24 @@ pub use badger::parser::parse;
 pub use badger::grammar::OperatorType::*;
 
 fn output_program(input_program: &str) -> String {
-    let mut ast = parser::parse(input_program.to_string());
-    transformer::transform(&mut ast, transformer::Settings::target_es5());
+    let mut ast = parser::parse(input_program.to_string()).unwrap();
+    transformer::transform(&mut ast, transformer::Settings::target_es5()).unwrap();
+    codegen::generate_code(ast, true)
+}
+
+fn output_commonjs(input_program: &str) -> String {
+    let mut ast = parser::parse(input_program.to_string()).unwrap();
+    let mut settings = transformer::Settings::target_es5();
+    settings.transform_commonjs = true;
+    transformer::transform(&mut ast, settings).unwrap();
+    codegen::generate_code(ast, true)
+}
+
+fn output_mangled(input_program: &str) -> String {
+    let mut ast = parser::parse(input_program.to_string()).unwrap();
+    let mut settings = transformer::Settings::target_es5();
+    settings.mangle = true;
+    transformer::transform(&mut ast, settings).unwrap();
     codegen::generate_code(ast, true)
 }
 
@@ -44,3 +60,757 @@ fn convert_let_to_var_in_block() {
     assert_compile!(program, expected);
 }
 
+#[test]
+fn rename_shadowing_block_bindings() {
+    let program = "let a = 1;
+    {
+      let a = 2;
+      log(a);
+    }
+    function f() {
+      if (a) { const log = 3; }
+      log(a);
+    }\n";
+
+    let expected = "var a=1;{var _a=2;log(_a);}function f(){if(a){var _log=3;}log(a);}";
+
+    assert_compile!(program, expected);
+}
+
+#[test]
+fn loop_closures_capture_each_iteration() {
+    let program = "for (let i = 0; i < 3; i++) {
+      fns.push(() => i);
+    }\n";
+
+    let expected = "var _loop1=function(i){fns.push(function(){return i;});};for(var i=0;i<3;i++){_loop1(i);}";
+
+    assert_compile!(program, expected);
+}
+
+#[test]
+fn loop_closures_take_jumps_after_call() {
+    let program = "function f() {
+      for (let i = 0; i < 3; i++) {
+        var last = i;
+        fns.push(() => i);
+        if (i) { break; }
+        if (last) { return i; }
+      }
+    }\n";
+
+    let expected = "function f(){var last;var _loop1=function(i){last=i;fns.push(function(){return i;});if(i){return \"break\";}if(last){return {v:i};}};for(var i=0;i<3;i++){var _ret2=_loop1(i);if(_ret2===\"break\")break;if(typeof _ret2===\"object\")return _ret2.v;}}";
+
+    assert_compile!(program, expected);
+}
+
+#[test]
+fn loop_closures_copy_back_assigned_bindings() {
+    let program = "for (let i = 0; i < 3; i++) {
+      fns.push(() => i);
+      i++;
+    }\n";
+
+    let expected = "var _i1;var _loop2=function(i){fns.push(function(){return i;});i++;_i1=i;};for(var i=0;i<3;i++){_loop2(i);i=_i1;}";
+
+    assert_compile!(program, expected);
+}
+
+#[test]
+fn temporaries_are_declared_in_their_function() {
+    assert_compile!(
+        "function g() { ({ a, b } = this.x); } var k = o => o.p.m(...o.q); ({ c } = d());",
+        "var _ref3;function g(){var _ref1;_ref1=this.x,a=_ref1.a,b=_ref1.b,_ref1;}\
+         var k=function(o){var _ref2;return (_ref2=o.p).m.apply(_ref2,o.q);};_ref3=d(),c=_ref3.c,_ref3;"
+    );
+    assert_compile!(
+        "function f() { for (let i = 0; i < 3; i++) { fns.push(() => i); i++; } }",
+        "function f(){var _i1;var _loop2=function(i){fns.push(function(){return i;});i++;_i1=i;};\
+         for(var i=0;i<3;i++){_loop2(i);i=_i1;}}"
+    );
+}
+
+#[test]
+fn loop_closures_keep_arguments_of_function() {
+    let program = "function f() {
+      for (let i = 0; i < 3; i++) {
+        fns.push(() => i);
+        g(this, arguments, function () { return arguments; });
+      }
+    }\n";
+
+    let expected = "function f(){var _arguments1=arguments;var _loop2=function(i){fns.push(function(){return i;});g(this,_arguments1,function(){return arguments;});}.bind(this);for(var i=0;i<3;i++){_loop2(i);}}";
+
+    assert_compile!(program, expected);
+}
+
+#[test]
+fn const_reassignment_is_an_error() {
+    let mut ast = parser::parse("const a = 1;\nfunction f() { a += 1; }\n".to_string()).unwrap();
+
+    assert_eq!(transformer::transform(&mut ast, transformer::Settings::target_es5()), Err(error::TransformError::ConstAssignment {
+        name: "a".to_string(),
+        start: 28,
+        end: 29,
+    }));
+}
+
+#[test]
+fn import_reassignment_is_an_error() {
+    let mut ast = parser::parse("import { a } from \"m\";\na++;\n".to_string()).unwrap();
+    let mut settings = transformer::Settings::target_es5();
+    settings.transform_commonjs = true;
+
+    assert_eq!(transformer::transform(&mut ast, settings), Err(error::TransformError::ImportAssignment {
+        name: "a".to_string(),
+        start: 23,
+        end: 24,
+    }));
+}
+
+#[test]
+fn super_without_parent_is_an_error() {
+    let mut ast = parser::parse("class A { constructor() { super.x = 1; } }".to_string()).unwrap();
+
+    assert_eq!(transformer::transform(&mut ast, transformer::Settings::target_es5()), Err(error::TransformError::SuperWithoutParent {
+        start: 26,
+        end: 31,
+    }));
+}
+
+#[test]
+fn mangle_function_bindings_by_usage() {
+    let program = "var total = 1;
+    function add(first, second) {
+      var rest = first;
+      return second + second + total + rest;
+    }\n";
+
+    assert_eq!(output_mangled(program), "var total=1;function add(b,a){var c=b;return a+a+total+c;}");
+}
+
+#[test]
+fn mangle_around_outer_and_global_names() {
+    let program = "function outer(value) {
+      return function inner(other) {
+        return value + other + a + inner;
+      };
+    }\n";
+
+    assert_eq!(output_mangled(program), "function outer(b){return function c(d){return b+d+a+c;};}");
+}
+
+#[test]
+fn mangle_keeps_properties_and_eval_scopes() {
+    let program = "function f(value) {
+      var g = function (count) { return { count, value: count }; };
+      var result = g(value);
+      return eval(\"value\") + result.count;
+    }\n";
+
+    assert_eq!(output_mangled(program), "function f(value){var g=function(a){return {count:a,value:a};};var result=g(value);return eval(\"value\")+result.count;}");
+}
+
+#[test]
+fn source_map_mappings() {
+    let ast = parser::parse("foo;\n  bar(foo);\n".to_string()).unwrap();
+    let (code, map) = codegen::generate_code_with_source_map(ast, true, "input.js");
+
+    assert_eq!(code, "foo;bar(foo);");
+    assert_eq!(map.names, vec!["foo".to_string(), "bar".to_string()]);
+    assert_eq!(map.mappings, "AAAAA,IACEC,IAAID");
+}
+
+#[test]
+fn source_map_columns_count_utf16_units() {
+    let ast = parser::parse("var s = \"üüüü\"; foo(s);\n\"😀\"; bar;\n".to_string()).unwrap();
+    let (code, map) = codegen::generate_code_with_source_map(ast, true, "input.js");
+
+    assert_eq!(code, "var s=\"üüüü\";foo(s);\"😀\";bar;");
+    // `foo` is at generated column 13 and source column 16, and the emoji
+    // counts as a surrogate pair, putting `bar` 5 and 6 columns after it
+    assert_eq!(map.mappings, "AAAA,MAAQ,OAAQA,IAAIC,GACpB,KAAMC");
+}
+
+#[test]
+fn source_map_json() {
+    let ast = parser::parse("let a = 1;".to_string()).unwrap();
+    let (_, mut map) = codegen::generate_code_with_source_map(ast, true, "input.js");
+
+    map.file = Some("output.js".to_string());
+
+    assert_eq!(map.to_json(), concat!(
+        r#"{"file":"output.js","mappings":"AAAA,MAAQ","names":[],"#,
+        r#""sources":["input.js"],"sourcesContent":["let a = 1;"],"version":3}"#
+    ));
+}
+
+#[test]
+fn regex_literal() {
+    assert_compile!("var re = /ab+c/gi, x = a / b;", "var re=/ab+c/gi,x=a/b;");
+}
+
+#[test]
+fn keep_parens_on_right_side_of_binary() {
+    assert_compile!("a - (b - c);", "a-(b-c);");
+}
+
+#[test]
+fn template_literal_to_concatenation() {
+    assert_compile!("var a = `foo ${bar}${baz ? 1 : 2}\n\"`;", r#"var a="foo "+bar+(baz?1:2)+"\n\"";"#);
+}
+
+#[test]
+fn template_literal_starting_with_expression() {
+    assert_compile!("var a = `${b}`;", r#"var a=""+b;"#);
+}
+
+#[test]
+fn template_literal_escapes_to_es5() {
+    assert_compile!(r"var a = `\u{1F600}\x41\u0042\0\`\${}\'`;", r#"var a="😀AB\x00`${}'";"#);
+    assert_compile!("var a = `\\uD83D\\u{2028}\\\nb`;", r#"var a="\ud83d\u2028b";"#);
+}
+
+#[test]
+fn tagged_template_with_invalid_escape() {
+    assert_compile!(
+        r"tag`\unicode${a}\x4`;",
+        concat!(
+            r#"var _templateObject1=Object.freeze(Object.defineProperty([void 0,void 0],"raw","#,
+            r#"{value:Object.freeze(["\\unicode","\\x4"])}));tag(_templateObject1,a);"#
+        )
+    );
+}
+
+#[test]
+fn tagged_template_to_call() {
+    assert_compile!(
+        r"foo`a\n${b}`;",
+        concat!(
+            r#"var _templateObject1=Object.freeze(Object.defineProperty(["a\n",""],"raw","#,
+            r#"{value:Object.freeze(["a\\n",""])}));foo(_templateObject1,b);"#
+        )
+    );
+}
+
+#[test]
+fn destructuring_declaration() {
+    assert_compile!(
+        "var [a, , b = 1] = foo(), { c, d: { e } } = bar;",
+        "var _ref1=foo(),a=_ref1[0],_ref2=_ref1[2],b=_ref2===void 0?1:_ref2,c=bar.c,e=bar.d.e;"
+    );
+}
+
+#[test]
+fn destructuring_parameters() {
+    assert_compile!(
+        "function f({ a }, [b, ...c]) {}",
+        "function f(_ref1,_ref2){var a=_ref1.a,b=_ref2[0],c=_ref2.slice(1);}"
+    );
+}
+
+#[test]
+fn destructuring_assignment() {
+    assert_compile!("[a, b] = [b, a];", "var _ref1;_ref1=[b,a],a=_ref1[0],b=_ref1[1],_ref1;");
+}
+
+#[test]
+fn destructuring_for_of_head() {
+    assert_compile!(
+        "for (const [k, v] of entries) log(k, v);",
+        "for(var _ref1 of entries){var k=_ref1[0],v=_ref1[1];log(k,v);}"
+    );
+}
+
+#[test]
+fn default_and_rest_parameters() {
+    assert_compile!(
+        "function f(a, b = 1, ...c) {}",
+        "function f(a,b){if(b===void 0)b=1;var c=Array.prototype.slice.call(arguments,2);}"
+    );
+}
+
+#[test]
+fn default_parameter_with_pattern() {
+    assert_compile!(
+        "function f({ a } = {}) {}",
+        "function f(_ref1){if(_ref1===void 0)_ref1={};var a=_ref1.a;}"
+    );
+}
+
+#[test]
+fn spread_call_to_apply() {
+    assert_compile!("f(...args);f(a, ...b);", "f.apply(void 0,args);f.apply(void 0,[].concat([a],b));");
+}
+
+#[test]
+fn spread_method_call_keeps_receiver() {
+    assert_compile!(
+        "foo.bar(...args);foo().bar(1, ...args);",
+        "var _ref1;foo.bar.apply(foo,args);(_ref1=foo()).bar.apply(_ref1,[].concat([1],args));"
+    );
+}
+
+#[test]
+fn spread_new_to_bind() {
+    assert_compile!(
+        "new F(...a);new a.B(1, ...args);",
+        "new (Function.prototype.bind.apply(F,[null].concat(a)))();new (Function.prototype.bind.apply(a.B,[null].concat([].concat([1],args))))();"
+    );
+}
+
+#[test]
+fn spread_array_to_concat() {
+    assert_compile!("var a = [...b, 1, 2, ...c];", "var a=[].concat(b,[1,2],c);");
+}
+
+#[test]
+fn keep_parens_around_member_object() {
+    assert_compile!("(a + b).c;", "(a+b).c;");
+}
+
+#[test]
+fn try_catch_finally() {
+    assert_compile!(
+        "try { a(); } catch (e) { b(e); } finally { c(); }",
+        "try{a();}catch(e){b(e);}finally{c();}"
+    );
+}
+
+#[test]
+fn optional_catch_binding() {
+    assert_compile!("try { a(); } catch { b(); }", "try{a();}catch(_e1){b();}");
+    assert_eq!(output_untransformed("try { a(); } catch { b(); }"), "try{a();}catch{b();}");
+}
+
+#[test]
+fn switch_do_while_and_continue() {
+    assert_compile!(
+        "switch (a) { case 1: foo(); break; default: bar(); }\ndo { continue } while (x)",
+        "switch(a){case 1:foo();break;default:bar();}do{continue;}while(x);"
+    );
+}
+
+#[test]
+fn dynamic_import() {
+    assert_compile!("import('./foo').then(function (foo) { return foo.default })", "import('./foo').then(function(foo){return foo.default;});");
+}
+
+#[test]
+fn import_and_export_declarations() {
+    assert_compile!(
+        "import a, { b as c } from 'a';import * as d from 'd';export const e = 1;export { c as f };export * from 'g';",
+        "import a,{b as c} from 'a';import *as d from 'd';export var e=1;export {c as f};export *from'g';"
+    );
+}
+
+#[test]
+fn commonjs_imports() {
+    assert_eq!(
+        output_commonjs("import a, { b as c } from './lib/foo.js';import * as d from 'd';import 'e';c(a, d);"),
+        "function _interopRequireDefault(obj){return obj&&obj.__esModule?obj:{default:obj};}\
+         function _interopRequireWildcard(obj){if(obj&&obj.__esModule)return obj;var ns={};for(var key in obj)ns[key]=obj[key];ns.default=obj;return ns;}\
+         var _foo1=_interopRequireDefault(require('./lib/foo.js'));\
+         var d=_interopRequireWildcard(require('d'));require('e');(0,_foo1.b)(_foo1.default,d);"
+    );
+}
+
+#[test]
+fn commonjs_imports_are_live() {
+    assert_eq!(
+        output_commonjs("import { count, inc, Base } from './counter';inc();f({ count });export { count as c };class A extends Base {}"),
+        "function _inherits(subClass,superClass){subClass.prototype=Object.create(superClass.prototype,{constructor:{value:subClass,writable:!0,configurable:!0}});\
+         if(Object.setPrototypeOf)Object.setPrototypeOf(subClass,superClass);else subClass.__proto__=superClass;}\
+         Object.defineProperty(exports,\"__esModule\",{value:!0});\
+         Object.defineProperty(exports,\"c\",{enumerable:!0,get:function(){return _counter1.count;}});\
+         var _counter1=require('./counter');(0,_counter1.inc)();f({count:_counter1.count});\
+         var _Base2=_counter1.Base;function A(){_Base2.apply(this,arguments);}_inherits(A,_Base2);"
+    );
+}
+
+#[test]
+fn export_default_anonymous_class() {
+    assert_eq!(output_untransformed("export default class {}"), "export default class{}");
+    assert_eq!(
+        output_commonjs("export default class { static a = 1; }"),
+        "Object.defineProperty(exports,\"__esModule\",{value:!0});\
+         Object.defineProperty(exports,\"default\",{enumerable:!0,get:function(){return _default1;}});\
+         function _default1(){}_default1.a=1;"
+    );
+}
+
+#[test]
+fn commonjs_exports() {
+    assert_eq!(
+        output_commonjs("export const a = 1;export function b() {}export { a as c };export default a + 1;"),
+        "Object.defineProperty(exports,\"__esModule\",{value:!0});\
+         Object.defineProperty(exports,\"a\",{enumerable:!0,get:function(){return a;}});\
+         Object.defineProperty(exports,\"b\",{enumerable:!0,get:function(){return b;}});\
+         Object.defineProperty(exports,\"c\",{enumerable:!0,get:function(){return a;}});\
+         var a=1;function b(){}exports.default=a+1;"
+    );
+}
+
+#[test]
+fn commonjs_reexports_are_live() {
+    assert_eq!(
+        output_commonjs("export { default as a, b } from 'foo';export * from 'bar';"),
+        "function _interopRequireDefault(obj){return obj&&obj.__esModule?obj:{default:obj};}\
+         Object.defineProperty(exports,\"__esModule\",{value:!0});\
+         Object.defineProperty(exports,\"a\",{enumerable:!0,get:function(){return _foo1.default;}});\
+         Object.defineProperty(exports,\"b\",{enumerable:!0,get:function(){return _foo1.b;}});\
+         var _foo1=_interopRequireDefault(require('foo'));var _bar2=require('bar');\
+         Object.keys(_bar2).forEach(function(key){if(key===\"default\"||key===\"__esModule\")return;\
+         Object.defineProperty(exports,key,{enumerable:!0,get:function(){return _bar2[key];}});});"
+    );
+}
+
+#[test]
+fn commonjs_dynamic_import() {
+    assert_eq!(
+        output_commonjs("import('./foo').then(foo => foo.default);"),
+        "function _interopRequireWildcard(obj){if(obj&&obj.__esModule)return obj;var ns={};for(var key in obj)ns[key]=obj[key];ns.default=obj;return ns;}\
+         Promise.resolve().then(function(){return _interopRequireWildcard(require('./foo'));}).then(function(foo){return foo.default;});"
+    );
+}
+
+#[test]
+fn class_methods_are_not_enumerable() {
+    assert_compile!(
+        "class Foo { constructor(a) { this.a = a; } bar() { return this.a; } static baz() {} }",
+        "function Foo(a){this.a=a;}\
+         Object.defineProperty(Foo.prototype,\"bar\",{value:function bar(){return this.a;},writable:!0,configurable:!0});\
+         Object.defineProperty(Foo,\"baz\",{value:function baz(){},writable:!0,configurable:!0});"
+    );
+}
+
+#[test]
+fn class_extends_and_super() {
+    assert_compile!(
+        "class Foo extends Bar { constructor(a) { super(a); } bar(...b) { return super.bar(...b); } static baz() { super.baz(1); } }",
+        "function _inherits(subClass,superClass){subClass.prototype=Object.create(superClass.prototype,{constructor:{value:subClass,writable:!0,configurable:!0}});\
+         if(Object.setPrototypeOf)Object.setPrototypeOf(subClass,superClass);else subClass.__proto__=superClass;}\
+         function Foo(a){Bar.call(this,a);}_inherits(Foo,Bar);\
+         Object.defineProperty(Foo.prototype,\"bar\",{value:function bar(){var b=Array.prototype.slice.call(arguments,0);return Bar.prototype.bar.apply(this,b);},writable:!0,configurable:!0});\
+         Object.defineProperty(Foo,\"baz\",{value:function baz(){Bar.baz.call(this,1);},writable:!0,configurable:!0});"
+    );
+}
+
+#[test]
+fn class_default_constructor_calls_super() {
+    assert_compile!(
+        "class Foo extends Bar { a = 1; }",
+        "function _inherits(subClass,superClass){subClass.prototype=Object.create(superClass.prototype,{constructor:{value:subClass,writable:!0,configurable:!0}});\
+         if(Object.setPrototypeOf)Object.setPrototypeOf(subClass,superClass);else subClass.__proto__=superClass;}\
+         function Foo(){Bar.apply(this,arguments);this.a=1;}_inherits(Foo,Bar);"
+    );
+}
+
+#[test]
+fn class_properties_follow_super_call() {
+    let mut ast = parser::parse("class Foo extends Bar { a = 1; constructor() { super(); } }".to_string()).unwrap();
+    transformer::transform(&mut ast, transformer::Settings::target_es2015()).unwrap();
+
+    assert_eq!(
+        codegen::generate_code(ast, true),
+        "class Foo extends Bar{constructor(){super();this.a=1;}}"
+    );
+}
+
+#[test]
+fn class_method_name_does_not_shadow() {
+    assert_compile!(
+        "class Foo { log(a) { log(a); } }",
+        "function Foo(){}Object.defineProperty(Foo.prototype,\"log\",{value:function(a){log(a);},writable:!0,configurable:!0});"
+    );
+}
+
+#[test]
+fn new_with_arguments() {
+    assert_compile!("new Foo(1).bar(new this(2))", "new Foo(1).bar(new this(2));");
+}
+
+#[test]
+fn getters_and_setters() {
+    let mut ast = parser::parse("class Foo { get a() { return 1; } static set a(b) {} }var c = { get a() { return 1; }, set a(b) {} };".to_string()).unwrap();
+    transformer::transform(&mut ast, transformer::Settings::target_es2015()).unwrap();
+
+    assert_eq!(
+        codegen::generate_code(ast, true),
+        "class Foo{get a(){return 1;}static set a(b){}}var c={get a(){return 1;},set a(b){}};"
+    );
+}
+
+#[test]
+fn object_accessors_are_lowered() {
+    assert_compile!(
+        "({ a: 1, get b() { return this.a; }, set b(c) { this.a = c; } });",
+        "Object.defineProperty({a:1},\"b\",{get:function(){return this.a;},set:function(c){this.a=c;},enumerable:!0,configurable:!0});"
+    );
+}
+
+#[test]
+fn object_accessors_keep_source_order() {
+    assert_compile!(
+        "({ get a() { return 1; }, a: 2, [b]: 3 });",
+        "Object.defineProperty(Object.defineProperty(Object.defineProperty({},\"a\",{get:function(){return 1;},enumerable:!0,configurable:!0}),\
+         \"a\",{value:2,writable:!0,enumerable:!0,configurable:!0}),b,{value:3,writable:!0,enumerable:!0,configurable:!0});"
+    );
+}
+
+#[test]
+fn class_accessors_are_lowered() {
+    assert_compile!(
+        "class Foo extends Bar { get a() { return super.a; } set a(b) {} static get c() {} }",
+        "function _get(object,property,receiver){var descriptor=Object.getOwnPropertyDescriptor(object,property);\
+         if(descriptor===void 0){var parent=Object.getPrototypeOf(object);return parent===null?void 0:_get(parent,property,receiver);}\
+         return descriptor.get?descriptor.get.call(receiver):descriptor.value;}\
+         function _inherits(subClass,superClass){subClass.prototype=Object.create(superClass.prototype,{constructor:{value:subClass,writable:!0,configurable:!0}});\
+         if(Object.setPrototypeOf)Object.setPrototypeOf(subClass,superClass);else subClass.__proto__=superClass;}\
+         function Foo(){Bar.apply(this,arguments);}_inherits(Foo,Bar);\
+         Object.defineProperty(Foo.prototype,\"a\",{get:function(){return _get(Bar.prototype,\"a\",this);},set:function(b){},configurable:!0});\
+         Object.defineProperty(Foo,\"c\",{get:function(){},configurable:!0});"
+    );
+}
+
+fn output_untransformed(input_program: &str) -> String {
+    let ast = parser::parse(input_program.to_string()).unwrap();
+    codegen::generate_code(ast, true)
+}
+
+const OPERATORS: &[OperatorType] = &[
+    FatArrow, Accessor, New, Increment, Decrement, LogicalNot, BitwiseNot,
+    Typeof, Void, Delete, Multiplication, Division, Remainder, Exponent,
+    Addition, Substraction, BitShiftLeft, BitShiftRight, UBitShiftRight,
+    Lesser, LesserEquals, Greater, GreaterEquals, Instanceof, In,
+    StrictEquality, StrictInequality, Equality, Inequality, BitwiseAnd,
+    BitwiseXor, BitwiseOr, LogicalAnd, LogicalOr, Conditional, Assign,
+    AddAssign, SubstractAssign, ExponentAssign, MultiplyAssign, DivideAssign,
+    RemainderAssign, BSLAssign, BSRAssign, UBSRAssign, BitAndAssign,
+    BitXorAssign, BitOrAssign, Spread,
+];
+
+fn boxed(expression: &Loc<Expression>) -> Box<Loc<Expression>> {
+    Box::new(expression.clone())
+}
+
+/// Every expression made of a single operator, or a member access, call
+/// and such, applied to the operands `x`, `y` and `z`.
+fn operations(x: &Loc<Expression>, y: &Loc<Expression>, z: &Loc<Expression>) -> Vec<Expression> {
+    let mut operations = Vec::new();
+
+    for &operator in OPERATORS {
+        match operator {
+            FatArrow => operations.push(Expression::ArrowFunction {
+                params: Vec::new(),
+                body: Box::new(x.clone().into()),
+            }),
+
+            Accessor => operations.push(Expression::Member {
+                object: boxed(x),
+                property: "p".into(),
+            }),
+
+            Conditional => operations.push(Expression::Conditional {
+                test: boxed(x),
+                consequent: boxed(y),
+                alternate: boxed(z),
+            }),
+
+            Spread => operations.push(Expression::Call {
+                callee: boxed(x),
+                arguments: vec![Expression::Prefix {
+                    operator: Spread,
+                    operand: boxed(y),
+                }.into()],
+            }),
+
+            // `new x(y)` is a call of `new x`
+            New => operations.push(Expression::Call {
+                callee: Box::new(Expression::Prefix {
+                    operator: New,
+                    operand: boxed(x),
+                }.into()),
+                arguments: vec![y.clone()],
+            }),
+
+            _ => {},
+        }
+
+        if operator.prefix() && operator != Spread {
+            operations.push(Expression::Prefix {
+                operator: operator,
+                operand: boxed(x),
+            });
+        }
+
+        if operator == Increment || operator == Decrement {
+            operations.push(Expression::Postfix {
+                operator: operator,
+                operand: boxed(x),
+            });
+        }
+
+        if operator.infix() && operator != FatArrow && operator != Accessor && operator != Conditional {
+            operations.push(Expression::Binary {
+                left: boxed(x),
+                operator: operator,
+                right: boxed(y),
+            });
+        }
+    }
+
+    operations.push(Expression::ComputedMember {
+        object: boxed(x),
+        property: boxed(y),
+    });
+    operations.push(Expression::Call {
+        callee: boxed(x),
+        arguments: vec![y.clone(), z.clone()],
+    });
+    operations.push(Expression::TaggedTemplate {
+        tag: boxed(x),
+        quasis: vec!["".into()],
+        expressions: Vec::new(),
+    });
+    operations.push(Expression::Sequence(vec![x.clone(), y.clone()]));
+
+    operations
+}
+
+/// Checks that the code generated for the `expression` is parsed back into
+/// the same tree.
+fn assert_round_trip(expression: Expression, minify: bool) {
+    let statement: Loc<Statement> = Statement::Expression {
+        value: expression.into(),
+    }.into();
+    let code = codegen::generate_code(Program::new(String::new(), vec![statement.clone()]), minify);
+    let program = parser::parse(code.clone()).unwrap_or_else(|error| {
+        panic!("Couldn't parse `{}` back: {:?}", code, error)
+    });
+
+    assert_eq!(program.body, vec![statement], "`{}` was read back as another tree", code);
+}
+
+#[test]
+fn round_trip_operator_precedence() {
+    let leaves: Vec<Loc<Expression>> = vec![
+        Expression::Identifier("a".into()).into(),
+        Expression::Identifier("b".into()).into(),
+        Expression::Identifier("c".into()).into(),
+    ];
+
+    for inner in operations(&leaves[0], &leaves[1], &leaves[2]) {
+        let inner: Loc<Expression> = inner.into();
+
+        for position in 0..leaves.len() {
+            let mut operands = leaves.clone();
+
+            operands[position] = inner.clone();
+
+            for outer in operations(&operands[0], &operands[1], &operands[2]) {
+                assert_round_trip(outer.clone(), true);
+                assert_round_trip(outer, false);
+            }
+        }
+    }
+}
+
+#[test]
+fn round_trip_literal_operands() {
+    let literals: Vec<Expression> = vec![
+        Expression::Literal(LiteralFloat("1".into())),
+        Expression::Literal(LiteralFloat("1.5".into())),
+        Expression::Literal(LiteralTrue),
+        Expression::Literal(LiteralUndefined),
+        Expression::Object(Vec::new()),
+        Expression::Function {
+            name: None,
+            params: Vec::new(),
+            body: Vec::new(),
+        },
+    ];
+    let leaf: Loc<Expression> = Expression::Identifier("a".into()).into();
+
+    for literal in literals {
+        let literal: Loc<Expression> = literal.into();
+
+        // Minified `true` is read back as `!0`, and literals can't be
+        // assigned to
+        for operation in operations(&literal, &leaf, &leaf) {
+            match operation {
+                Expression::Binary {
+                    operator,
+                    ..
+                } if operator.assignment() => continue,
+                _ => assert_round_trip(operation, false),
+            }
+        }
+    }
+}
+
+/// Checks that the code generated for the statements of the `source` is
+/// parsed back into the same tree.
+fn assert_source_round_trip(source: &str) {
+    let original = parser::parse(source.to_string()).unwrap();
+
+    for &minify in &[true, false] {
+        let code = codegen::generate_code(Program::new(String::new(), original.body.clone()), minify);
+        let program = parser::parse(code.clone()).unwrap_or_else(|error| {
+            panic!("Couldn't parse `{}` back: {:?}", code, error)
+        });
+
+        assert_eq!(program.body, original.body, "`{}` was read back as another tree", code);
+    }
+}
+
+#[test]
+fn round_trip_empty_statement_bodies() {
+    assert_source_round_trip("do ; while (a);");
+    assert_source_round_trip("function f() { for (var i = 0; i < 3; i++) ; }");
+    assert_source_round_trip("if (a) ; else ;");
+    assert_source_round_trip("while (a) ; for (b in c) ; for (d of e) ; f: ;");
+}
+
+#[test]
+fn round_trip_in_inside_for_initializer() {
+    assert_source_round_trip("for (var i = (a in b); i; ) ;");
+    assert_source_round_trip("for (i = (a in b) ? c : d, [e in f]; i; ) ;");
+    assert_source_round_trip("for (var g = function () { if (a in b) return; }; ; ) ;");
+
+    // The parser reads `in` back either way, the parens are required by
+    // JavaScript engines
+    assert_eq!(output_untransformed("for (var i = (a in b); i; ) ;"), "for(var i=(a in b);i;);");
+    assert_eq!(output_untransformed("for (i = (a in b) ? c : d; ; ) ;"), "for(i=(a in b)?c:d;;);");
+}
+
+#[test]
+fn minimal_parens() {
+    assert_eq!(output_untransformed("(a * b) + (c * d);"), "a*b+c*d;");
+    assert_eq!(output_untransformed("(a - b) - (c - d);"), "a-b-(c-d);");
+    assert_eq!(output_untransformed("a ** (b ** c); (a ** b) ** c;"), "a**b**c;(a**b)**c;");
+    assert_eq!(output_untransformed("(-a) ** b; -(a ** b);"), "(-a)**b;-(a**b);");
+    assert_eq!(output_untransformed("a = (b, c); (a, b);"), "a=(b,c);a,b;");
+    assert_eq!(output_untransformed("(a = b) ? (c, d) : e = f;"), "(a=b)?(c,d):e=f;");
+}
+
+#[test]
+fn parens_for_calls_and_new() {
+    assert_eq!(output_untransformed("a + b.c(d); !f(); n ? f(x) : y;"), "a+b.c(d);!f();n?f(x):y;");
+    assert_eq!(output_untransformed("new (a())(); new (a().b); (new a).b; new a.b();"), "new (a())();new (a().b);(new a).b;new a.b();");
+    assert_eq!(output_untransformed("(1).toString(); (1.5).toString();"), "1 .toString();1.5.toString();");
+    assert_eq!(output_untransformed("(a || b)++;"), "(a||b)++;");
+}
+
+#[test]
+fn space_between_merging_tokens() {
+    assert_eq!(output_untransformed("a - -b; a + +b; a - --b; - -a;"), "a- -b;a+ +b;a- --b;- -a;");
+    assert_eq!(output_untransformed("a instanceof b; a in b;"), "a instanceof b;a in b;");
+    assert_eq!(output_untransformed("a / /b/g;"), "a/ /b/g;");
+}
+
+#[test]
+fn parens_at_statement_start() {
+    assert_eq!(output_untransformed("({}).a; (function () {})();"), "({}.a);(function(){}());");
+    assert_eq!(output_untransformed("f = () => ({}); g = () => ({}).a;"), "f=()=>({});g=()=>({}.a);");
+}
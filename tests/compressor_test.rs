@@ -0,0 +1,178 @@
+extern crate badger;
+
+use badger::{ codegen, compressor, parser, transformer };
+use badger::compressor::Define;
+use badger::error::DefineError;
+
+fn compress_with(source: &str, defines: &[Define]) -> String {
+    let mut program = parser::parse(source.to_string()).unwrap();
+
+    transformer::transform(&mut program, transformer::Settings::target_es5()).unwrap();
+    compressor::compress(&mut program, defines);
+
+    codegen::generate_code(program, true)
+}
+
+fn compress(source: &str) -> String {
+    compress_with(source, &[])
+}
+
+#[test]
+fn fold_arithmetic() {
+    assert_eq!(compress("x = 1 + 2 * 3;"), "x=7;");
+    assert_eq!(compress("x = 0.1 + 0.2;"), "x=0.30000000000000004;");
+    assert_eq!(compress("x = 7 % -3;"), "x=1;");
+    assert_eq!(compress("x = 5 & 3 | 8;"), "x=9;");
+    assert_eq!(compress("x = -1 >>> 0;"), "x=4294967295;");
+    assert_eq!(compress("x = ~-1;"), "x=0;");
+}
+
+#[test]
+fn keep_negative_zero_and_nan() {
+    assert_eq!(compress("x = 0 * -1;"), "x=-0;");
+    assert_eq!(compress("x = 0 / 0;"), "x=0/0;");
+    assert_eq!(compress("x = 1 / 0;"), "x=1/0;");
+    assert_eq!(compress("x = \"a\" * 1;"), "x=\"a\"*1;");
+    assert_eq!(compress("x = 0 === -0;"), "x=!0;");
+}
+
+#[test]
+fn fold_strings() {
+    assert_eq!(compress("x = \"a\" + 'b';"), "x=\"ab\";");
+    assert_eq!(compress("x = \"a\" + 1 + 2;"), "x=\"a12\";");
+    assert_eq!(compress("x = 1 + 2 + \"a\";"), "x=\"3a\";");
+    assert_eq!(compress("x = y + \"a\" + \"b\";"), "x=y+\"ab\";");
+    assert_eq!(compress("x = \"q\" + 1 / 10000000;"), "x=\"q1e-7\";");
+    assert_eq!(compress("x = `${1}\\n${null}`;"), "x=\"1\\nnull\";");
+}
+
+#[test]
+fn fold_typeof_and_comparisons() {
+    assert_eq!(compress("x = typeof null;"), "x=\"object\";");
+    assert_eq!(compress("x = typeof function () {};"), "x=\"function\";");
+    assert_eq!(compress("x = typeof y;"), "x=typeof y;");
+    assert_eq!(compress("x = 1 == \"1\";"), "x=!0;");
+    assert_eq!(compress("x = null == 0;"), "x=!1;");
+    assert_eq!(compress("x = \"10\" < \"9\";"), "x=!0;");
+    assert_eq!(compress("x = 10 < \"9\";"), "x=!1;");
+}
+
+#[test]
+fn simplify_conditions() {
+    assert_eq!(compress("if (!!y) { z(); }"), "y&&z();");
+    assert_eq!(compress("x = !!!y;"), "x=!y;");
+    assert_eq!(compress("x = !y ? 1 : 2;"), "x=y?2:1;");
+    assert_eq!(compress("x = 1 ? y : z;"), "x=y;");
+    assert_eq!(compress("x = \"\" ? y : z;"), "x=z;");
+    assert_eq!(compress("x = 0 || y;"), "x=y;");
+    assert_eq!(compress("x = 1 && y;"), "x=y;");
+    assert_eq!(compress("x = 0 && y;"), "x=0;");
+}
+
+#[test]
+fn keep_this_of_folded_callees() {
+    assert_eq!(compress("(1 ? a.b : c)();"), "(!0?a.b:c)();");
+    assert_eq!(compress("(1 ? a : c)();"), "a();");
+}
+
+#[test]
+fn remove_unreachable_statements() {
+    assert_eq!(
+        compress("function f() { return h; g(); var a = 2; function h() { return a; } }"),
+        "function f(){return h;function h(){return a;}var a;}"
+    );
+    assert_eq!(compress("while (x) { break; y(); }"), "while(x)break;");
+    assert_eq!(compress("function f() { throw 1; { var a; } }"), "function f(){throw 1;}");
+}
+
+#[test]
+fn remove_branches_with_constant_tests() {
+    assert_eq!(compress("if (1) { a(); } else { b(); }"), "a();");
+    assert_eq!(compress("if (\"\") { a(); } else { b(); }"), "b();");
+    assert_eq!(compress("if (0) { a(); }"), "");
+    assert_eq!(compress("if (0) { var a = 1; } log(a);"), "var a;log(a);");
+    assert_eq!(compress("while (false) { a(); }"), "");
+}
+
+#[test]
+fn remove_unused_declarations() {
+    assert_eq!(
+        compress("function f() { var a = 1, b = g(), c = 2; function h() {} function i() { return j(); } function j() {} return c; }"),
+        "function f(){var b=g(),c=2;return c;}"
+    );
+
+    // Functions declared in blocks can be used after the block
+    assert_eq!(
+        compress("function f() { if (true) { function g() { return 42; } } return g(); }"),
+        "function f(){{function g(){return 42;}}return g();}"
+    );
+
+    // Globals and functions using `eval` keep their declarations
+    assert_eq!(compress("var a = 1; function f() {}"), "var a=1;function f(){}");
+    assert_eq!(compress("function f() { var a = 1; eval(\"a\"); }"), "function f(){var a=1;eval(\"a\");}");
+}
+
+#[test]
+fn join_expression_statements() {
+    assert_eq!(compress("a(); b = 1; c();"), "a(),b=1,c();");
+    assert_eq!(compress("a(); (b(), c()); d();"), "a(),b(),c(),d();");
+    assert_eq!(compress("a(); var b; c();"), "a();var b;c();");
+    assert_eq!(compress("function f() { \"use strict\"; a(); b(); }"), "function f(){\"use strict\";a(),b();}");
+}
+
+#[test]
+fn turn_branches_into_expressions() {
+    assert_eq!(compress("if (a) b();"), "a&&b();");
+    assert_eq!(compress("if (!a) { b(); }"), "a||b();");
+    assert_eq!(compress("if (a) { b(); } else { c = 1; }"), "a?b():c=1;");
+    assert_eq!(compress("if (!a) b(); else c();"), "a?c():b();");
+    assert_eq!(compress("if (a) { b(); c(); }"), "a&&(b(),c());");
+    assert_eq!(compress("if (a = b) c = 1;"), "(a=b)&&(c=1);");
+    assert_eq!(compress("if (a = b) c(); else d();"), "(a=b)?c():d();");
+}
+
+#[test]
+fn merge_variable_declarations() {
+    assert_eq!(compress("var a = 1; var b; var c = a;"), "var a=1,b,c=a;");
+    assert_eq!(compress("var a = 1; x(); var b = 2;"), "var a=1;x();var b=2;");
+}
+
+#[test]
+fn remove_redundant_braces() {
+    assert_eq!(compress("for (;;) { if (a) { break; } }"), "for(;;)if(a)break;");
+    assert_eq!(compress("while (a) { a--; }"), "while(a)a--;");
+    assert_eq!(compress("{ a(); { b(); } }"), "a(),b();");
+    assert_eq!(compress("if (a) { return; } else { b(); c(); }"), "if(a)return;else b(),c();");
+
+    // Braces are needed by block scoped declarations and a dangling `else`
+    assert_eq!(compress("if (a) { function g() {} }"), "if(a){function g(){}}");
+    assert_eq!(compress("if (a) { if (b) { return; } } else { c(); }"), "if(a){if(b)return;}else c();");
+}
+
+#[test]
+fn replace_defined_globals() {
+    let defines = vec![
+        Define::parse("process.env.NODE_ENV=\"production\"").unwrap(),
+        Define::parse("DEBUG=false").unwrap(),
+    ];
+
+    assert_eq!(
+        compress_with("if (process.env.NODE_ENV !== \"production\") { check(); } run(DEBUG);", &defines),
+        "run(!1);"
+    );
+    assert_eq!(compress_with("process.env.NODE_ENV = 1; DEBUG++;", &defines), "process.env.NODE_ENV=1,DEBUG++;");
+    assert_eq!(compress_with("function f(DEBUG) { return DEBUG; }", &defines), "function f(DEBUG){return DEBUG;}");
+}
+
+#[test]
+fn invalid_defines() {
+    assert_eq!(Define::parse("DEBUG"), Err(DefineError::InvalidName {
+        definition: "DEBUG".to_string(),
+    }));
+    assert_eq!(Define::parse("a.1=2"), Err(DefineError::InvalidName {
+        definition: "a.1=2".to_string(),
+    }));
+    assert_eq!(Define::parse("MODE=production"), Err(DefineError::InvalidValue {
+        definition: "MODE=production".to_string(),
+    }));
+}
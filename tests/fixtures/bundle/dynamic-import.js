@@ -0,0 +1,3 @@
+export function load(name) {
+    return import('./lib/' + name);
+}
@@ -0,0 +1,3 @@
+var name = './lib/' + process.env.NAME;
+
+module.exports = require(name);
@@ -0,0 +1,7 @@
+import greet, { name } from './lib';
+import pad from 'left-pad';
+import * as counter from 'counter';
+import config from './config.json';
+
+counter.increment();
+console.log(pad(greet(name), config.width), counter.count);
@@ -0,0 +1,5 @@
+export const name = 'badger';
+
+export default function greet(who) {
+    return `hello ${who}`;
+}
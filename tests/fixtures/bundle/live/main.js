@@ -0,0 +1,4 @@
+import { count, inc } from './re';
+
+inc();
+console.log(count);
@@ -0,0 +1 @@
+export { count, inc } from './c';
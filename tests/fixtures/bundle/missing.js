@@ -0,0 +1 @@
+import foo from './does-not-exist';
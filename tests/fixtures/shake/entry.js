@@ -0,0 +1,6 @@
+import { used } from './utils';
+import { add } from 'mathlib';
+import './polyfill';
+import './styles';
+
+console.log(used(), add(1, 2));
@@ -0,0 +1,5 @@
+export function helper() {
+    return 1;
+}
+
+export const other = 2, another = helper;
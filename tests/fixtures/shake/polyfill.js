@@ -0,0 +1 @@
+window.polyfilled = true;
@@ -0,0 +1,14 @@
+import { helper } from './helpers';
+
+const unreferenced = 42;
+const kept = console.log('kept');
+
+export function used() {
+    return helper();
+}
+
+export function unused() {
+    return unreferenced;
+}
+
+export default 'unused';
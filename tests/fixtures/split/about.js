@@ -0,0 +1,6 @@
+import { log } from './shared/log';
+import { fields } from './shared/form';
+
+export default function () {
+    log('about ' + fields.length);
+}
@@ -0,0 +1,5 @@
+import { fields } from './shared/form';
+
+export function render() {
+    console.log('contact', fields.join(','));
+}
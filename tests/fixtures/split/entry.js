@@ -0,0 +1,10 @@
+import { log } from './shared/log';
+
+log('entry');
+
+export function route(name) {
+    return name === 'about' ? import('./about') : import('./contact');
+}
+
+route('about').then(about => about.default());
+route('contact').then(contact => contact.render());
@@ -0,0 +1 @@
+export const fields = ['name', 'email'];
@@ -6,15 +6,22 @@ pub use badger::parser::parse;
 pub use badger::grammar::OperatorType::*;
 
 macro_rules! assert_parse {
-    ($string:expr, $body:expr) => {
-        assert_eq!(parse($string.to_string()).body, $body);
-    }
+    ($string:expr, $body:expr) => {{
+        let body: Vec<Statement> = $body;
+        let body: Vec<Loc<Statement>> = body.into_iter().map(Loc::from).collect();
+
+        assert_eq!(parse($string.to_string()).unwrap().body, body)
+    }}
 }
 
 macro_rules! assert_expression {
     ($string:expr, $ex:expr) => {
-        match parse($string.to_string()).body[0] {
-            Statement::Expression { ref value } => assert_eq!(*value, $ex),
+        match parse($string.to_string()).unwrap().body[0].item {
+            Statement::Expression { ref value } => {
+                let expected: Expression = $ex;
+
+                assert_eq!(*value, Loc::from(expected))
+            },
             _                                   => panic!("No expression found"),
         }
     }
@@ -25,7 +32,7 @@ macro_rules! assert_statement {
 }
 
 macro_rules! num {
-    ($num:expr) => (Expression::Literal(LiteralFloat($num)))
+    ($num:expr) => (Expression::Literal(LiteralFloat($num.into())).into())
 }
 
 macro_rules! boxnum {
@@ -33,13 +40,11 @@ macro_rules! boxnum {
 }
 
 macro_rules! ident {
-    ($name:expr) => (Expression::Identifier($name.to_string()))
+    ($name:expr) => (Expression::Identifier($name.into()).into())
 }
 
 macro_rules! param {
-    ($name:expr) => (Parameter {
-        name: $name.to_string()
-    })
+    ($name:expr) => (Parameter::from($name))
 }
 
 #[test]
@@ -52,10 +57,10 @@ fn block_statement() {
 #[test]
 fn labeled_statement() {
     assert_statement!("foo: {}", Statement::Labeled {
-        label: "foo".to_string(),
+        label: "foo".into(),
         body: Box::new(Statement::Block {
             body: Vec::new(),
-        }),
+        }.into()),
     });
 }
 
@@ -69,7 +74,7 @@ fn break_statement() {
 #[test]
 fn break_label_statement() {
     assert_statement!("break foo;", Statement::Break {
-        label: Some("foo".to_string())
+        label: Some("foo".into())
     });
 }
 
@@ -88,6 +93,28 @@ fn break_asi_statement() {
     ]);
 }
 
+#[test]
+fn continue_statement() {
+    assert_statement!("continue;", Statement::Continue {
+        label: None
+    });
+}
+
+#[test]
+fn continue_label_asi_statement() {
+    assert_parse!("continue foo\ncontinue\nfoo", vec![
+        Statement::Continue {
+            label: Some("foo".into())
+        },
+        Statement::Continue {
+            label: None
+        },
+        Statement::Expression {
+            value: ident!("foo")
+        },
+    ]);
+}
+
 #[test]
 fn return_statement() {
     assert_statement!("return;", Statement::Return {
@@ -106,10 +133,10 @@ fn return_value_statement() {
 fn return_sequence_statement() {
     assert_statement!("return 1, 2, 3;", Statement::Return {
         value: Some(Expression::Sequence(vec![
-            num!(1.0),
-            num!(2.0),
-            num!(3.0),
-        ])),
+            num!("1"),
+            num!("2"),
+            num!("3"),
+        ]).into()),
     });
 }
 
@@ -133,7 +160,7 @@ fn var_declare() {
     assert_statement!("var foo;", Statement::VariableDeclaration {
         kind: VariableDeclarationKind::Var,
         declarators: vec![VariableDeclarator {
-            name: "foo".to_string(),
+            name: "foo".into(),
             value: None,
         }]
     });
@@ -144,8 +171,8 @@ fn var_declare_value() {
     assert_statement!("var foo = 100;", Statement::VariableDeclaration {
         kind: VariableDeclarationKind::Var,
         declarators: vec![VariableDeclarator {
-            name: "foo".to_string(),
-            value: Some(num!(100.0)),
+            name: "foo".into(),
+            value: Some(num!("100")),
         }]
     });
 }
@@ -155,7 +182,7 @@ fn let_declare() {
     assert_statement!("let foo;", Statement::VariableDeclaration {
         kind: VariableDeclarationKind::Let,
         declarators: vec![VariableDeclarator {
-            name: "foo".to_string(),
+            name: "foo".into(),
             value: None,
         }]
     });
@@ -166,8 +193,8 @@ fn let_declare_value() {
     assert_statement!("let foo = 100;", Statement::VariableDeclaration {
         kind: VariableDeclarationKind::Let,
         declarators: vec![VariableDeclarator {
-            name: "foo".to_string(),
-            value: Some(num!(100.0)),
+            name: "foo".into(),
+            value: Some(num!("100")),
         }]
     });
 }
@@ -177,7 +204,7 @@ fn const_declare() {
     assert_statement!("const foo;", Statement::VariableDeclaration {
         kind: VariableDeclarationKind::Const,
         declarators: vec![VariableDeclarator {
-            name: "foo".to_string(),
+            name: "foo".into(),
             value: None,
         }]
     });
@@ -188,8 +215,8 @@ fn const_declare_value() {
     assert_statement!("const foo = 100;", Statement::VariableDeclaration {
         kind: VariableDeclarationKind::Const,
         declarators: vec![VariableDeclarator {
-            name: "foo".to_string(),
-            value: Some(num!(100.0)),
+            name: "foo".into(),
+            value: Some(num!("100")),
         }]
     });
 }
@@ -199,10 +226,10 @@ fn var_muliple_declare() {
     assert_statement!("var foo, bar;", Statement::VariableDeclaration {
         kind: VariableDeclarationKind::Var,
         declarators: vec![VariableDeclarator {
-            name: "foo".to_string(),
+            name: "foo".into(),
             value: None,
         }, VariableDeclarator {
-            name: "bar".to_string(),
+            name: "bar".into(),
             value: None,
         }]
     });
@@ -214,11 +241,60 @@ fn var_muliple_declare_value() {
     assert_statement!("var foo = 100, bar = 200;", Statement::VariableDeclaration {
         kind: VariableDeclarationKind::Var,
         declarators: vec![VariableDeclarator {
-            name: "foo".to_string(),
-            value: Some(num!(100.0)),
+            name: "foo".into(),
+            value: Some(num!("100")),
         }, VariableDeclarator {
-            name: "bar".to_string(),
-            value: Some(num!(200.0)),
+            name: "bar".into(),
+            value: Some(num!("200")),
+        }]
+    });
+}
+
+#[test]
+fn var_array_pattern_declare() {
+    assert_statement!("var [a, , b = 1, ...c] = foo;", Statement::VariableDeclaration {
+        kind: VariableDeclarationKind::Var,
+        declarators: vec![VariableDeclarator {
+            name: Pattern::Array(vec![
+                Some("a".into()),
+                None,
+                Some(Pattern::Default {
+                    pattern: Box::new("b".into()),
+                    value: Box::new(num!("1")),
+                }),
+                Some(Pattern::Rest(Box::new("c".into()))),
+            ]),
+            value: Some(ident!("foo")),
+        }]
+    });
+}
+
+#[test]
+fn const_object_pattern_declare() {
+    assert_statement!("const { a, b: { c }, d = 1 } = foo;", Statement::VariableDeclaration {
+        kind: VariableDeclarationKind::Const,
+        declarators: vec![VariableDeclarator {
+            name: Pattern::Object(vec![
+                PropertyPattern {
+                    key: "a".into(),
+                    value: "a".into(),
+                },
+                PropertyPattern {
+                    key: "b".into(),
+                    value: Pattern::Object(vec![PropertyPattern {
+                        key: "c".into(),
+                        value: "c".into(),
+                    }]),
+                },
+                PropertyPattern {
+                    key: "d".into(),
+                    value: Pattern::Default {
+                        pattern: Box::new("d".into()),
+                        value: Box::new(num!("1")),
+                    },
+                },
+            ]),
+            value: Some(ident!("foo")),
         }]
     });
 }
@@ -250,7 +326,7 @@ fn false_expression() {
 
 #[test]
 fn number_expression() {
-    assert_expression!("100", num!(100.0));
+    assert_expression!("100", num!("100"));
 }
 
 #[test]
@@ -270,28 +346,88 @@ fn hexdec_number_expression() {
 
 #[test]
 fn floating_number_expression() {
-    assert_expression!("3.14", num!(3.14));
+    assert_expression!("3.14", num!("3.14"));
+}
+
+#[test]
+fn regex_expression() {
+    assert_expression!("/ab+c/gi", Expression::Literal(LiteralRegEx {
+        pattern: "ab+c".into(),
+        flags: "gi".into(),
+    }));
+}
+
+#[test]
+fn regex_with_slash_in_class_expression() {
+    assert_expression!(r"/[/]\//", Expression::Literal(LiteralRegEx {
+        pattern: r"[/]\/".into(),
+        flags: "".into(),
+    }));
+}
+
+#[test]
+fn regex_or_division_expression() {
+    assert_expression!("a / /b/", Expression::Binary {
+        left: Box::new(ident!("a")),
+        operator: Division,
+        right: Box::new(Expression::Literal(LiteralRegEx {
+            pattern: "b".into(),
+            flags: "".into(),
+        }).into()),
+    });
+}
+
+#[test]
+fn regex_after_paren_and_brace_statement() {
+    assert_statement!("if (x) /foo/.test(y);", Statement::If {
+        test: ident!("x"),
+        consequent: Box::new(Statement::Expression {
+            value: Expression::call(
+                Expression::member(Expression::Literal(LiteralRegEx {
+                    pattern: "foo".into(),
+                    flags: "".into(),
+                }), "test"),
+                vec![ident!("y")]
+            ).into(),
+        }.into()),
+        alternate: None,
+    });
+
+    assert_parse!("{}\n/=a/g.exec(b);", vec![
+        Statement::Block {
+            body: Vec::new(),
+        },
+        Statement::Expression {
+            value: Expression::call(
+                Expression::member(Expression::Literal(LiteralRegEx {
+                    pattern: "=a".into(),
+                    flags: "g".into(),
+                }), "exec"),
+                vec![ident!("b")]
+            ).into(),
+        },
+    ]);
 }
 
 #[test]
 fn binary_expression() {
     assert_expression!("true == 1", Expression::Binary {
-        left: Box::new(Expression::Literal(LiteralTrue)),
+        left: Box::new(Expression::Literal(LiteralTrue).into()),
         operator: Equality,
-        right: boxnum!(1.0)
+        right: boxnum!("1")
     });
 }
 
 #[test]
 fn op_precedence_left() {
     assert_expression!("1 + 2 * 3", Expression::Binary {
-        left: boxnum!(1.0),
+        left: boxnum!("1"),
         operator: Addition,
         right: Box::new(Expression::Binary {
-            left: boxnum!(2.0),
+            left: boxnum!("2"),
             operator: Multiplication,
-            right: boxnum!(3.0),
-        }),
+            right: boxnum!("3"),
+        }.into()),
     });
 }
 
@@ -299,12 +435,109 @@ fn op_precedence_left() {
 fn op_precedence_right() {
     assert_expression!("1 * 2 + 3", Expression::Binary {
         left: Box::new(Expression::Binary {
-            left: boxnum!(1.0),
+            left: boxnum!("1"),
             operator: Multiplication,
-            right: boxnum!(2.0),
-        }),
+            right: boxnum!("2"),
+        }.into()),
         operator: Addition,
-        right: boxnum!(3.0),
+        right: boxnum!("3"),
+    });
+}
+
+#[test]
+fn op_associativity_left() {
+    assert_expression!("1 - 2 - 3", Expression::Binary {
+        left: Box::new(Expression::Binary {
+            left: boxnum!("1"),
+            operator: Substraction,
+            right: boxnum!("2"),
+        }.into()),
+        operator: Substraction,
+        right: boxnum!("3"),
+    });
+}
+
+#[test]
+fn op_associativity_right() {
+    assert_expression!("a = b = 1", Expression::Binary {
+        left: Box::new(ident!("a")),
+        operator: Assign,
+        right: Box::new(Expression::Binary {
+            left: Box::new(ident!("b")),
+            operator: Assign,
+            right: boxnum!("1"),
+        }.into()),
+    });
+}
+
+#[test]
+fn call_after_operator() {
+    assert_expression!("!a.b(1)", Expression::Prefix {
+        operator: LogicalNot,
+        operand: Box::new(Expression::call(Expression::member("a", "b"), vec![num!("1")]).into()),
+    });
+}
+
+#[test]
+fn new_with_member_and_arguments() {
+    assert_expression!("new a.b(1)", Expression::call(Expression::Prefix {
+        operator: New,
+        operand: Box::new(Expression::member("a", "b").into()),
+    }, vec![num!("1")]));
+}
+
+#[test]
+fn conditional_with_assignments() {
+    assert_expression!("a ? b = f(1) : c = 2", Expression::Conditional {
+        test: Box::new(ident!("a")),
+        consequent: Box::new(Expression::Binary {
+            left: Box::new(ident!("b")),
+            operator: Assign,
+            right: Box::new(Expression::call("f", vec![num!("1")]).into()),
+        }.into()),
+        alternate: Box::new(Expression::Binary {
+            left: Box::new(ident!("c")),
+            operator: Assign,
+            right: boxnum!("2"),
+        }.into()),
+    });
+}
+
+#[test]
+fn template_literal_expression() {
+    assert_expression!("`foo`", Expression::TemplateLiteral {
+        quasis: vec!["foo".into()],
+        expressions: Vec::new(),
+    });
+}
+
+#[test]
+fn template_literal_with_expressions() {
+    assert_expression!("`foo${ bar }${1}baz`", Expression::TemplateLiteral {
+        quasis: vec!["foo".into(), "".into(), "baz".into()],
+        expressions: vec![ident!("bar"), num!("1")],
+    });
+}
+
+#[test]
+fn nested_template_literal() {
+    assert_expression!("`a${ `b${c}` }`", Expression::TemplateLiteral {
+        quasis: vec!["a".into(), "".into()],
+        expressions: vec![
+            Expression::TemplateLiteral {
+                quasis: vec!["b".into(), "".into()],
+                expressions: vec![ident!("c")],
+            }.into()
+        ],
+    });
+}
+
+#[test]
+fn tagged_template_expression() {
+    assert_expression!("foo.bar`baz${1}`", Expression::TaggedTemplate {
+        tag: Box::new(Expression::member("foo", "bar").into()),
+        quasis: vec!["baz".into(), "".into()],
+        expressions: vec![num!("1")],
     });
 }
 
@@ -317,12 +550,12 @@ fn function_statement() {
     }
 
     ", Statement::Function {
-        name: "foo".to_string(),
+        name: "foo".into(),
         params: vec![],
         body: vec![
             Statement::Return {
                 value: Some(ident!("bar"))
-            }
+            }.into()
         ]
     });
 }
@@ -336,7 +569,7 @@ fn function_with_params_statement() {
     }
 
     ", Statement::Function {
-        name: "foo".to_string(),
+        name: "foo".into(),
         params: vec![
             param!("a"),
             param!("b"),
@@ -345,11 +578,31 @@ fn function_with_params_statement() {
         body: vec![
             Statement::Return {
                 value: Some(ident!("bar"))
-            }
+            }.into()
         ]
     });
 }
 
+#[test]
+fn function_with_default_and_rest_params_statement() {
+    assert_statement!("function foo(a = 1, ...b) {}", Statement::Function {
+        name: "foo".into(),
+        params: vec![
+            Parameter {
+                name: "a".into(),
+                default: Some(num!("1")),
+                rest: false,
+            },
+            Parameter {
+                name: "b".into(),
+                default: None,
+                rest: true,
+            },
+        ],
+        body: vec![]
+    });
+}
+
 #[test]
 fn if_statement() {
     assert_statement!("
@@ -359,12 +612,12 @@ fn if_statement() {
     }
 
     ", Statement::If {
-        test: Expression::Literal(LiteralTrue),
+        test: Expression::Literal(LiteralTrue).into(),
         consequent: Box::new(Statement::Block {
             body: vec![Statement::Expression {
                 value: ident!("foo")
-            }]
-        }),
+            }.into()]
+        }.into()),
         alternate: None,
     });
 }
@@ -380,17 +633,17 @@ fn if_else_statement() {
     }
 
     ", Statement::If {
-        test: Expression::Literal(LiteralTrue),
+        test: Expression::Literal(LiteralTrue).into(),
         consequent: Box::new(Statement::Block {
             body: vec![Statement::Expression {
                 value: ident!("foo")
-            }]
-        }),
+            }.into()]
+        }.into()),
         alternate: Some(Box::new(Statement::Block {
             body: vec![Statement::Expression {
                 value: ident!("bar")
-            }]
-        })),
+            }.into()]
+        }.into())),
     })
 }
 
@@ -407,35 +660,35 @@ fn if_else_if_else_statement() {
     }
 
     ", Statement::If {
-        test: Expression::Literal(LiteralTrue),
+        test: Expression::Literal(LiteralTrue).into(),
         consequent: Box::new(Statement::Block {
             body: vec![Statement::Expression {
                 value: ident!("foo")
-            }]
-        }),
+            }.into()]
+        }.into()),
         alternate: Some(Box::new(Statement::If {
-            test: Expression::Literal(LiteralFalse),
+            test: Expression::Literal(LiteralFalse).into(),
             consequent: Box::new(Statement::Block {
                 body: vec![Statement::Expression {
                     value: ident!("bar")
-                }]
-            }),
+                }.into()]
+            }.into()),
             alternate: Some(Box::new(Statement::Block {
                 body: vec![Statement::Expression {
                     value: ident!("baz")
-                }]
-            })),
-        })),
+                }.into()]
+            }.into())),
+        }.into())),
     });
 }
 
 #[test]
 fn if_no_block_statement() {
     assert_statement!("if (true) foo;", Statement::If {
-        test: Expression::Literal(LiteralTrue),
+        test: Expression::Literal(LiteralTrue).into(),
         consequent: Box::new(Statement::Expression {
             value: ident!("foo")
-        }),
+        }.into()),
         alternate: None,
     });
 }
@@ -443,13 +696,13 @@ fn if_no_block_statement() {
 #[test]
 fn if_else_no_block_statement() {
     assert_statement!("if (true) foo; else bar;", Statement::If {
-        test: Expression::Literal(LiteralTrue),
+        test: Expression::Literal(LiteralTrue).into(),
         consequent: Box::new(Statement::Expression {
             value: ident!("foo")
-        }),
+        }.into()),
         alternate: Some(Box::new(Statement::Expression {
             value: ident!("bar")
-        })),
+        }.into())),
     })
 }
 
@@ -460,21 +713,21 @@ fn for_statement() {
             value: Expression::Binary {
                 left: Box::new(ident!("i")),
                 operator: OperatorType::Assign,
-                right: Box::new(num!(0.0)),
-            }
-        })),
+                right: Box::new(num!("0")),
+            }.into()
+        }.into())),
         test: Some(Expression::Binary {
             left: Box::new(ident!("i")),
             operator: OperatorType::Lesser,
-            right: Box::new(num!(10.0)),
-        }),
+            right: Box::new(num!("10")),
+        }.into()),
         update: Some(Expression::Postfix {
             operator: OperatorType::Increment,
             operand: Box::new(ident!("i")),
-        }),
+        }.into()),
         body: Box::new(Statement::Block {
             body: Vec::new(),
-        }),
+        }.into()),
     });
 }
 
@@ -485,23 +738,23 @@ fn for_declare_statement() {
             kind: VariableDeclarationKind::Let,
             declarators: vec![
                 VariableDeclarator {
-                    name: "i".to_string(),
-                    value: Some(num!(0.0)),
+                    name: "i".into(),
+                    value: Some(num!("0")),
                 }
             ],
-        })),
+        }.into())),
         test: Some(Expression::Binary {
             left: Box::new(ident!("i")),
             operator: OperatorType::Lesser,
-            right: Box::new(num!(10.0)),
-        }),
+            right: Box::new(num!("10")),
+        }.into()),
         update: Some(Expression::Postfix {
             operator: OperatorType::Increment,
             operand: Box::new(ident!("i")),
-        }),
+        }.into()),
         body: Box::new(Statement::Block {
             body: Vec::new(),
-        }),
+        }.into()),
     });
 }
 
@@ -513,7 +766,7 @@ fn for_empty_statement() {
         update: None,
         body: Box::new(Statement::Block {
             body: Vec::new(),
-        }),
+        }.into()),
     });
 }
 
@@ -522,11 +775,11 @@ fn for_in_statement() {
     assert_statement!("for (item in object) {}", Statement::ForIn {
         left: Box::new(Statement::Expression {
             value: ident!("item")
-        }),
+        }.into()),
         right: ident!("object"),
         body: Box::new(Statement::Block {
             body: Vec::new(),
-        }),
+        }.into()),
     });
 }
 
@@ -538,15 +791,15 @@ fn for_in_declare_statement() {
             kind: VariableDeclarationKind::Let,
             declarators: vec![
                 VariableDeclarator {
-                    name: "item".to_string(),
+                    name: "item".into(),
                     value: None,
                 }
             ],
-        }),
+        }.into()),
         right: ident!("object"),
         body: Box::new(Statement::Block {
             body: Vec::new(),
-        }),
+        }.into()),
     });
 }
 
@@ -555,11 +808,11 @@ fn for_of_statement() {
     assert_statement!("for (item of array) {}", Statement::ForOf {
         left: Box::new(Statement::Expression {
             value: ident!("item")
-        }),
+        }.into()),
         right: ident!("array"),
         body: Box::new(Statement::Block {
             body: Vec::new(),
-        }),
+        }.into()),
     });
 }
 
@@ -570,15 +823,31 @@ fn for_of_declare_statement() {
             kind: VariableDeclarationKind::Let,
             declarators: vec![
                 VariableDeclarator {
-                    name: "item".to_string(),
+                    name: "item".into(),
                     value: None,
                 }
             ],
-        }),
+        }.into()),
         right: ident!("array"),
         body: Box::new(Statement::Block {
             body: Vec::new(),
-        }),
+        }.into()),
+    });
+}
+
+#[test]
+fn for_of_pattern_statement() {
+    assert_statement!("for ([a, b] of array) {}", Statement::ForOf {
+        left: Box::new(Statement::Expression {
+            value: Expression::Pattern(Pattern::Array(vec![
+                Some("a".into()),
+                Some("b".into()),
+            ])).into()
+        }.into()),
+        right: ident!("array"),
+        body: Box::new(Statement::Block {
+            body: Vec::new(),
+        }.into()),
     });
 }
 
@@ -591,22 +860,116 @@ fn while_statement() {
     }
 
     ", Statement::While {
-        test: Expression::Literal(LiteralTrue),
+        test: Expression::Literal(LiteralTrue).into(),
         body: Box::new(Statement::Block {
             body: vec![Statement::Expression {
                 value: ident!("foo")
-            }]
-        }),
+            }.into()]
+        }.into()),
     });
 }
 
 #[test]
 fn while_no_block_statement() {
     assert_statement!("while (true) foo;", Statement::While {
-        test: Expression::Literal(LiteralTrue),
+        test: Expression::Literal(LiteralTrue).into(),
         body: Box::new(Statement::Expression {
             value: ident!("foo")
+        }.into()),
+    });
+}
+
+#[test]
+fn try_catch_finally_statement() {
+    assert_statement!("try { foo; } catch (err) { bar; } finally { baz; }", Statement::Try {
+        block: vec![Statement::Expression {
+            value: ident!("foo")
+        }.into()],
+        handler: Some(CatchClause {
+            param: Some("err".into()),
+            body: vec![Statement::Expression {
+                value: ident!("bar")
+            }.into()],
+        }),
+        finalizer: Some(vec![Statement::Expression {
+            value: ident!("baz")
+        }.into()]),
+    });
+}
+
+#[test]
+fn try_catch_without_binding_statement() {
+    assert_statement!("try {} catch {}", Statement::Try {
+        block: Vec::new(),
+        handler: Some(CatchClause {
+            param: None,
+            body: Vec::new(),
         }),
+        finalizer: None,
+    });
+}
+
+#[test]
+fn try_finally_statement() {
+    assert_statement!("try {} finally {}", Statement::Try {
+        block: Vec::new(),
+        handler: None,
+        finalizer: Some(Vec::new()),
+    });
+}
+
+#[test]
+fn do_while_statement() {
+    assert_statement!("do foo; while (true)", Statement::DoWhile {
+        body: Box::new(Statement::Expression {
+            value: ident!("foo")
+        }.into()),
+        test: Expression::Literal(LiteralTrue).into(),
+    });
+}
+
+#[test]
+fn do_while_asi_statement() {
+    assert_parse!("do {} while (a) b", vec![
+        Statement::DoWhile {
+            body: Box::new(Statement::Block {
+                body: Vec::new(),
+            }.into()),
+            test: ident!("a"),
+        },
+        Statement::Expression {
+            value: ident!("b")
+        },
+    ]);
+}
+
+#[test]
+fn switch_statement() {
+    assert_statement!("switch (a) { case 1: case 2: foo; break; default: bar; }", Statement::Switch {
+        discriminant: ident!("a"),
+        cases: vec![
+            SwitchCase {
+                test: Some(num!("1")),
+                consequent: Vec::new(),
+            },
+            SwitchCase {
+                test: Some(num!("2")),
+                consequent: vec![
+                    Statement::Expression {
+                        value: ident!("foo")
+                    }.into(),
+                    Statement::Break {
+                        label: None
+                    }.into(),
+                ],
+            },
+            SwitchCase {
+                test: None,
+                consequent: vec![Statement::Expression {
+                    value: ident!("bar")
+                }.into()],
+            },
+        ],
     });
 }
 
@@ -623,8 +986,8 @@ fn arrow_function() {
         body: Box::new(Statement::Block {
             body: vec![Statement::Expression {
                 value: ident!("bar")
-            }]
-        })
+            }.into()]
+        }.into())
     });
 }
 
@@ -639,8 +1002,8 @@ fn arrow_function_shorthand() {
                 left: Box::new(ident!("n")),
                 operator: Multiplication,
                 right: Box::new(ident!("n")),
-            }
-        }),
+            }.into()
+        }.into()),
     });
 }
 
@@ -661,8 +1024,45 @@ fn arrow_function_with_params() {
         body: Box::new(Statement::Block {
             body: vec![Statement::Expression {
                 value: ident!("bar")
-            }]
-        })
+            }.into()]
+        }.into())
+    });
+}
+
+#[test]
+fn arrow_function_with_default_and_rest_params() {
+    assert_expression!("(a = 1, ...b) => a", Expression::ArrowFunction {
+        params: vec![
+            Parameter {
+                name: "a".into(),
+                default: Some(num!("1")),
+                rest: false,
+            },
+            Parameter {
+                name: "b".into(),
+                default: None,
+                rest: true,
+            },
+        ],
+        body: Box::new(Statement::Expression {
+            value: ident!("a")
+        }.into())
+    });
+}
+
+#[test]
+fn arrow_function_with_patterns() {
+    assert_expression!("([a], { b }) => a", Expression::ArrowFunction {
+        params: vec![
+            param!(Pattern::Array(vec![Some("a".into())])),
+            param!(Pattern::Object(vec![PropertyPattern {
+                key: "b".into(),
+                value: "b".into(),
+            }])),
+        ],
+        body: Box::new(Statement::Expression {
+            value: ident!("a")
+        }.into())
     });
 }
 
@@ -680,7 +1080,7 @@ fn function_expression() {
         body: vec![
             Statement::Return {
                 value: Some(ident!("bar"))
-            }
+            }.into()
         ]
     });
 }
@@ -694,16 +1094,31 @@ fn named_function_expression() {
     })
 
     ", Expression::Function {
-        name: Some("foo".to_string()),
+        name: Some("foo".into()),
         params: vec![],
         body: vec![
             Statement::Return {
                 value: Some(ident!("bar"))
-            }
+            }.into()
         ]
     });
 }
 
+#[test]
+fn array_pattern_assignment() {
+    assert_expression!("[a, foo.b = 1] = c", Expression::binary(
+        Expression::Pattern(Pattern::Array(vec![
+            Some("a".into()),
+            Some(Pattern::Default {
+                pattern: Box::new(Pattern::Expression(Box::new(Expression::member("foo", "b").into()))),
+                value: Box::new(num!("1")),
+            }),
+        ])),
+        Assign,
+        Expression::Identifier("c".into())
+    ));
+}
+
 #[test]
 fn expression_statement() {
     assert_statement!("foo", Statement::Expression {
@@ -718,21 +1133,19 @@ fn sequence_expression_statement() {
             ident!("foo"),
             ident!("bar"),
             ident!("baz"),
-        ])
+        ]).into()
     });
 }
 
 #[test]
 fn sequence_in_accessor() {
-    assert_expression!("foo[1, 2, 3]", Expression::Member {
+    assert_expression!("foo[1, 2, 3]", Expression::ComputedMember {
         object: Box::new(ident!("foo")),
-        property: Box::new(MemberKey::Computed(
-            Expression::Sequence(vec![
-                num!(1.0),
-                num!(2.0),
-                num!(3.0),
-            ])
-        ))
+        property: Box::new(Expression::Sequence(vec![
+            num!("1"),
+            num!("2"),
+            num!("3"),
+        ]).into())
     });
 }
 
@@ -740,9 +1153,9 @@ fn sequence_in_accessor() {
 fn object_literal_member() {
     assert_expression!("({foo:100})", Expression::Object(vec![
         ObjectMember::Literal {
-            key: "foo".to_string(),
-            value: num!(100.0),
-        }
+            key: "foo".into(),
+            value: num!("100"),
+        }.into()
     ]));
 }
 
@@ -750,9 +1163,9 @@ fn object_literal_member() {
 fn object_computed_member() {
     assert_expression!("({[100]:100})", Expression::Object(vec![
         ObjectMember::Computed {
-            key: num!(100.0),
-            value: num!(100.0),
-        }
+            key: num!("100"),
+            value: num!("100"),
+        }.into()
     ]));
 }
 
@@ -760,8 +1173,8 @@ fn object_computed_member() {
 fn object_shorthand_member() {
     assert_expression!("({foo})", Expression::Object(vec![
         ObjectMember::Shorthand {
-            key: "foo".to_string(),
-        }
+            key: "foo".into(),
+        }.into()
     ]));
 }
 
@@ -769,10 +1182,34 @@ fn object_shorthand_member() {
 fn object_method_member() {
     assert_expression!("({foo() {} })", Expression::Object(vec![
         ObjectMember::Method {
-            name: "foo".to_string(),
+            name: "foo".into(),
             params: vec![],
             body: vec![],
-        }
+        }.into()
+    ]));
+}
+
+#[test]
+fn object_getter_and_setter_members() {
+    assert_expression!("({get foo() {}, set foo(bar) {}, get: 1, set() {} })", Expression::Object(vec![
+        ObjectMember::Getter {
+            name: "foo".into(),
+            body: vec![],
+        }.into(),
+        ObjectMember::Setter {
+            name: "foo".into(),
+            params: vec![param!("bar")],
+            body: vec![],
+        }.into(),
+        ObjectMember::Literal {
+            key: "get".into(),
+            value: num!("1"),
+        }.into(),
+        ObjectMember::Method {
+            name: "set".into(),
+            params: vec![],
+            body: vec![],
+        }.into(),
     ]));
 }
 
@@ -780,17 +1217,17 @@ fn object_method_member() {
 fn object_computed_method_member() {
     assert_expression!("({[100]() {} })", Expression::Object(vec![
         ObjectMember::ComputedMethod {
-            name: num!(100.0),
+            name: num!("100"),
             params: vec![],
             body: vec![],
-        }
+        }.into()
     ]));
 }
 
 #[test]
 fn class_statement() {
     assert_statement!("class Foo {}", Statement::Class {
-        name: "Foo".to_string(),
+        name: "Foo".into(),
         extends: None,
         body: Vec::new(),
     });
@@ -799,8 +1236,8 @@ fn class_statement() {
 #[test]
 fn class_extends_statement() {
     assert_statement!("class Foo extends Bar {}", Statement::Class {
-        name: "Foo".to_string(),
-        extends: Some("Bar".to_string()),
+        name: "Foo".into(),
+        extends: Some("Bar".into()),
         body: Vec::new(),
     });
 }
@@ -814,13 +1251,13 @@ fn class_with_constructor_statement() {
     }
 
     ", Statement::Class {
-        name: "Foo".to_string(),
+        name: "Foo".into(),
         extends: None,
         body: vec![
             ClassMember::Constructor {
                 params: Vec::new(),
                 body: Vec::new(),
-            }
+            }.into()
         ],
     });
 }
@@ -834,15 +1271,15 @@ fn class_with_method_statement() {
     }
 
     ", Statement::Class {
-        name: "Foo".to_string(),
+        name: "Foo".into(),
         extends: None,
         body: vec![
             ClassMember::Method {
                 is_static: false,
-                name: "bar".to_string(),
+                name: "bar".into(),
                 params: Vec::new(),
                 body: Vec::new(),
-            }
+            }.into()
         ],
     });
 }
@@ -856,15 +1293,15 @@ fn class_with_static_method_statement() {
     }
 
     ", Statement::Class {
-        name: "Foo".to_string(),
+        name: "Foo".into(),
         extends: None,
         body: vec![
             ClassMember::Method {
                 is_static: true,
-                name: "bar".to_string(),
+                name: "bar".into(),
                 params: Vec::new(),
                 body: Vec::new(),
-            }
+            }.into()
         ],
     });
 }
@@ -878,14 +1315,14 @@ fn class_with_property_statement() {
     }
 
     ", Statement::Class {
-        name: "Foo".to_string(),
+        name: "Foo".into(),
         extends: None,
         body: vec![
             ClassMember::Property {
                 is_static: false,
-                name: "bar".to_string(),
-                value: num!(100.0),
-            }
+                name: "bar".into(),
+                value: num!("100"),
+            }.into()
         ],
     });
 }
@@ -899,14 +1336,392 @@ fn class_with_static_property_statement() {
     }
 
     ", Statement::Class {
-        name: "Foo".to_string(),
+        name: "Foo".into(),
         extends: None,
         body: vec![
             ClassMember::Property {
                 is_static: true,
-                name: "bar".to_string(),
-                value: num!(100.0),
-            }
+                name: "bar".into(),
+                value: num!("100"),
+            }.into()
+        ],
+    });
+}
+
+fn compile_class(source: &str, settings: transformer::Settings) -> String {
+    let mut program = parse(source.to_string()).unwrap();
+
+    transformer::transform(&mut program, settings).unwrap();
+    codegen::generate_code(program, true)
+}
+
+#[test]
+fn class_with_static_property_es2015() {
+    assert_eq!(
+        compile_class("class Foo { static bar = 100; baz = 1; static qux() {} }", transformer::Settings::target_es2015()),
+        "class Foo{constructor(){this.baz=1;}static qux(){}}Foo.bar=100;"
+    );
+    assert_eq!(
+        compile_class("class Foo { static bar = 100; }", transformer::Settings::target_es2015()),
+        "class Foo{}Foo.bar=100;"
+    );
+}
+
+#[test]
+fn class_with_static_property_es5() {
+    assert_eq!(
+        compile_class("class Foo { static bar = 100; static baz = Foo.bar + 1; }", transformer::Settings::target_es5()),
+        "function Foo(){}Foo.bar=100;Foo.baz=Foo.bar+1;"
+    );
+}
+
+#[test]
+fn class_with_static_property_using_this() {
+    assert_eq!(
+        compile_class(
+            "class Foo { static a = 1; static b = this.a + 1; static f = () => this; static g = function () { return this; }; }",
+            transformer::Settings::target_es5()
+        ),
+        "function Foo(){}Foo.a=1;Foo.b=Foo.a+1;Foo.f=function(){return Foo;};Foo.g=function(){return this;};"
+    );
+    assert_eq!(
+        compile_class("class Foo { static a = { b: this, c() { return this; } }; }", transformer::Settings::target_es2015()),
+        "class Foo{}Foo.a={b:Foo,c(){return this;}};"
+    );
+}
+
+#[test]
+fn class_with_static_property_using_super() {
+    // Helpers `_get` and `_inherits` come first
+    let es5 = compile_class("class Foo extends Bar { static a = super.b; static c = super.d(1); }", transformer::Settings::target_es5());
+    let es2015 = compile_class("class Foo extends Bar { static a = super.b; }", transformer::Settings::target_es2015());
+
+    assert!(es5.ends_with("function Foo(){Bar.apply(this,arguments);}_inherits(Foo,Bar);Foo.a=_get(Bar,\"b\",Foo);Foo.c=Bar.d.call(Foo,1);"), "{}", es5);
+    assert!(es2015.ends_with("}class Foo extends Bar{}Foo.a=_get(Bar,\"b\",Foo);"), "{}", es2015);
+}
+
+#[test]
+fn class_with_getter_and_setter_statement() {
+    assert_statement!("
+
+    class Foo {
+        get bar() {}
+        static set bar(baz) {}
+        get() {}
+    }
+
+    ", Statement::Class {
+        name: "Foo".into(),
+        extends: None,
+        body: vec![
+            ClassMember::Getter {
+                is_static: false,
+                name: "bar".into(),
+                body: Vec::new(),
+            }.into(),
+            ClassMember::Setter {
+                is_static: true,
+                name: "bar".into(),
+                params: vec![param!("baz")],
+                body: Vec::new(),
+            }.into(),
+            ClassMember::Method {
+                is_static: false,
+                name: "get".into(),
+                params: Vec::new(),
+                body: Vec::new(),
+            }.into()
+        ],
+    });
+}
+
+#[test]
+fn class_with_super_statement() {
+    assert_statement!("
+
+    class Foo extends Bar {
+        constructor() { super(1); }
+        bar() { super.bar(); }
+    }
+
+    ", Statement::Class {
+        name: "Foo".into(),
+        extends: Some("Bar".into()),
+        body: vec![
+            ClassMember::Constructor {
+                params: Vec::new(),
+                body: vec![
+                    Expression::call(Expression::Super, vec![num!("1")]).into()
+                ],
+            }.into(),
+            ClassMember::Method {
+                is_static: false,
+                name: "bar".into(),
+                params: Vec::new(),
+                body: vec![
+                    Expression::call(Expression::member(Expression::Super, "bar"), Vec::new()).into()
+                ],
+            }.into()
         ],
     });
 }
+
+#[test]
+fn import_declarations() {
+    assert_parse!("
+        import 'foo';
+        import foo, { bar, baz as qux, default as quux } from 'foo';
+        import * as ns from 'foo';
+    ", vec![
+        Statement::ImportDeclaration {
+            specifiers: Vec::new(),
+            source: "'foo'".into(),
+        },
+        Statement::ImportDeclaration {
+            specifiers: vec![
+                ImportSpecifier::Default {
+                    local: "foo".into(),
+                },
+                ImportSpecifier::Named {
+                    imported: "bar".into(),
+                    local: "bar".into(),
+                },
+                ImportSpecifier::Named {
+                    imported: "baz".into(),
+                    local: "qux".into(),
+                },
+                ImportSpecifier::Named {
+                    imported: "default".into(),
+                    local: "quux".into(),
+                },
+            ],
+            source: "'foo'".into(),
+        },
+        Statement::ImportDeclaration {
+            specifiers: vec![
+                ImportSpecifier::Namespace {
+                    local: "ns".into(),
+                },
+            ],
+            source: "'foo'".into(),
+        },
+    ]);
+}
+
+#[test]
+fn export_declarations() {
+    assert_parse!("
+        export var foo = 1;
+        export { foo as bar, baz };
+        export { default } from 'foo';
+        export * from 'foo';
+        export default foo;
+    ", vec![
+        Statement::ExportDeclaration {
+            kind: ExportKind::Declaration(Box::new(Statement::VariableDeclaration {
+                kind: VariableDeclarationKind::Var,
+                declarators: vec![VariableDeclarator {
+                    name: "foo".into(),
+                    value: Some(num!("1")),
+                }],
+            }.into())),
+        },
+        Statement::ExportDeclaration {
+            kind: ExportKind::Named {
+                specifiers: vec![
+                    ExportSpecifier {
+                        local: "foo".into(),
+                        exported: "bar".into(),
+                    },
+                    ExportSpecifier {
+                        local: "baz".into(),
+                        exported: "baz".into(),
+                    },
+                ],
+                source: None,
+            },
+        },
+        Statement::ExportDeclaration {
+            kind: ExportKind::Named {
+                specifiers: vec![
+                    ExportSpecifier {
+                        local: "default".into(),
+                        exported: "default".into(),
+                    },
+                ],
+                source: Some("'foo'".into()),
+            },
+        },
+        Statement::ExportDeclaration {
+            kind: ExportKind::All {
+                source: "'foo'".into(),
+            },
+        },
+        Statement::ExportDeclaration {
+            kind: ExportKind::Default(Box::new(Statement::Expression {
+                value: ident!("foo")
+            }.into())),
+        },
+    ]);
+}
+
+#[test]
+fn export_default_function_declaration() {
+    assert_statement!("export default function foo() {}", Statement::ExportDeclaration {
+        kind: ExportKind::Default(Box::new(Statement::Function {
+            name: "foo".into(),
+            params: Vec::new(),
+            body: Vec::new(),
+        }.into())),
+    });
+}
+
+#[test]
+fn export_default_anonymous_class() {
+    assert_statement!("export default class extends Foo { bar() {} }", Statement::ExportDeclaration {
+        kind: ExportKind::Default(Box::new(Statement::Class {
+            name: "".into(),
+            extends: Some("Foo".into()),
+            body: vec![
+                ClassMember::Method {
+                    is_static: false,
+                    name: "bar".into(),
+                    params: Vec::new(),
+                    body: Vec::new(),
+                }.into()
+            ],
+        }.into())),
+    });
+}
+
+#[test]
+fn dynamic_import_expression() {
+    assert_expression!("import('./foo').then(bar)", Expression::Call {
+        callee: Box::new(Expression::member(Expression::Import {
+            source: Box::new(Expression::Literal(LiteralString("'./foo'".into())).into()),
+        }, "then").into()),
+        arguments: vec![ident!("bar")],
+    });
+}
+
+#[test]
+fn keyword_property_names() {
+    assert_expression!("foo.default.catch", Expression::member(Expression::member("foo", "default"), "catch"));
+}
+
+#[test]
+fn unexpected_token_error() {
+    assert_eq!(parse("var 1;".to_string()), Err(error::ParseError::UnexpectedToken {
+        token: "1".to_string(),
+        start: 4,
+        end: 5,
+        expected: vec![
+            error::Expected::Identifier,
+            error::Expected::Token(lexicon::Token::Control(b'[')),
+            error::Expected::Token(lexicon::Token::Control(b'{')),
+        ],
+    }));
+}
+
+#[test]
+fn unexpected_token_in_list_error() {
+    assert_eq!(parse("foo(bar;".to_string()), Err(error::ParseError::UnexpectedToken {
+        token: ";".to_string(),
+        start: 7,
+        end: 8,
+        expected: vec![
+            error::Expected::Token(lexicon::Token::Control(b',')),
+            error::Expected::Token(lexicon::Token::Control(b')')),
+        ],
+    }));
+}
+
+#[test]
+fn unexpected_end_of_program_error() {
+    assert_eq!(parse("{ foo".to_string()), Err(error::ParseError::UnexpectedEndOfProgram {
+        expected: vec![error::Expected::Token(lexicon::Token::Control(b'}'))],
+    }));
+}
+
+#[test]
+fn unexpected_character_error() {
+    assert_eq!(parse("foo #".to_string()), Err(error::ParseError::UnexpectedCharacter {
+        start: 4,
+        end: 5,
+    }));
+}
+
+#[test]
+fn statement_spans() {
+    let body = parse("foo;\n  bar = 1;\n".to_string()).unwrap().body;
+
+    assert_eq!(body[0].span, Span { start: 0, end: 4, line: 1, column: 0 });
+    assert_eq!(body[1].span, Span { start: 7, end: 15, line: 2, column: 2 });
+}
+
+#[test]
+fn expression_spans() {
+    let body = parse("a +\n  (b * c)".to_string()).unwrap().body;
+
+    match body[0].item {
+        Statement::Expression { ref value } => {
+            assert_eq!(value.span, Span { start: 0, end: 13, line: 1, column: 0 });
+
+            match value.item {
+                Expression::Binary { ref left, ref right, .. } => {
+                    assert_eq!(left.span, Span { start: 0, end: 1, line: 1, column: 0 });
+                    assert_eq!(right.span, Span { start: 6, end: 13, line: 2, column: 2 });
+                },
+                _ => panic!("No binary expression found"),
+            }
+        },
+        _ => panic!("No expression found"),
+    }
+}
+
+#[test]
+fn invalid_regex_flags_error() {
+    assert_eq!(parse("/a/gig".to_string()), Err(error::ParseError::InvalidRegExFlags {
+        start: 3,
+        end: 6,
+    }));
+}
+
+#[test]
+fn invalid_pattern_error() {
+    assert_eq!(parse("[a, 1] = b;".to_string()), Err(error::ParseError::InvalidPattern {
+        start: 4,
+        end: 5,
+    }));
+}
+
+#[test]
+fn rest_parameter_not_last_error() {
+    assert_eq!(parse("function f(...a, b) {}".to_string()), Err(error::ParseError::UnexpectedToken {
+        token: ",".to_string(),
+        start: 15,
+        end: 16,
+        expected: vec![error::Expected::Token(lexicon::Token::Control(b')'))],
+    }));
+}
+
+#[test]
+fn arrow_rest_parameter_not_last_error() {
+    assert_eq!(parse("x = (...a, b) => 1;".to_string()), Err(error::ParseError::InvalidPattern {
+        start: 5,
+        end: 9,
+    }));
+}
+
+#[test]
+fn try_without_catch_or_finally_error() {
+    assert_eq!(parse("try {} foo".to_string()), Err(error::ParseError::UnexpectedToken {
+        token: "foo".to_string(),
+        start: 7,
+        end: 10,
+        expected: vec![
+            error::Expected::Token(lexicon::Token::Catch),
+            error::Expected::Token(lexicon::Token::Finally),
+        ],
+    }));
+}
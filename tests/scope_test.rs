@@ -0,0 +1,159 @@
+extern crate badger;
+
+use badger::grammar::{ Program, VariableDeclarationKind };
+use badger::parser::parse;
+use badger::scope::{ self, BindingKind, ScopeKind, ScopeTree };
+
+fn analyze(source: &str) -> (Program, ScopeTree) {
+    let program = parse(source.to_string()).unwrap();
+    let tree = scope::analyze(&program);
+
+    (program, tree)
+}
+
+fn names(tree: &ScopeTree, bindings: &[usize]) -> Vec<String> {
+    bindings.iter().map(|&id| tree.bindings[id].name.to_string()).collect()
+}
+
+#[test]
+fn scope_kinds_and_declarations() {
+    let (_program, tree) = analyze("
+        var a = 1;
+        function f(b, c) {
+            if (b) { let d = c; var e; }
+            try {} catch (err) { const g = err; }
+        }
+        class Foo { bar() {} }
+    ");
+
+    let kinds: Vec<ScopeKind> = tree.scopes.iter().map(|scope| scope.kind).collect();
+
+    assert_eq!(kinds, vec![
+        ScopeKind::Program,
+        ScopeKind::Function,
+        ScopeKind::Block,
+        ScopeKind::Block,
+        ScopeKind::Catch,
+        ScopeKind::Class,
+        ScopeKind::Function,
+    ]);
+
+    assert_eq!(names(&tree, &tree.scopes[0].bindings), vec!["a", "f", "Foo"]);
+    assert_eq!(names(&tree, &tree.scopes[1].bindings), vec!["b", "c", "e"]);
+    assert_eq!(names(&tree, &tree.scopes[2].bindings), vec!["d"]);
+    assert_eq!(names(&tree, &tree.scopes[4].bindings), vec!["err", "g"]);
+
+    let kind = |scope, name| tree.bindings[tree.binding(scope, name).unwrap()].kind;
+
+    assert_eq!(kind(0, "a"), BindingKind::Variable(VariableDeclarationKind::Var));
+    assert_eq!(kind(0, "f"), BindingKind::Function);
+    assert_eq!(kind(0, "Foo"), BindingKind::Class);
+    assert_eq!(kind(1, "b"), BindingKind::Parameter);
+    assert_eq!(kind(2, "d"), BindingKind::Variable(VariableDeclarationKind::Let));
+    assert_eq!(kind(4, "err"), BindingKind::CatchParameter);
+    assert_eq!(kind(4, "g"), BindingKind::Variable(VariableDeclarationKind::Const));
+}
+
+#[test]
+fn resolve_references_and_globals() {
+    let (_program, tree) = analyze("
+        foo(a);
+        var a = 1;
+        a = 2;
+        [b, { c }] = [console, a];
+        let b, c;
+    ");
+
+    let resolved: Vec<(String, Option<String>, bool)> = tree.references.iter().map(|reference| (
+        reference.name.to_string(),
+        reference.binding.map(|id| tree.bindings[id].name.to_string()),
+        reference.write,
+    )).collect();
+
+    assert_eq!(resolved, vec![
+        ("foo".to_string(), None, false),
+        ("a".to_string(), Some("a".to_string()), false),
+        ("a".to_string(), Some("a".to_string()), true),
+        ("b".to_string(), Some("b".to_string()), true),
+        ("c".to_string(), Some("c".to_string()), true),
+        ("console".to_string(), None, false),
+        ("a".to_string(), Some("a".to_string()), false),
+    ]);
+
+    assert_eq!(tree.bindings[tree.binding(0, "a").unwrap()].references, vec![1, 2, 6]);
+
+    let globals: Vec<String> = tree.globals().iter().map(|name| name.to_string()).collect();
+
+    assert_eq!(globals, vec!["foo", "console"]);
+}
+
+#[test]
+fn shadowed_names() {
+    let (_program, tree) = analyze("
+        let x = 1;
+        function f(y) {
+            let x = y;
+            { let y = x; }
+        }
+    ");
+
+    assert!(!tree.is_shadowed(0, "x"));
+    assert!(tree.is_shadowed(1, "x"));
+    assert!(!tree.is_shadowed(1, "y"));
+    assert!(tree.is_shadowed(2, "y"));
+    assert!(tree.is_shadowed(2, "x"));
+    assert!(!tree.is_shadowed(2, "z"));
+
+    assert_eq!(tree.resolve(2, "x"), tree.binding(1, "x"));
+    assert_eq!(tree.resolve(2, "f"), tree.binding(0, "f"));
+    assert_eq!(tree.resolve(2, "z"), None);
+}
+
+#[test]
+fn free_variables_of_closures() {
+    let (_program, tree) = analyze("
+        var counter = 0;
+        function make(step) {
+            var unused;
+            return function next() {
+                counter += step;
+                return () => next(Math.max(counter, step));
+            };
+        }
+    ");
+
+    assert_eq!(names(&tree, &tree.free_variables(1)), vec!["counter"]);
+    assert_eq!(names(&tree, &tree.free_variables(2)), vec!["counter", "step"]);
+    assert_eq!(names(&tree, &tree.free_variables(3)), vec!["next", "counter", "step"]);
+}
+
+#[test]
+fn repeated_declarations_share_a_binding() {
+    let (_program, tree) = analyze("
+        var a = 1;
+        function f() {}
+        var a, f;
+    ");
+
+    let a = &tree.bindings[tree.binding(0, "a").unwrap()];
+    let f = &tree.bindings[tree.binding(0, "f").unwrap()];
+
+    assert_eq!(tree.bindings.len(), 2);
+    assert_eq!(a.declarations.len(), 2);
+    assert_eq!(f.declarations.len(), 2);
+    assert_eq!(f.kind, BindingKind::Function);
+}
+
+#[test]
+fn block_functions_are_function_scoped() {
+    let (_program, tree) = analyze("
+        function f() {
+            if (true) { function g() { return 42; } }
+            return g();
+        }
+    ");
+
+    assert_eq!(names(&tree, &tree.scopes[1].bindings), vec!["g"]);
+    assert!(tree.scopes[2].bindings.is_empty());
+    assert_eq!(tree.bindings[tree.binding(1, "g").unwrap()].references.len(), 1);
+}
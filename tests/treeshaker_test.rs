@@ -0,0 +1,70 @@
+extern crate badger;
+
+use std::path::{ Path, PathBuf };
+
+use badger::bundler::ModuleGraph;
+use badger::treeshaker::{ self, Reason, Removal };
+
+fn fixture(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/shake").join(path)
+}
+
+fn shaken() -> (ModuleGraph, Vec<Removal>) {
+    let mut graph = ModuleGraph::build(&fixture("entry.js")).unwrap();
+    let report = treeshaker::shake(&mut graph);
+
+    (graph, report.removed)
+}
+
+fn removed(removals: &[Removal], file: &str) -> Vec<(Option<String>, Reason)> {
+    removals.iter()
+            .filter(|removal| removal.path.ends_with(file))
+            .map(|removal| (removal.name.clone(), removal.reason))
+            .collect()
+}
+
+#[test]
+fn remove_unused_declarations() {
+    let (_, removals) = shaken();
+
+    assert_eq!(removed(&removals, "utils.js"), vec![
+        (Some("unreferenced".to_string()), Reason::Unreferenced),
+        (Some("unused".to_string()), Reason::UnusedExport),
+        (Some("default".to_string()), Reason::UnusedExport),
+    ]);
+    assert_eq!(removed(&removals, "helpers.js"), vec![
+        (Some("other".to_string()), Reason::UnusedExport),
+        (Some("another".to_string()), Reason::UnusedExport),
+    ]);
+}
+
+#[test]
+fn remove_modules_without_side_effects() {
+    let (graph, removals) = shaken();
+
+    assert_eq!(removed(&removals, "styles.js"), vec![(None, Reason::NoSideEffects)]);
+    assert_eq!(removed(&removals, "mathlib/multiply.js"), vec![(None, Reason::NoSideEffects)]);
+    assert_eq!(removed(&removals, "polyfill.js"), vec![]);
+
+    let paths: Vec<&str> = graph.modules.iter()
+                                        .map(|module| module.path.file_name().unwrap().to_str().unwrap())
+                                        .collect();
+
+    assert_eq!(paths, vec!["entry.js", "utils.js", "index.js", "polyfill.js", "helpers.js", "add.js"]);
+    assert_eq!(graph.modules[0].dependencies, vec![
+        ("./utils".to_string(), 1),
+        ("mathlib".to_string(), 2),
+        ("./polyfill".to_string(), 3),
+    ]);
+}
+
+#[test]
+fn keep_declarations_with_side_effects() {
+    let (graph, _) = shaken();
+    let program = graph.generate_code(true).unwrap().code;
+
+    assert!(program.contains("kept=console.log('kept');"));
+    assert!(program.contains("window.polyfilled=!0;"));
+    assert!(!program.contains("unreferenced"));
+    assert!(!program.contains("multiply"));
+}